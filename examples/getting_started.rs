@@ -21,7 +21,7 @@ use std::time::Duration;
 use anyhow::{bail, Context, Result};
 use camino::{Utf8Path, Utf8PathBuf};
 use clap::Parser;
-use compact_str::{format_compact, CompactString, ToCompactString};
+use compact_str::{format_compact, CompactString};
 use tokio::{process::Command, time::sleep};
 use wick::Api;
 
@@ -158,6 +158,7 @@ async fn start_microvm(fcc: &::wick::Client) -> Result<()> {
 
     fcc.create_sync_action(::wick::models::InstanceActionInfo {
         action_type: ActionType::InstanceStart,
+        extras: Default::default(),
     })
     .await
     .context("failed to put ActionType::InstanceStart")
@@ -168,13 +169,9 @@ async fn set_network_interface(fcc: &::wick::Client, tap_name: CompactString) ->
 
     fcc.put_guest_network_interface_by_id(
         NET1_IFACE_ID,
-        ::wick::models::NetworkInterface {
-            iface_id: NET1_IFACE_ID.to_compact_string(),
-            guest_mac: Some(FC_MAC_ADDRESS.to_compact_string()),
-            host_dev_name: tap_name,
-            rx_rate_limiter: None,
-            tx_rate_limiter: None,
-        },
+        ::wick::models::NetworkInterface::builder(tap_name, NET1_IFACE_ID.parse()?)
+            .guest_mac(FC_MAC_ADDRESS.parse::<::wick::models::MacAddress>()?)
+            .build(),
     )
     .await
     .context("failed to put network network interface")
@@ -185,40 +182,31 @@ async fn set_rootfs(fcc: &::wick::Client, rootfs_path: impl AsRef<Utf8Path>) ->
 
     fcc.put_guest_drive_by_id(
         ROOTFS_DRIVE_ID,
-        ::wick::models::Drive {
-            drive_id: ROOTFS_DRIVE_ID.to_compact_string(),
-            path_on_host: Some(rootfs_path.as_ref().to_owned()),
-            is_root_device: true,
-            is_read_only: Some(false),
-            partuuid: None,
-            cache_type: None,
-            rate_limiter: None,
-            io_engine: None,
-            socket: None,
-        },
+        ::wick::models::Drive::builder(ROOTFS_DRIVE_ID.parse()?, true)
+            .path_on_host(rootfs_path.as_ref())
+            .is_read_only(false)
+            .build(),
     )
     .await
     .context("failed to put guest drive")
 }
 
 async fn set_boot_source(fcc: &::wick::Client, kernel_path: impl AsRef<Utf8Path>) -> Result<()> {
-    fcc.put_guest_boot_source(::wick::models::BootSource {
-        kernel_image_path: kernel_path.as_ref().to_owned(),
-        boot_args: Some(KERNEL_BOOT_ARGS.into()),
-        initrd_path: None,
-    })
-    .await
-    .context("failed to PUT guest boot source")
+    let boot_source = ::wick::models::BootSource::builder(kernel_path.as_ref())
+        .boot_args(KERNEL_BOOT_ARGS)
+        .build();
+    fcc.put_guest_boot_source(boot_source)
+        .await
+        .context("failed to PUT guest boot source")
 }
 
 async fn set_log_file(fcc: &::wick::Client, log_file_path: Utf8PathBuf) -> Result<()> {
-    let logger = ::wick::models::Logger {
-        level: Some(::wick::models::logger::Level::Debug),
-        log_path: Some(log_file_path),
-        show_level: Some(true),
-        show_log_origin: Some(true),
-        module: None,
-    };
+    let logger = ::wick::models::Logger::builder()
+        .level(::wick::models::logger::Level::Debug)
+        .log_path(log_file_path)
+        .show_level(true)
+        .show_log_origin(true)
+        .build();
     fcc.put_logger(logger).await.context("failed to PUT logger")
 }
 
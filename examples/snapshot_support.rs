@@ -35,7 +35,7 @@ use std::time::Duration;
 use anyhow::{bail, Context, Result};
 use camino::{Utf8Path, Utf8PathBuf};
 use clap::{Parser, Subcommand};
-use compact_str::{format_compact, CompactString, ToCompactString};
+use compact_str::{format_compact, CompactString};
 use tokio::{process::Command, time::sleep};
 use wick::{models, Api};
 
@@ -153,19 +153,20 @@ async fn cmd_load(
         .context("failed to set log file before VM snapshot loading")?;
 
     // Load snapshot
-    fcc.load_snapshot(models::SnapshotLoadParams {
-        track_dirty_pages: Some(false),
-        mem_file_path: None,
-        mem_backend: Some(models::MemoryBackend {
-            backend_type: models::memory_backend::BackendType::File,
-            backend_path: snapshot_path.join(format_compact!("snap_vm{id}.mem").as_str()),
-        }),
-        snapshot_path: snapshot_path.join(format_compact!("snap_vm{id}.state").as_str()),
-        resume_vm: Some(true),
-        network_overrides: None,
-    })
-    .await
-    .context("failed to load VM from snapshot")
+    let mem_backend = models::MemoryBackend::new(
+        models::memory_backend::BackendType::File,
+        snapshot_path.join(format_compact!("snap_vm{id}.mem").as_str()),
+    );
+    let params = models::SnapshotLoadParams::builder(
+        snapshot_path.join(format_compact!("snap_vm{id}.state").as_str()),
+    )
+    .track_dirty_pages(false)
+    .mem_backend(mem_backend)
+    .resume_vm(true)
+    .build();
+    fcc.load_snapshot(params)
+        .await
+        .context("failed to load VM from snapshot")
 }
 
 async fn cmd_snap(cli: Cli, fcc: ::wick::Client) -> Result<()> {
@@ -195,22 +196,26 @@ async fn create_snapshot(
     // Pause VM
     fcc.patch_vm(models::Vm {
         state: models::vm::State::Paused,
+        extras: Default::default(),
     })
     .await
     .context("failed to pause VM")?;
 
     // Create snapshot
-    fcc.create_snapshot(models::SnapshotCreateParams {
-        mem_file_path: snapshot_path.join(format_compact!("snap_vm{id}.mem").as_str()),
-        snapshot_path: snapshot_path.join(format_compact!("snap_vm{id}.state").as_str()),
-        snapshot_type: Some(models::snapshot_create_params::SnapshotType::Full),
-    })
-    .await
-    .context("failed to create VM snapshot")?;
+    let params = models::SnapshotCreateParams::builder(
+        snapshot_path.join(format_compact!("snap_vm{id}.mem").as_str()),
+        snapshot_path.join(format_compact!("snap_vm{id}.state").as_str()),
+    )
+    .snapshot_type(models::snapshot_create_params::SnapshotType::Full)
+    .build();
+    fcc.create_snapshot(params)
+        .await
+        .context("failed to create VM snapshot")?;
 
     // Resume VM
     fcc.patch_vm(models::Vm {
         state: models::vm::State::Resumed,
+        extras: Default::default(),
     })
     .await
     .context("failed to resume VM")
@@ -264,6 +269,7 @@ async fn start_microvm(fcc: &::wick::Client) -> Result<()> {
 
     fcc.create_sync_action(models::InstanceActionInfo {
         action_type: ActionType::InstanceStart,
+        extras: Default::default(),
     })
     .await
     .context("failed to put ActionType::InstanceStart")
@@ -274,13 +280,9 @@ async fn set_network_interface(fcc: &::wick::Client, tap_name: CompactString) ->
 
     fcc.put_guest_network_interface_by_id(
         NET1_IFACE_ID,
-        models::NetworkInterface {
-            iface_id: NET1_IFACE_ID.to_compact_string(),
-            guest_mac: Some(FC_MAC_ADDRESS.to_compact_string()),
-            host_dev_name: tap_name,
-            rx_rate_limiter: None,
-            tx_rate_limiter: None,
-        },
+        models::NetworkInterface::builder(tap_name, NET1_IFACE_ID.parse()?)
+            .guest_mac(FC_MAC_ADDRESS.parse::<models::MacAddress>()?)
+            .build(),
     )
     .await
     .context("failed to put network network interface")
@@ -291,30 +293,22 @@ async fn set_rootfs(fcc: &::wick::Client, rootfs_path: impl AsRef<Utf8Path>) ->
 
     fcc.put_guest_drive_by_id(
         ROOTFS_DRIVE_ID,
-        models::Drive {
-            drive_id: ROOTFS_DRIVE_ID.to_compact_string(),
-            path_on_host: Some(rootfs_path.as_ref().to_owned()),
-            is_root_device: true,
-            is_read_only: Some(false),
-            partuuid: None,
-            cache_type: None,
-            rate_limiter: None,
-            io_engine: None,
-            socket: None,
-        },
+        models::Drive::builder(ROOTFS_DRIVE_ID.parse()?, true)
+            .path_on_host(rootfs_path.as_ref())
+            .is_read_only(false)
+            .build(),
     )
     .await
     .context("failed to put guest drive")
 }
 
 async fn set_boot_source(fcc: &::wick::Client, kernel_path: impl AsRef<Utf8Path>) -> Result<()> {
-    fcc.put_guest_boot_source(models::BootSource {
-        kernel_image_path: kernel_path.as_ref().to_owned(),
-        boot_args: Some(KERNEL_BOOT_ARGS.into()),
-        initrd_path: None,
-    })
-    .await
-    .context("failed to PUT guest boot source")
+    let boot_source = models::BootSource::builder(kernel_path.as_ref())
+        .boot_args(KERNEL_BOOT_ARGS)
+        .build();
+    fcc.put_guest_boot_source(boot_source)
+        .await
+        .context("failed to PUT guest boot source")
 }
 
 async fn set_log_file(fcc: &::wick::Client, id: &str) -> Result<()> {
@@ -325,13 +319,12 @@ async fn set_log_file(fcc: &::wick::Client, id: &str) -> Result<()> {
         .context("failed to touch log file")?;
 
     // Put logger
-    let logger = models::Logger {
-        level: Some(models::logger::Level::Debug),
-        log_path: Some(log_file_path),
-        show_level: Some(true),
-        show_log_origin: Some(true),
-        module: None,
-    };
+    let logger = models::Logger::builder()
+        .level(models::logger::Level::Debug)
+        .log_path(log_file_path)
+        .show_level(true)
+        .show_log_origin(true)
+        .build();
     fcc.put_logger(logger).await.context("failed to PUT logger")
 }
 
@@ -1,18 +1,25 @@
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use compact_str::{CompactString, ToCompactString};
 use hyper::http;
 use hyper_util::client::legacy::Client as HyperClient;
 use hyper_util::rt::TokioExecutor;
 use hyperlocal::UnixConnector;
+use tokio::sync::OnceCell;
 use tracing::{instrument, Level};
 
-use crate::{api::request::Request, models, Api, Error};
+use crate::{
+    api::{request::Request, V1_14},
+    models, Api, Error,
+};
 
 #[derive(Debug, Clone)]
 pub struct Client {
     socket_path: PathBuf,
     client: HyperClient<UnixConnector, String>,
+    /// The version of the VMM, fetched the first time a version-gated endpoint is called.
+    version: Arc<OnceCell<models::FirecrackerVersion>>,
 }
 
 impl Client {
@@ -61,6 +68,27 @@ impl Client {
         Self {
             socket_path: socket_path.as_ref().to_path_buf(),
             client,
+            version: Default::default(),
+        }
+    }
+
+    /// Fail with [`Error::Unsupported`] if the VMM is older than `since`, so that calls to
+    /// endpoints it lacks fail early with a descriptive error instead of an HTTP 400.
+    ///
+    /// A VMM whose version cannot be parsed is assumed to support the endpoint.
+    async fn require(&self, endpoint: &'static str, since: &'static str) -> Result<(), Error> {
+        let version = self
+            .version
+            .get_or_try_init(|| self.get_firecracker_version())
+            .await?;
+        let required = models::FirecrackerVersion::new(since).release();
+        match (version.release(), required) {
+            (Some(found), Some(required)) if found < required => Err(Error::Unsupported {
+                endpoint,
+                required: since,
+                found: compact_str::format_compact!("v{}", version.firecracker_version),
+            }),
+            _ => Ok(()),
         }
     }
 }
@@ -109,6 +137,19 @@ impl Api for Client {
         req.execute(&self.socket_path, &self.client).await
     }
 
+    #[instrument(level = Level::DEBUG, skip(self))]
+    async fn describe_balloon_hinting(&self) -> Result<models::BalloonHintingStatus, Error> {
+        const PATH: &str = "/balloon/hinting/status";
+
+        self.require(PATH, V1_14).await?;
+
+        let path = PATH.to_compact_string();
+
+        let req = Request::new(http::Method::GET, path);
+
+        req.execute(&self.socket_path, &self.client).await
+    }
+
     #[instrument(level = Level::DEBUG, skip(self))]
     async fn describe_balloon_stats(&self) -> Result<models::BalloonStats, Error> {
         const PATH: &str = "/balloon/statistics";
@@ -164,6 +205,19 @@ impl Api for Client {
         req.execute(&self.socket_path, &self.client).await
     }
 
+    #[instrument(level = Level::DEBUG, skip(self))]
+    async fn get_memory_hotplug(&self) -> Result<models::MemoryHotplugStatus, Error> {
+        const PATH: &str = "/hotplug/memory";
+
+        self.require(PATH, V1_14).await?;
+
+        let path = PATH.to_compact_string();
+
+        let req = Request::new(http::Method::GET, path);
+
+        req.execute(&self.socket_path, &self.client).await
+    }
+
     #[instrument(level = Level::DEBUG, skip(self))]
     async fn get_mmds(&self) -> Result<serde_json::Value, Error> {
         const PATH: &str = "/mmds";
@@ -201,6 +255,38 @@ impl Api for Client {
         req.execute(&self.socket_path, &self.client).await
     }
 
+    #[instrument(level = Level::DEBUG, skip(self))]
+    async fn patch_balloon_hinting_start(
+        &self,
+        start: models::BalloonStartHinting,
+    ) -> Result<(), Error> {
+        const PATH: &str = "/balloon/hinting/start";
+
+        self.require(PATH, V1_14).await?;
+
+        let path = PATH.to_compact_string();
+
+        let mut req = Request::new(http::Method::PATCH, path);
+        req = req.with_body(start)?;
+        req = req.returns_nothing();
+
+        req.execute(&self.socket_path, &self.client).await
+    }
+
+    #[instrument(level = Level::DEBUG, skip(self))]
+    async fn patch_balloon_hinting_stop(&self) -> Result<(), Error> {
+        const PATH: &str = "/balloon/hinting/stop";
+
+        self.require(PATH, V1_14).await?;
+
+        let path = PATH.to_compact_string();
+
+        let mut req = Request::new(http::Method::PATCH, path);
+        req = req.returns_nothing();
+
+        req.execute(&self.socket_path, &self.client).await
+    }
+
     #[instrument(level = Level::DEBUG, skip(self))]
     async fn patch_balloon_stats_interval(
         &self,
@@ -271,6 +357,24 @@ impl Api for Client {
         req.execute(&self.socket_path, &self.client).await
     }
 
+    #[instrument(level = Level::DEBUG, skip(self))]
+    async fn patch_memory_hotplug(
+        &self,
+        size_update: models::MemoryHotplugSizeUpdate,
+    ) -> Result<(), Error> {
+        const PATH: &str = "/hotplug/memory";
+
+        self.require(PATH, V1_14).await?;
+
+        let path = PATH.to_compact_string();
+
+        let mut req = Request::new(http::Method::PATCH, path);
+        req = req.with_body(size_update)?;
+        req = req.returns_nothing();
+
+        req.execute(&self.socket_path, &self.client).await
+    }
+
     #[instrument(level = Level::DEBUG, skip(self))]
     async fn patch_mmds(&self, body: Option<serde_json::Value>) -> Result<(), Error> {
         const PATH: &str = "/mmds";
@@ -432,6 +536,21 @@ impl Api for Client {
         req.execute(&self.socket_path, &self.client).await
     }
 
+    #[instrument(level = Level::DEBUG, skip(self))]
+    async fn put_memory_hotplug(&self, config: models::MemoryHotplugConfig) -> Result<(), Error> {
+        const PATH: &str = "/hotplug/memory";
+
+        self.require(PATH, V1_14).await?;
+
+        let path = PATH.to_compact_string();
+
+        let mut req = Request::new(http::Method::PUT, path);
+        req = req.with_body(config)?;
+        req = req.returns_nothing();
+
+        req.execute(&self.socket_path, &self.client).await
+    }
+
     #[instrument(level = Level::DEBUG, skip(self))]
     async fn put_metrics(&self, metrics: models::Metrics) -> Result<(), Error> {
         const PATH: &str = "/metrics";
@@ -470,4 +589,36 @@ impl Api for Client {
 
         req.execute(&self.socket_path, &self.client).await
     }
+
+    #[instrument(level = Level::DEBUG, skip(self))]
+    async fn put_pmem_by_id(&self, pmem_id: &str, body: models::Pmem) -> Result<(), Error> {
+        const PATH: &str = "/pmem/";
+
+        self.require(PATH, V1_14).await?;
+
+        let mut path = CompactString::with_capacity(PATH.len() + pmem_id.len());
+        path.push_str(PATH);
+        path.push_str(pmem_id);
+
+        let mut req = Request::new(http::Method::PUT, path);
+        req = req.with_body(body)?;
+        req = req.returns_nothing();
+
+        req.execute(&self.socket_path, &self.client).await
+    }
+
+    #[instrument(level = Level::DEBUG, skip(self))]
+    async fn put_serial(&self, serial: models::Serial) -> Result<(), Error> {
+        const PATH: &str = "/serial";
+
+        self.require(PATH, V1_14).await?;
+
+        let path = PATH.to_compact_string();
+
+        let mut req = Request::new(http::Method::PUT, path);
+        req = req.with_body(serial)?;
+        req = req.returns_nothing();
+
+        req.execute(&self.socket_path, &self.client).await
+    }
 }
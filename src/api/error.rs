@@ -1,5 +1,6 @@
 use std::fmt::{self, Debug};
 
+use compact_str::CompactString;
 use hyper::http;
 
 #[derive(Debug, thiserror::Error)]
@@ -18,6 +19,17 @@ pub enum Error {
 
     #[error("(de)serialization error")]
     Serde(#[source] ::serde_json::Error),
+
+    #[error("{endpoint} requires Firecracker v{required} or later, found {found}")]
+    Unsupported {
+        endpoint: &'static str,
+        required: &'static str,
+        /// The version of the VMM, e.g. `v1.13.1`, or what was found instead.
+        found: CompactString,
+    },
+
+    #[error("{endpoint} is not implemented by this API client")]
+    NotImplemented { endpoint: &'static str },
 }
 
 #[derive(::thiserror::Error)]
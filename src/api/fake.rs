@@ -0,0 +1,153 @@
+//! A scripted [`Api`] for unit tests.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::{models, Api, Error};
+
+/// A request made to a [`FakeApi`].
+#[derive(Clone, Debug, PartialEq)]
+struct Call {
+    /// Name of the [`Api`] method.
+    method: &'static str,
+    /// Arguments of the request, e.g. the device ID and the body.
+    args: Vec<Value>,
+}
+
+#[derive(Clone, Debug)]
+enum Response {
+    Ok(Value),
+    Err,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    responses: HashMap<&'static str, VecDeque<Response>>,
+    calls: Vec<Call>,
+}
+
+/// An [`Api`] that records every request and replies with the responses scripted for its
+/// method, in order; the last response of a method is repeated. Methods without responses
+/// reply with `null`, i.e. succeed if they return nothing.
+///
+/// Clones share their script and record.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct FakeApi(Arc<Mutex<State>>);
+
+impl FakeApi {
+    #[inline]
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a successful response to `method`.
+    pub(crate) fn respond(&self, method: &'static str, response: impl Serialize) -> &Self {
+        let response = serde_json::to_value(response).expect("response serializes");
+        self.script(method, Response::Ok(response))
+    }
+
+    /// Queue a failure of `method`.
+    pub(crate) fn fail(&self, method: &'static str) -> &Self {
+        self.script(method, Response::Err)
+    }
+
+    /// The arguments of every request made to `method` so far.
+    pub(crate) fn calls_to(&self, method: &str) -> Vec<Vec<Value>> {
+        let state = self.state();
+        let calls = state.calls.iter().filter(|call| call.method == method);
+        calls.map(|call| call.args.clone()).collect()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.0.lock().expect("fake API lock poisoned")
+    }
+
+    fn script(&self, method: &'static str, response: Response) -> &Self {
+        let mut state = self.state();
+        state
+            .responses
+            .entry(method)
+            .or_default()
+            .push_back(response);
+        self
+    }
+
+    fn reply<T: DeserializeOwned>(
+        &self,
+        method: &'static str,
+        args: Vec<Value>,
+    ) -> Result<T, Error> {
+        let mut state = self.state();
+        state.calls.push(Call { method, args });
+        let queue = state.responses.entry(method).or_default();
+        let response = match queue.len() {
+            0 => Response::Ok(Value::Null),
+            1 => queue[0].clone(),
+            _ => queue.pop_front().expect("queue is not empty"),
+        };
+        match response {
+            Response::Ok(value) => serde_json::from_value(value).map_err(Error::Serde),
+            Response::Err => Err(Error::Serde(serde::de::Error::custom(format!(
+                "scripted failure of {method}"
+            )))),
+        }
+    }
+}
+
+macro_rules! fake_api {
+    ($($method:ident($($arg:ident: $ty:ty),*) -> $ret:ty;)*) => {
+        impl Api for FakeApi {
+            $(
+                async fn $method(&self $(, $arg: $ty)*) -> Result<$ret, Error> {
+                    let args = vec![$(serde_json::to_value(&$arg).expect("argument serializes")),*];
+                    self.reply(stringify!($method), args)
+                }
+            )*
+        }
+    };
+}
+
+fake_api! {
+    create_snapshot(body: models::SnapshotCreateParams) -> ();
+    create_sync_action(info: models::InstanceActionInfo) -> ();
+    describe_balloon_config() -> models::Balloon;
+    describe_balloon_hinting() -> models::BalloonHintingStatus;
+    describe_balloon_stats() -> models::BalloonStats;
+    describe_instance() -> models::InstanceInfo;
+    get_export_vm_config() -> models::FullVmConfiguration;
+    get_firecracker_version() -> models::FirecrackerVersion;
+    get_machine_configuration() -> models::MachineConfiguration;
+    get_memory_hotplug() -> models::MemoryHotplugStatus;
+    get_mmds() -> Value;
+    load_snapshot(body: models::SnapshotLoadParams) -> ();
+    patch_balloon(body: models::BalloonUpdate) -> ();
+    patch_balloon_hinting_start(body: models::BalloonStartHinting) -> ();
+    patch_balloon_hinting_stop() -> ();
+    patch_balloon_stats_interval(body: models::BalloonStatsUpdate) -> ();
+    patch_guest_drive_by_id(drive_id: &str, body: models::PartialDrive) -> ();
+    patch_guest_network_interface_by_id(iface_id: &str, body: models::PartialNetworkInterface) -> ();
+    patch_machine_configuration(body: Option<models::MachineConfiguration>) -> ();
+    patch_memory_hotplug(body: models::MemoryHotplugSizeUpdate) -> ();
+    patch_mmds(body: Option<Value>) -> ();
+    patch_vm(body: models::Vm) -> ();
+    put_balloon(body: models::Balloon) -> ();
+    put_cpu_configuration(body: Option<models::CpuConfig>) -> ();
+    put_entropy_device(body: models::EntropyDevice) -> ();
+    put_guest_boot_source(body: models::BootSource) -> ();
+    put_guest_drive_by_id(drive_id: &str, body: models::Drive) -> ();
+    put_guest_network_interface_by_id(iface_id: &str, body: models::NetworkInterface) -> ();
+    put_guest_vsock(body: models::Vsock) -> ();
+    put_logger(body: models::Logger) -> ();
+    put_machine_configuration(body: Option<models::MachineConfiguration>) -> ();
+    put_memory_hotplug(body: models::MemoryHotplugConfig) -> ();
+    put_metrics(body: models::Metrics) -> ();
+    put_mmds(body: Option<Value>) -> ();
+    put_mmds_config(body: models::MmdsConfig) -> ();
+    put_pmem_by_id(pmem_id: &str, body: models::Pmem) -> ();
+    put_serial(body: models::Serial) -> ();
+}
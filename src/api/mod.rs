@@ -1,11 +1,24 @@
 pub mod client;
 pub mod error;
+#[cfg(test)]
+pub(crate) mod fake;
 mod request;
 
 use std::future::Future;
 
 use crate::{api::error::Error, models};
 
+/// The Firecracker release that introduced the endpoints gated on it.
+pub(crate) const V1_14: &str = "1.14.0";
+
+/// The default implementation of an endpoint added to [`Api`] after its first release, so that
+/// implementors predating it keep compiling.
+fn not_implemented<T: Send>(
+    endpoint: &'static str,
+) -> impl Future<Output = Result<T, Error>> + Send {
+    std::future::ready(Err(Error::NotImplemented { endpoint }))
+}
+
 pub trait Api: Send + Sync {
     fn create_snapshot(
         &self,
@@ -21,6 +34,14 @@ pub trait Api: Send + Sync {
         &self,
     ) -> impl Future<Output = Result<models::Balloon, Error>> + Send;
 
+    /// Requires Firecracker v1.14.0 or later; the default implementation fails with
+    /// [`Error::NotImplemented`].
+    fn describe_balloon_hinting(
+        &self,
+    ) -> impl Future<Output = Result<models::BalloonHintingStatus, Error>> + Send {
+        not_implemented("/balloon/hinting/status")
+    }
+
     fn describe_balloon_stats(
         &self,
     ) -> impl Future<Output = Result<models::BalloonStats, Error>> + Send;
@@ -40,6 +61,14 @@ pub trait Api: Send + Sync {
         &self,
     ) -> impl Future<Output = Result<models::MachineConfiguration, Error>> + Send;
 
+    /// Requires Firecracker v1.14.0 or later; the default implementation fails with
+    /// [`Error::NotImplemented`].
+    fn get_memory_hotplug(
+        &self,
+    ) -> impl Future<Output = Result<models::MemoryHotplugStatus, Error>> + Send {
+        not_implemented("/hotplug/memory")
+    }
+
     fn get_mmds(&self) -> impl Future<Output = Result<serde_json::Value, Error>> + Send;
 
     fn load_snapshot(
@@ -52,6 +81,22 @@ pub trait Api: Send + Sync {
         body: models::BalloonUpdate,
     ) -> impl Future<Output = Result<(), Error>> + Send;
 
+    /// Requires Firecracker v1.14.0 or later; the default implementation fails with
+    /// [`Error::NotImplemented`].
+    fn patch_balloon_hinting_start(
+        &self,
+        body: models::BalloonStartHinting,
+    ) -> impl Future<Output = Result<(), Error>> + Send {
+        let _ = body;
+        not_implemented("/balloon/hinting/start")
+    }
+
+    /// Requires Firecracker v1.14.0 or later; the default implementation fails with
+    /// [`Error::NotImplemented`].
+    fn patch_balloon_hinting_stop(&self) -> impl Future<Output = Result<(), Error>> + Send {
+        not_implemented("/balloon/hinting/stop")
+    }
+
     fn patch_balloon_stats_interval(
         &self,
         body: models::BalloonStatsUpdate,
@@ -74,6 +119,16 @@ pub trait Api: Send + Sync {
         body: Option<models::MachineConfiguration>,
     ) -> impl Future<Output = Result<(), Error>> + Send;
 
+    /// Requires Firecracker v1.14.0 or later; the default implementation fails with
+    /// [`Error::NotImplemented`].
+    fn patch_memory_hotplug(
+        &self,
+        body: models::MemoryHotplugSizeUpdate,
+    ) -> impl Future<Output = Result<(), Error>> + Send {
+        let _ = body;
+        not_implemented("/hotplug/memory")
+    }
+
     fn patch_mmds(
         &self,
         body: Option<serde_json::Value>,
@@ -122,6 +177,16 @@ pub trait Api: Send + Sync {
         body: Option<models::MachineConfiguration>,
     ) -> impl Future<Output = Result<(), Error>> + Send;
 
+    /// Requires Firecracker v1.14.0 or later; the default implementation fails with
+    /// [`Error::NotImplemented`].
+    fn put_memory_hotplug(
+        &self,
+        body: models::MemoryHotplugConfig,
+    ) -> impl Future<Output = Result<(), Error>> + Send {
+        let _ = body;
+        not_implemented("/hotplug/memory")
+    }
+
     fn put_metrics(&self, body: models::Metrics) -> impl Future<Output = Result<(), Error>> + Send;
 
     fn put_mmds(
@@ -133,4 +198,22 @@ pub trait Api: Send + Sync {
         &self,
         body: models::MmdsConfig,
     ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Requires Firecracker v1.14.0 or later; the default implementation fails with
+    /// [`Error::NotImplemented`].
+    fn put_pmem_by_id(
+        &self,
+        pmem_id: &str,
+        body: models::Pmem,
+    ) -> impl Future<Output = Result<(), Error>> + Send {
+        let _ = (pmem_id, body);
+        not_implemented("/pmem/")
+    }
+
+    /// Requires Firecracker v1.14.0 or later; the default implementation fails with
+    /// [`Error::NotImplemented`].
+    fn put_serial(&self, body: models::Serial) -> impl Future<Output = Result<(), Error>> + Send {
+        let _ = body;
+        not_implemented("/serial")
+    }
 }
@@ -0,0 +1,529 @@
+//! A host-wide arbiter that distributes balloon inflation across many microVMs when the host
+//! runs low on memory, and gives memory back once the pressure is gone.
+//!
+//! Host memory readings come from a [`HostMemorySource`]; [`ProcHostMemory`] reads them from
+//! `/proc/meminfo` and `/proc/pressure/memory`.
+
+use std::{future::Future, io, time::Duration};
+
+use camino::Utf8PathBuf;
+use compact_str::CompactString;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{info, instrument, warn, Level};
+
+use crate::{
+    balloon::controller::available_mib,
+    models::{self, BalloonUpdate, MiB},
+    Api,
+};
+
+/// Memory state of the host, in MiB.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct HostMemory {
+    /// `MemTotal` of `/proc/meminfo`.
+    pub total_mib: i64,
+    /// `MemAvailable` of `/proc/meminfo`.
+    pub available_mib: i64,
+    /// Memory pressure stall information, if the kernel exposes it.
+    pub pressure: Option<MemoryPressure>,
+}
+
+/// Memory pressure stall information (PSI); the share (%) of time in which some (or all)
+/// non-idle tasks were stalled on memory, averaged over the last 10 seconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MemoryPressure {
+    pub some_avg10: f64,
+    pub full_avg10: f64,
+}
+
+/// A source of [`HostMemory`] readings.
+pub trait HostMemorySource: Send + Sync {
+    fn read(&self) -> impl Future<Output = io::Result<HostMemory>> + Send;
+}
+
+/// Reads [`HostMemory`] from procfs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProcHostMemory {
+    pub meminfo_path: Utf8PathBuf,
+    /// Readings carry no [`MemoryPressure`] if this file does not exist.
+    pub pressure_path: Utf8PathBuf,
+}
+
+impl Default for ProcHostMemory {
+    fn default() -> Self {
+        Self {
+            meminfo_path: "/proc/meminfo".into(),
+            pressure_path: "/proc/pressure/memory".into(),
+        }
+    }
+}
+
+impl HostMemorySource for ProcHostMemory {
+    async fn read(&self) -> io::Result<HostMemory> {
+        let meminfo = ::tokio::fs::read_to_string(&self.meminfo_path).await?;
+        let mut host = parse_meminfo(&meminfo)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed /proc/meminfo"))?;
+        host.pressure = match ::tokio::fs::read_to_string(&self.pressure_path).await {
+            Ok(psi) => parse_pressure(&psi),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err),
+        };
+        Ok(host)
+    }
+}
+
+/// Parse the contents of `/proc/meminfo`; the returned [`HostMemory`] carries no pressure
+/// information.
+pub fn parse_meminfo(meminfo: &str) -> Option<HostMemory> {
+    let field = |name: &str| -> Option<i64> {
+        meminfo.lines().find_map(|line| {
+            let value = line.strip_prefix(name)?.strip_prefix(':')?;
+            let kib: i64 = value.trim().trim_end_matches("kB").trim().parse().ok()?;
+            Some(kib / 1024)
+        })
+    };
+    Some(HostMemory {
+        total_mib: field("MemTotal")?,
+        available_mib: field("MemAvailable")?,
+        pressure: None,
+    })
+}
+
+/// Parse the contents of `/proc/pressure/memory`.
+pub fn parse_pressure(psi: &str) -> Option<MemoryPressure> {
+    let avg10 = |kind: &str| -> Option<f64> {
+        psi.lines().find_map(|line| {
+            let fields = line.strip_prefix(kind)?.strip_prefix(' ')?;
+            fields
+                .split_whitespace()
+                .find_map(|kv| kv.strip_prefix("avg10=")?.parse().ok())
+        })
+    };
+    Some(MemoryPressure {
+        some_avg10: avg10("some")?,
+        full_avg10: avg10("full").unwrap_or(0.0),
+    })
+}
+
+/// When and how aggressively the [`Arbiter`] reclaims memory. All amounts are in MiB.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ArbiterPolicy {
+    /// Reclaim memory from microVMs while the host has less than this available.
+    pub low_watermark_mib: i64,
+    /// Give memory back to microVMs while the host has more than this available.
+    pub high_watermark_mib: i64,
+    /// Reclaim at least `max_step_mib` while `some` memory pressure exceeds this (%).
+    pub pressure_threshold: f64,
+    /// Maximum amount reclaimed from, or returned to, all microVMs in a single step.
+    pub max_step_mib: i64,
+    /// Amount of available memory left to every guest that reports balloon statistics.
+    pub guest_reserve_mib: i64,
+    /// Interval between arbiter steps.
+    pub poll_interval: Duration,
+}
+
+impl Default for ArbiterPolicy {
+    fn default() -> Self {
+        Self {
+            low_watermark_mib: 1024,
+            high_watermark_mib: 2048,
+            pressure_threshold: 10.0,
+            max_step_mib: 1024,
+            guest_reserve_mib: 128,
+            poll_interval: Duration::from_secs(2),
+        }
+    }
+}
+
+/// The arbiter's view of a single microVM during a step.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VmView {
+    pub id: CompactString,
+    /// Lower priority microVMs are reclaimed from first, and given memory back last.
+    pub priority: u32,
+    /// The guest is never left with less memory than this.
+    pub floor_mib: i64,
+    pub mem_size_mib: i64,
+    /// Current target size of the balloon.
+    pub balloon_mib: i64,
+    /// Whether the balloon deflates when the guest runs out of memory.
+    pub deflate_on_oom: bool,
+    /// Memory available to the guest's applications, if reported.
+    pub available_mib: Option<i64>,
+}
+
+/// A balloon resize planned by the [`Arbiter`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Allocation {
+    pub id: CompactString,
+    pub from_mib: i64,
+    pub to_mib: i64,
+}
+
+impl ArbiterPolicy {
+    /// Plan the balloon resizes needed to bring `host` back within the watermarks.
+    ///
+    /// Inflation is taken from microVMs in increasing priority order; at equal priority, from
+    /// those whose balloon deflates on OOM first, since they can recover from over-reclamation.
+    /// Guests keep [`guest_reserve_mib`](Self::guest_reserve_mib) of available memory, whether
+    /// or not their balloon deflates on OOM, and guests that report no statistics are only
+    /// reclaimed from if they deflate on OOM. Memory is given back in the reverse order.
+    pub fn plan(&self, host: &HostMemory, vms: &[VmView]) -> Vec<Allocation> {
+        let under_pressure = host
+            .pressure
+            .is_some_and(|p| p.some_avg10 > self.pressure_threshold);
+
+        let mut deficit = self.low_watermark_mib - host.available_mib;
+        if under_pressure {
+            deficit = deficit.max(self.max_step_mib);
+        }
+        let surplus = host.available_mib - self.high_watermark_mib;
+
+        let mut order: Vec<&VmView> = vms.iter().collect();
+        order.sort_by_key(|vm| (vm.priority, !vm.deflate_on_oom));
+
+        let mut plan = Vec::new();
+        if deficit > 0 {
+            let mut remaining = deficit.min(self.max_step_mib);
+            for vm in order {
+                if remaining <= 0 {
+                    break;
+                }
+                let by_floor = vm.mem_size_mib - vm.floor_mib - vm.balloon_mib;
+                let by_usage = match (vm.available_mib, vm.deflate_on_oom) {
+                    (Some(available), _) => available - self.guest_reserve_mib,
+                    (None, true) => by_floor,
+                    (None, false) => 0,
+                };
+                let take = by_floor.min(by_usage).min(remaining);
+                if take > 0 {
+                    remaining -= take;
+                    plan.push(Allocation {
+                        id: vm.id.clone(),
+                        from_mib: vm.balloon_mib,
+                        to_mib: vm.balloon_mib + take,
+                    });
+                }
+            }
+        } else if surplus > 0 && !under_pressure {
+            let mut remaining = surplus.min(self.max_step_mib);
+            for vm in order.into_iter().rev() {
+                if remaining <= 0 {
+                    break;
+                }
+                let give = vm.balloon_mib.min(remaining);
+                if give > 0 {
+                    remaining -= give;
+                    plan.push(Allocation {
+                        id: vm.id.clone(),
+                        from_mib: vm.balloon_mib,
+                        to_mib: vm.balloon_mib - give,
+                    });
+                }
+            }
+        }
+        plan
+    }
+}
+
+/// A microVM managed by an [`Arbiter`].
+#[derive(Debug)]
+struct ManagedVm<A> {
+    id: CompactString,
+    api: A,
+    priority: u32,
+    floor_mib: i64,
+    mem_size_mib: i64,
+}
+
+/// Distributes balloon inflation across all managed microVMs according to host memory
+/// pressure.
+#[derive(Debug)]
+pub struct Arbiter<A, S> {
+    source: S,
+    policy: ArbiterPolicy,
+    vms: Vec<ManagedVm<A>>,
+}
+
+impl<A: Api, S: HostMemorySource> Arbiter<A, S> {
+    #[inline]
+    pub fn new(source: S, policy: ArbiterPolicy) -> Self {
+        Self {
+            source,
+            policy,
+            vms: Vec::new(),
+        }
+    }
+
+    /// Start managing the microVM behind `api`, which must have a balloon device; a microVM
+    /// already managed under the same `id` is replaced.
+    ///
+    /// Its guest is never left with less than `floor_mib` of memory.
+    pub async fn add_vm(
+        &mut self,
+        id: impl Into<CompactString>,
+        api: A,
+        priority: u32,
+        floor_mib: i64,
+    ) -> Result<(), crate::Error> {
+        let id = id.into();
+        let machine_config = api.get_machine_configuration().await?;
+        self.remove_vm(&id);
+        self.vms.push(ManagedVm {
+            id,
+            api,
+            priority,
+            floor_mib,
+            mem_size_mib: machine_config.mem_size_mib.into(),
+        });
+        Ok(())
+    }
+
+    /// Stop managing the microVM identified by `id`, returning its [`Api`].
+    pub fn remove_vm(&mut self, id: &str) -> Option<A> {
+        let idx = self.vms.iter().position(|vm| vm.id == id)?;
+        Some(self.vms.swap_remove(idx).api)
+    }
+
+    /// Read the host's memory state, plan and apply balloon resizes.
+    ///
+    /// MicroVMs whose balloon cannot be queried or resized are skipped (and logged), so that a
+    /// single exited VMM does not stall the whole fleet. Returns the allocations that were
+    /// applied successfully.
+    #[instrument(level = Level::DEBUG, skip(self), fields(vms = self.vms.len()))]
+    pub async fn step(&mut self) -> io::Result<Vec<Allocation>> {
+        let host = self.source.read().await?;
+
+        let mut views = Vec::with_capacity(self.vms.len());
+        for vm in &self.vms {
+            match view(vm).await {
+                Ok(view) => views.push(view),
+                Err(err) => warn!(id = %vm.id, error = %err, "failed to query balloon"),
+            }
+        }
+
+        let mut applied = Vec::new();
+        for allocation in self.policy.plan(&host, &views) {
+            let Some(vm) = self.vms.iter().find(|vm| vm.id == allocation.id) else {
+                continue;
+            };
+            let to_mib = u32::try_from(allocation.to_mib.max(0)).unwrap_or(u32::MAX);
+            let update = BalloonUpdate::new(MiB(to_mib));
+            match vm.api.patch_balloon(update).await {
+                Ok(()) => {
+                    info!(?allocation, "resized balloon");
+                    applied.push(allocation);
+                }
+                Err(err) => warn!(id = %vm.id, error = %err, "failed to resize balloon"),
+            }
+        }
+        Ok(applied)
+    }
+
+    /// Step at the policy's poll interval, until reading the host's memory state fails.
+    pub async fn run(mut self) -> io::Error {
+        let mut ticks = interval(self.policy.poll_interval);
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticks.tick().await;
+            if let Err(err) = self.step().await {
+                return err;
+            }
+        }
+    }
+}
+
+async fn view<A: Api>(vm: &ManagedVm<A>) -> Result<VmView, crate::Error> {
+    let balloon: models::Balloon = vm.api.describe_balloon_config().await?;
+    // Statistics are optional; a guest without them is treated conservatively.
+    let stats = vm.api.describe_balloon_stats().await.ok();
+    Ok(VmView {
+        id: vm.id.clone(),
+        priority: vm.priority,
+        floor_mib: vm.floor_mib,
+        mem_size_mib: vm.mem_size_mib,
+        balloon_mib: stats
+            .as_ref()
+            .map(|s| s.target_mib.into())
+            .unwrap_or(balloon.amount_mib.into()),
+        deflate_on_oom: balloon.deflate_on_oom,
+        available_mib: stats.as_ref().and_then(available_mib),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MEMINFO: &str = "\
+MemTotal:       16318480 kB
+MemFree:         1234567 kB
+MemAvailable:    8159240 kB
+Buffers:          345678 kB
+Cached:          5432100 kB
+HugePages_Total:       0
+Hugepagesize:       2048 kB
+";
+
+    const PSI: &str = "\
+some avg10=12.50 avg60=3.00 avg300=1.00 total=123456
+full avg10=4.25 avg60=1.00 avg300=0.50 total=65432
+";
+
+    #[test]
+    fn parses_meminfo() {
+        assert_eq!(
+            parse_meminfo(MEMINFO),
+            Some(HostMemory {
+                total_mib: 15936,
+                available_mib: 7968,
+                pressure: None,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_meminfo() {
+        let without_available = MEMINFO.replace("MemAvailable", "MemAvail");
+        assert_eq!(parse_meminfo(&without_available), None);
+        let garbled = MEMINFO.replace("16318480", "16318x80");
+        assert_eq!(parse_meminfo(&garbled), None);
+        assert_eq!(parse_meminfo(""), None);
+    }
+
+    #[test]
+    fn parses_pressure() {
+        assert_eq!(
+            parse_pressure(PSI),
+            Some(MemoryPressure {
+                some_avg10: 12.5,
+                full_avg10: 4.25,
+            })
+        );
+        // Without a `full` line, full pressure is taken to be zero.
+        let some_only = PSI.lines().next().unwrap();
+        assert_eq!(
+            parse_pressure(some_only),
+            Some(MemoryPressure {
+                some_avg10: 12.5,
+                full_avg10: 0.0,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_pressure() {
+        assert_eq!(parse_pressure(""), None);
+        assert_eq!(parse_pressure(&PSI.replace("some", "none")), None);
+        assert_eq!(
+            parse_pressure(&PSI.replace("avg10=12.50", "avg10=lots")),
+            None
+        );
+    }
+
+    fn host(available_mib: i64, some_avg10: Option<f64>) -> HostMemory {
+        HostMemory {
+            total_mib: 16384,
+            available_mib,
+            pressure: some_avg10.map(|some_avg10| MemoryPressure {
+                some_avg10,
+                full_avg10: 0.0,
+            }),
+        }
+    }
+
+    fn vm(id: &str, priority: u32, deflate_on_oom: bool, balloon_mib: i64) -> VmView {
+        VmView {
+            id: id.into(),
+            priority,
+            floor_mib: 512,
+            mem_size_mib: 4096,
+            balloon_mib,
+            deflate_on_oom,
+            available_mib: Some(2048),
+        }
+    }
+
+    fn allocation(id: &str, from_mib: i64, to_mib: i64) -> Allocation {
+        Allocation {
+            id: id.into(),
+            from_mib,
+            to_mib,
+        }
+    }
+
+    #[test]
+    fn leaves_balloons_alone_without_pressure() {
+        let policy = ArbiterPolicy::default();
+        let vms = [vm("a", 0, true, 256), vm("b", 1, false, 0)];
+        // Between the watermarks.
+        assert_eq!(policy.plan(&host(1500, None), &vms), []);
+        // Some pressure, but below the threshold.
+        assert_eq!(policy.plan(&host(1500, Some(5.0)), &vms), []);
+    }
+
+    #[test]
+    fn reclaims_the_deficit_in_priority_order() {
+        let policy = ArbiterPolicy::default();
+        let vms = [vm("high", 1, true, 0), vm("low", 0, false, 0)];
+        // 300 MiB short of the low watermark.
+        assert_eq!(
+            policy.plan(&host(724, None), &vms),
+            [allocation("low", 0, 300)]
+        );
+        // A larger deficit is reclaimed one step at a time...
+        assert_eq!(
+            policy.plan(&host(-2000, None), &vms),
+            [allocation("low", 0, 1024)]
+        );
+        // ...and spills over to higher priorities once a guest reaches its floor.
+        let vms = [vm("high", 1, true, 0), vm("low", 0, false, 3000)];
+        assert_eq!(
+            policy.plan(&host(24, None), &vms),
+            [allocation("low", 3000, 3584), allocation("high", 0, 416)]
+        );
+    }
+
+    #[test]
+    fn reclaims_a_full_step_under_pressure() {
+        let policy = ArbiterPolicy::default();
+        let vms = [vm("a", 0, true, 0)];
+        assert_eq!(
+            policy.plan(&host(4096, Some(20.0)), &vms),
+            [allocation("a", 0, 1024)]
+        );
+    }
+
+    #[test]
+    fn keeps_the_guest_reserve_even_if_the_balloon_deflates_on_oom() {
+        let policy = ArbiterPolicy::default();
+        let mut oom = vm("oom", 0, true, 0);
+        oom.available_mib = Some(200);
+        let mut silent = vm("silent", 0, false, 0);
+        silent.available_mib = None;
+        let mut silent_oom = vm("silent_oom", 1, true, 0);
+        silent_oom.available_mib = None;
+
+        // Reclaiming from `oom` stops at the reserve; `silent` reports no statistics and cannot
+        // recover from over-reclamation, so it is skipped.
+        assert_eq!(
+            policy.plan(&host(724, None), &[oom, silent, silent_oom]),
+            [allocation("oom", 0, 72), allocation("silent_oom", 0, 228)]
+        );
+    }
+
+    #[test]
+    fn gives_memory_back_in_reverse_priority_order() {
+        let policy = ArbiterPolicy::default();
+        let vms = [vm("low", 0, false, 300), vm("high", 1, false, 300)];
+        assert_eq!(
+            policy.plan(&host(2548, None), &vms),
+            [allocation("high", 300, 0), allocation("low", 300, 100)]
+        );
+        // Under pressure, memory is reclaimed instead.
+        assert_eq!(
+            policy.plan(&host(2548, Some(20.0)), &vms),
+            [allocation("low", 300, 1324)]
+        );
+    }
+}
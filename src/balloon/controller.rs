@@ -0,0 +1,412 @@
+//! A per-microVM controller that inflates and deflates the balloon to keep a target amount of
+//! memory available in the guest.
+
+use std::time::Duration;
+
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{debug, info, instrument, Level};
+
+use crate::{balloon::bytes_to_mib, models, Api, Error};
+
+/// Sizing policy of a [`BalloonController`]. All amounts are in MiB.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BalloonPolicy {
+    /// Amount of memory that should be available to the guest's applications.
+    pub target_available_mib: i64,
+    /// Deviation from `target_available_mib` that is tolerated without resizing the balloon.
+    pub hysteresis_mib: i64,
+    /// The guest is never left with less memory than this.
+    pub min_guest_mib: i64,
+    /// The guest is never given more memory than this (or than its machine configuration).
+    pub max_guest_mib: i64,
+    /// Maximum change of the balloon size in a single step.
+    pub max_step_mib: i64,
+    /// Interval between statistics updates of the balloon device, in seconds.
+    pub stats_polling_interval_s: i32,
+    /// Interval between controller steps.
+    pub poll_interval: Duration,
+}
+
+impl Default for BalloonPolicy {
+    fn default() -> Self {
+        Self {
+            target_available_mib: 256,
+            hysteresis_mib: 64,
+            min_guest_mib: 128,
+            max_guest_mib: i64::MAX,
+            max_step_mib: 256,
+            stats_polling_interval_s: 1,
+            poll_interval: Duration::from_secs(2),
+        }
+    }
+}
+
+/// Outcome of a single controller step.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Decision {
+    /// Leave the balloon as is.
+    Hold(HoldReason),
+    /// Grow the balloon, reclaiming memory from the guest.
+    Inflate { from_mib: i32, to_mib: i32 },
+    /// Shrink the balloon, returning memory to the guest.
+    Deflate { from_mib: i32, to_mib: i32 },
+}
+
+/// Why a [`Decision::Hold`] was made.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum HoldReason {
+    /// The guest does not report the statistics needed.
+    NoStatistics,
+    /// Available memory is within the tolerated deviation from the target.
+    WithinHysteresis,
+    /// The balloon is already at the limit imposed by the guest memory bounds.
+    AtLimit,
+}
+
+impl Decision {
+    /// The balloon size to apply, if the balloon should be resized.
+    #[inline]
+    pub fn target_mib(&self) -> Option<models::MiB> {
+        match *self {
+            Self::Hold(_) => None,
+            // Targets are clamped to the guest memory bounds, hence never negative.
+            Self::Inflate { to_mib, .. } | Self::Deflate { to_mib, .. } => {
+                Some(models::MiB(to_mib.max(0).unsigned_abs()))
+            }
+        }
+    }
+}
+
+impl BalloonPolicy {
+    /// Decide how to resize the balloon of a microVM with `mem_size_mib` of memory, given its
+    /// latest balloon statistics and the ones of the previous step, if any.
+    ///
+    /// Any major page fault or swap-in since the previous step is treated as memory pressure,
+    /// and results in deflation even if available memory seems sufficient.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wick::balloon::{BalloonPolicy, Decision};
+    /// use wick::models::{BalloonStats, MiB};
+    ///
+    /// let policy = BalloonPolicy::default();
+    /// let stats = BalloonStats {
+    ///     available_memory: Some(1024 << 20),
+    ///     ..BalloonStats::new(0, 0, 0, 0)
+    /// };
+    /// let decision = policy.decide(MiB(2048), None, &stats);
+    /// assert_eq!(decision, Decision::Inflate { from_mib: 0, to_mib: 256 });
+    /// ```
+    pub fn decide(
+        &self,
+        mem_size_mib: models::MiB,
+        previous: Option<&models::BalloonStats>,
+        stats: &models::BalloonStats,
+    ) -> Decision {
+        let Some(available_mib) = available_mib(stats) else {
+            return Decision::Hold(HoldReason::NoStatistics);
+        };
+
+        let grew = |field: fn(&models::BalloonStats) -> Option<i64>| matches!((previous.and_then(field), field(stats)), (Some(a), Some(b)) if b > a);
+        let pressure = grew(|s| s.major_faults) || grew(|s| s.swap_in);
+
+        let mut excess = available_mib - self.target_available_mib;
+        if pressure {
+            excess = excess.min(-self.hysteresis_mib - 1);
+        } else if excess.abs() <= self.hysteresis_mib {
+            return Decision::Hold(HoldReason::WithinHysteresis);
+        }
+
+        let mem_size_mib = i64::from(mem_size_mib);
+        let current = i64::from(stats.target_mib);
+        let min_balloon = (mem_size_mib - self.max_guest_mib.min(mem_size_mib)).max(0);
+        let max_balloon = (mem_size_mib - self.min_guest_mib).max(min_balloon);
+
+        let step = excess.clamp(-self.max_step_mib, self.max_step_mib);
+        let target = (current + step).clamp(min_balloon, max_balloon);
+        let (from_mib, to_mib) = (stats.target_mib, target as i32);
+        match to_mib.cmp(&from_mib) {
+            std::cmp::Ordering::Equal => Decision::Hold(HoldReason::AtLimit),
+            std::cmp::Ordering::Greater => Decision::Inflate { from_mib, to_mib },
+            std::cmp::Ordering::Less => Decision::Deflate { from_mib, to_mib },
+        }
+    }
+}
+
+/// Memory available to the guest's applications, in MiB.
+///
+/// Falls back to free memory plus disk caches for guests that do not report available memory.
+pub(crate) fn available_mib(stats: &models::BalloonStats) -> Option<i64> {
+    stats
+        .available_memory
+        .or_else(|| Some(stats.free_memory? + stats.disk_caches.unwrap_or(0)))
+        .map(bytes_to_mib)
+}
+
+/// Drives the balloon of a single microVM according to a [`BalloonPolicy`].
+#[derive(Debug)]
+pub struct BalloonController<A> {
+    api: A,
+    policy: BalloonPolicy,
+    mem_size_mib: models::MiB,
+    previous: Option<models::BalloonStats>,
+}
+
+impl<A: Api> BalloonController<A> {
+    /// Construct a controller for the microVM behind `api`.
+    ///
+    /// This enables balloon statistics at the policy's polling interval, and queries the
+    /// microVM's memory size.
+    pub async fn start(api: A, policy: BalloonPolicy) -> Result<Self, Error> {
+        api.patch_balloon_stats_interval(models::BalloonStatsUpdate::new(
+            policy.stats_polling_interval_s,
+        ))
+        .await?;
+        let machine_config = api.get_machine_configuration().await?;
+        Ok(Self {
+            api,
+            policy,
+            mem_size_mib: machine_config.mem_size_mib,
+            previous: None,
+        })
+    }
+
+    #[inline]
+    pub fn policy(&self) -> &BalloonPolicy {
+        &self.policy
+    }
+
+    /// Replace the policy; it takes effect from the next step.
+    #[inline]
+    pub fn set_policy(&mut self, policy: BalloonPolicy) {
+        self.policy = policy;
+    }
+
+    /// Fetch the balloon statistics, decide, and apply the decision.
+    #[instrument(level = Level::DEBUG, skip(self))]
+    pub async fn step(&mut self) -> Result<Decision, Error> {
+        let stats = self.api.describe_balloon_stats().await?;
+        let decision = self
+            .policy
+            .decide(self.mem_size_mib, self.previous.as_ref(), &stats);
+        self.previous = Some(stats);
+
+        match decision.target_mib() {
+            Some(amount_mib) => {
+                info!(?decision, "resizing balloon");
+                self.api
+                    .patch_balloon(models::BalloonUpdate::new(amount_mib))
+                    .await?;
+            }
+            None => debug!(?decision, "leaving balloon as is"),
+        }
+        Ok(decision)
+    }
+
+    /// Step at the policy's poll interval until a request fails, reporting every decision to
+    /// `on_decision`.
+    ///
+    /// Returns the error of the failed request; this typically means that the VMM has exited.
+    pub async fn run(mut self, mut on_decision: impl FnMut(&Decision)) -> Error {
+        let mut ticks = interval(self.policy.poll_interval);
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticks.tick().await;
+            match self.step().await {
+                Ok(decision) => on_decision(&decision),
+                Err(err) => return err,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{api::fake::FakeApi, models::MiB};
+
+    const MEM: MiB = MiB(2048);
+
+    fn stats(available_mib: i64, balloon_mib: i32) -> models::BalloonStats {
+        models::BalloonStats {
+            available_memory: Some(available_mib << 20),
+            ..models::BalloonStats::new(0, 0, balloon_mib, balloon_mib)
+        }
+    }
+
+    fn faulting(mut stats: models::BalloonStats, major_faults: i64) -> models::BalloonStats {
+        stats.major_faults = Some(major_faults);
+        stats
+    }
+
+    #[test]
+    fn inflates_by_at_most_a_step_while_memory_is_plentiful() {
+        let policy = BalloonPolicy::default();
+        assert_eq!(
+            policy.decide(MEM, None, &stats(1024, 0)),
+            Decision::Inflate {
+                from_mib: 0,
+                to_mib: 256
+            }
+        );
+        assert_eq!(
+            policy.decide(MEM, None, &stats(400, 0)),
+            Decision::Inflate {
+                from_mib: 0,
+                to_mib: 144
+            }
+        );
+    }
+
+    #[test]
+    fn deflates_when_memory_runs_short() {
+        let policy = BalloonPolicy::default();
+        assert_eq!(
+            policy.decide(MEM, None, &stats(100, 512)),
+            Decision::Deflate {
+                from_mib: 512,
+                to_mib: 356
+            }
+        );
+    }
+
+    #[test]
+    fn deflates_under_pressure_even_within_the_band() {
+        let policy = BalloonPolicy::default();
+        let previous = faulting(stats(300, 512), 10);
+        assert_eq!(
+            policy.decide(MEM, Some(&previous), &faulting(stats(300, 512), 10)),
+            Decision::Hold(HoldReason::WithinHysteresis)
+        );
+        assert_eq!(
+            policy.decide(MEM, Some(&previous), &faulting(stats(300, 512), 11)),
+            Decision::Deflate {
+                from_mib: 512,
+                to_mib: 447
+            }
+        );
+
+        let mut swapping = stats(300, 512);
+        swapping.swap_in = Some(1);
+        let mut swapped = stats(300, 512);
+        swapped.swap_in = Some(0);
+        assert!(matches!(
+            policy.decide(MEM, Some(&swapped), &swapping),
+            Decision::Deflate { .. }
+        ));
+    }
+
+    #[test]
+    fn holds_within_the_hysteresis_band() {
+        let policy = BalloonPolicy::default();
+        for available_mib in [192, 256, 320] {
+            assert_eq!(
+                policy.decide(MEM, None, &stats(available_mib, 512)),
+                Decision::Hold(HoldReason::WithinHysteresis),
+                "{available_mib} MiB available"
+            );
+        }
+        assert!(matches!(
+            policy.decide(MEM, None, &stats(191, 512)),
+            Decision::Deflate { .. }
+        ));
+        assert!(matches!(
+            policy.decide(MEM, None, &stats(321, 512)),
+            Decision::Inflate { .. }
+        ));
+    }
+
+    #[test]
+    fn clamps_to_the_guest_memory_bounds() {
+        let policy = BalloonPolicy {
+            min_guest_mib: 128,
+            max_guest_mib: 1024,
+            ..Default::default()
+        };
+        // The guest is left with at least `min_guest_mib`.
+        assert_eq!(
+            policy.decide(MEM, None, &stats(1000, 1800)),
+            Decision::Inflate {
+                from_mib: 1800,
+                to_mib: 1920
+            }
+        );
+        // The guest is given at most `max_guest_mib`.
+        assert_eq!(
+            policy.decide(MEM, None, &stats(0, 1100)),
+            Decision::Deflate {
+                from_mib: 1100,
+                to_mib: 1024
+            }
+        );
+    }
+
+    #[test]
+    fn holds_when_the_target_is_unchanged() {
+        let policy = BalloonPolicy {
+            max_guest_mib: 1024,
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.decide(MEM, None, &stats(1000, 1920)),
+            Decision::Hold(HoldReason::AtLimit)
+        );
+        assert_eq!(
+            policy.decide(MEM, None, &stats(0, 1024)),
+            Decision::Hold(HoldReason::AtLimit)
+        );
+        assert_eq!(Decision::Hold(HoldReason::AtLimit).target_mib(), None);
+    }
+
+    #[test]
+    fn holds_without_statistics() {
+        let policy = BalloonPolicy::default();
+        let none = models::BalloonStats::new(0, 0, 0, 0);
+        assert_eq!(
+            policy.decide(MEM, None, &none),
+            Decision::Hold(HoldReason::NoStatistics)
+        );
+        // Free memory plus disk caches stand in for available memory.
+        let fallback = models::BalloonStats {
+            free_memory: Some(600 << 20),
+            disk_caches: Some(424 << 20),
+            ..none
+        };
+        assert_eq!(
+            policy.decide(MEM, None, &fallback),
+            policy.decide(MEM, None, &stats(1024, 0))
+        );
+    }
+
+    #[tokio::test]
+    async fn steps_apply_resizes_through_the_api() {
+        let api = FakeApi::new();
+        let machine = models::MachineConfiguration::new(MEM, models::VcpuCount::MIN);
+        api.respond("get_machine_configuration", machine)
+            .respond("describe_balloon_stats", stats(1024, 0))
+            .respond("describe_balloon_stats", stats(300, 256))
+            .fail("describe_balloon_stats");
+
+        let mut controller = BalloonController::start(api.clone(), BalloonPolicy::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            api.calls_to("patch_balloon_stats_interval"),
+            [[json!({"stats_polling_interval_s": 1})]]
+        );
+
+        let decision = controller.step().await.unwrap();
+        assert_eq!(decision.target_mib(), Some(MiB(256)));
+        let decision = controller.step().await.unwrap();
+        assert_eq!(decision, Decision::Hold(HoldReason::WithinHysteresis));
+        assert!(controller.step().await.is_err());
+
+        assert_eq!(
+            api.calls_to("patch_balloon"),
+            [[json!({"amount_mib": 256})]]
+        );
+    }
+}
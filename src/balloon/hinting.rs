@@ -0,0 +1,142 @@
+//! Free page hinting runs, which let the host reclaim the memory the guest has free at a point
+//! in time, e.g. right before a snapshot, without inflating the balloon.
+
+use tokio::time::{sleep, Instant};
+use tracing::{debug, instrument, warn, Level};
+
+use crate::{models, Api, Polling};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("API request failed")]
+    Api(#[source] crate::Error),
+
+    #[error("timed out waiting for the guest to finish hinting")]
+    Timeout(models::BalloonHintingStatus),
+}
+
+impl From<crate::Error> for Error {
+    #[inline]
+    fn from(err: crate::Error) -> Self {
+        Error::Api(err)
+    }
+}
+
+/// Run a free page hinting cycle to completion: start a run, wait for the guest to hint all of
+/// its free pages, and stop it, so that the guest can reuse the hinted pages.
+///
+/// The guest is done once it has [acknowledged](models::BalloonHintingStatus::is_acknowledged)
+/// the run and then [stopped](models::BalloonHintingStatus::is_stopped); `polling.interval`
+/// should be short enough to observe the acknowledgement.
+///
+/// The balloon must have been configured with
+/// [`free_page_hinting`](models::Balloon::free_page_hinting). The run is stopped even if the
+/// guest does not finish within `polling.timeout`, in which case [`Error::Timeout`] carries
+/// the last status observed.
+#[instrument(level = Level::DEBUG, skip(api))]
+pub async fn run_hinting<A: Api>(
+    api: &A,
+    polling: Polling,
+) -> Result<models::BalloonHintingStatus, Error> {
+    api.patch_balloon_hinting_start(models::BalloonStartHinting::new())
+        .await?;
+
+    let deadline = Instant::now() + polling.timeout;
+    let mut acknowledged = None;
+    let status = loop {
+        let status = match api.describe_balloon_hinting().await {
+            Ok(status) => status,
+            Err(err) => {
+                stop(api).await;
+                return Err(err.into());
+            }
+        };
+        if acknowledged == Some(status.host_cmd) && status.is_stopped() {
+            break status;
+        }
+        if status.is_acknowledged() {
+            acknowledged = Some(status.host_cmd);
+        }
+        if Instant::now() >= deadline {
+            stop(api).await;
+            return Err(Error::Timeout(status));
+        }
+        sleep(polling.interval).await;
+    };
+
+    api.patch_balloon_hinting_stop().await?;
+    debug!(host_cmd = status.host_cmd, "free page hinting done");
+    Ok(status)
+}
+
+/// Stop a run that is being abandoned; the original error takes precedence over this one's.
+async fn stop<A: Api>(api: &A) {
+    if let Err(err) = api.patch_balloon_hinting_stop().await {
+        warn!(?err, "failed to stop the free page hinting run");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::api::fake::FakeApi;
+
+    const POLLING: Polling = Polling {
+        interval: Duration::from_millis(1),
+        timeout: Duration::from_millis(50),
+    };
+
+    #[tokio::test]
+    async fn waits_for_the_guest_to_acknowledge_and_stop() {
+        let api = FakeApi::new();
+        api.respond(
+            "describe_balloon_hinting",
+            json!({"host_cmd": 2, "guest_cmd": 0}),
+        )
+        .respond("describe_balloon_hinting", json!({"host_cmd": 2}))
+        .respond(
+            "describe_balloon_hinting",
+            json!({"host_cmd": 2, "guest_cmd": 2}),
+        )
+        .respond(
+            "describe_balloon_hinting",
+            json!({"host_cmd": 2, "guest_cmd": 0}),
+        );
+
+        let status = run_hinting(&api, POLLING).await.unwrap();
+        assert_eq!((status.host_cmd, status.guest_cmd), (2, Some(0)));
+        // The stale stop of a previous run is not mistaken for the end of this one.
+        assert_eq!(api.calls_to("describe_balloon_hinting").len(), 4);
+        assert_eq!(api.calls_to("patch_balloon_hinting_start").len(), 1);
+        assert_eq!(api.calls_to("patch_balloon_hinting_stop").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn stops_the_run_on_timeout() {
+        let api = FakeApi::new();
+        api.respond(
+            "describe_balloon_hinting",
+            json!({"host_cmd": 2, "guest_cmd": 2}),
+        );
+
+        match run_hinting(&api, POLLING).await {
+            Err(Error::Timeout(status)) => assert!(status.is_acknowledged()),
+            other => panic!("expected a timeout, got {other:?}"),
+        }
+        assert_eq!(api.calls_to("patch_balloon_hinting_stop").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn stops_the_run_if_the_status_cannot_be_read() {
+        let api = FakeApi::new();
+        api.fail("describe_balloon_hinting");
+
+        let result = run_hinting(&api, POLLING).await;
+        assert!(matches!(result, Err(Error::Api(_))), "{result:?}");
+        assert_eq!(api.calls_to("patch_balloon_hinting_stop").len(), 1);
+    }
+}
@@ -0,0 +1,17 @@
+//! Automatic sizing of balloon devices.
+
+pub mod arbiter;
+pub mod controller;
+pub mod hinting;
+
+pub use controller::{BalloonController, BalloonPolicy, Decision};
+pub use hinting::run_hinting;
+
+const MIB: i64 = 1 << 20;
+
+/// Convert an amount of bytes, as reported in [`BalloonStats`](crate::models::BalloonStats),
+/// into MiB.
+#[inline]
+pub(crate) fn bytes_to_mib(bytes: i64) -> i64 {
+    bytes / MIB
+}
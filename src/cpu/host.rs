@@ -0,0 +1,713 @@
+//! Detection of the host CPU, and selection of the CPU template that lets microVMs migrate
+//! (i.e. have their snapshots restored) across a pool of hosts.
+//!
+//! Host CPU readings come from a [`HostCpuSource`]; [`ProcCpuInfo`] reads them from
+//! `/proc/cpuinfo`, and [`Cpuid`] from the `CPUID` instruction on x86_64. A [`HostCpu`] is a
+//! source of itself, e.g. to evaluate a pool of remote hosts.
+
+use std::{collections::BTreeSet, fmt, future::Future, io};
+
+use camino::Utf8PathBuf;
+use compact_str::CompactString;
+
+use crate::models::{
+    Bitmap, CpuConfig, CpuTemplate, CpuidLeafModifier, CpuidRegister, CpuidRegisterModifier,
+};
+
+/// Vendor of a CPU.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Vendor {
+    Intel,
+    Amd,
+    /// Arm Ltd., i.e. `CPU implementer : 0x41`.
+    Arm,
+    #[default]
+    Unknown,
+    Other(CompactString),
+}
+
+/// Microarchitectures that CPU templates are defined for.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Microarch {
+    Skylake,
+    CascadeLake,
+    IceLake,
+    SapphireRapids,
+    Milan,
+    Genoa,
+    NeoverseN1,
+    NeoverseV1,
+    NeoverseV2,
+    #[default]
+    Unknown,
+}
+
+impl fmt::Display for Microarch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Skylake => "Intel Skylake",
+            Self::CascadeLake => "Intel Cascade Lake",
+            Self::IceLake => "Intel Ice Lake",
+            Self::SapphireRapids => "Intel Sapphire Rapids",
+            Self::Milan => "AMD Milan",
+            Self::Genoa => "AMD Genoa",
+            Self::NeoverseN1 => "Arm Neoverse N1",
+            Self::NeoverseV1 => "Arm Neoverse V1",
+            Self::NeoverseV2 => "Arm Neoverse V2",
+            Self::Unknown => "an unknown microarchitecture",
+        })
+    }
+}
+
+/// Identification and features of a host CPU.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HostCpu {
+    pub vendor: Vendor,
+    /// Display family on x86_64; zero on aarch64.
+    pub family: u32,
+    /// Display model on x86_64, part number on aarch64.
+    pub model: u32,
+    /// Stepping on x86_64, revision on aarch64.
+    pub stepping: u32,
+    pub model_name: Option<String>,
+    /// Feature flags, as named by `/proc/cpuinfo`.
+    pub flags: BTreeSet<CompactString>,
+}
+
+impl HostCpu {
+    #[inline]
+    pub fn is_x86(&self) -> bool {
+        matches!(self.vendor, Vendor::Intel | Vendor::Amd)
+    }
+
+    /// The microarchitecture of the CPU, if it is one that CPU templates are defined for.
+    pub fn microarch(&self) -> Microarch {
+        match (&self.vendor, self.family, self.model) {
+            (Vendor::Intel, 6, 0x55) if self.stepping < 5 => Microarch::Skylake,
+            (Vendor::Intel, 6, 0x55) if self.stepping < 8 => Microarch::CascadeLake,
+            (Vendor::Intel, 6, 0x6a | 0x6c) => Microarch::IceLake,
+            (Vendor::Intel, 6, 0x8f) => Microarch::SapphireRapids,
+            (Vendor::Amd, 0x19, 0x00..=0x0f) => Microarch::Milan,
+            (Vendor::Amd, 0x19, 0x10..=0x1f | 0xa0..=0xaf) => Microarch::Genoa,
+            (Vendor::Arm, _, 0xd0c) => Microarch::NeoverseN1,
+            (Vendor::Arm, _, 0xd40) => Microarch::NeoverseV1,
+            (Vendor::Arm, _, 0xd4f) => Microarch::NeoverseV2,
+            _ => Microarch::Unknown,
+        }
+    }
+}
+
+/// A source of [`HostCpu`] readings.
+pub trait HostCpuSource: Send + Sync {
+    fn read(&self) -> impl Future<Output = io::Result<HostCpu>> + Send;
+}
+
+impl HostCpuSource for HostCpu {
+    #[inline]
+    async fn read(&self) -> io::Result<HostCpu> {
+        Ok(self.clone())
+    }
+}
+
+/// Reads [`HostCpu`] from procfs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProcCpuInfo {
+    pub path: Utf8PathBuf,
+}
+
+impl Default for ProcCpuInfo {
+    fn default() -> Self {
+        Self {
+            path: "/proc/cpuinfo".into(),
+        }
+    }
+}
+
+impl HostCpuSource for ProcCpuInfo {
+    async fn read(&self) -> io::Result<HostCpu> {
+        let cpuinfo = ::tokio::fs::read_to_string(&self.path).await?;
+        parse_cpuinfo(&cpuinfo)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed /proc/cpuinfo"))
+    }
+}
+
+/// Parse the contents of `/proc/cpuinfo`, as written on x86_64 or aarch64.
+///
+/// Only the first processor is considered.
+pub fn parse_cpuinfo(cpuinfo: &str) -> Option<HostCpu> {
+    let processor = cpuinfo
+        .split("\n\n")
+        .find(|block| !block.trim().is_empty())?;
+    let field = |name: &str| -> Option<&str> {
+        processor.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            (key.trim() == name).then(|| value.trim())
+        })
+    };
+    let number = |name: &str| -> Option<u32> {
+        let value = field(name)?;
+        match value.strip_prefix("0x") {
+            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+            None => value.parse().ok(),
+        }
+    };
+    let flags = |name: &str| {
+        field(name)
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(CompactString::from)
+            .collect()
+    };
+
+    if let Some(vendor_id) = field("vendor_id") {
+        return Some(HostCpu {
+            vendor: x86_vendor(vendor_id),
+            family: number("cpu family")?,
+            model: number("model")?,
+            stepping: number("stepping").unwrap_or(0),
+            model_name: field("model name").map(str::to_owned),
+            flags: flags("flags"),
+        });
+    }
+    let implementer = number("CPU implementer")?;
+    Some(HostCpu {
+        vendor: match implementer {
+            0x41 => Vendor::Arm,
+            other => Vendor::Other(compact_str::format_compact!("{other:#x}")),
+        },
+        family: 0,
+        model: number("CPU part")?,
+        stepping: number("CPU revision").unwrap_or(0),
+        model_name: field("model name").map(str::to_owned),
+        flags: flags("Features"),
+    })
+}
+
+fn x86_vendor(vendor_id: &str) -> Vendor {
+    match vendor_id {
+        "GenuineIntel" => Vendor::Intel,
+        "AuthenticAMD" => Vendor::Amd,
+        other => Vendor::Other(other.into()),
+    }
+}
+
+/// Reads [`HostCpu`] through the `CPUID` instruction.
+///
+/// Only the flags listed in the feature table used to build custom templates are detected; the
+/// model name is not.
+#[cfg(target_arch = "x86_64")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Cpuid;
+
+#[cfg(target_arch = "x86_64")]
+impl HostCpuSource for Cpuid {
+    #[allow(unused_unsafe)]
+    async fn read(&self) -> io::Result<HostCpu> {
+        use std::arch::x86_64::{__cpuid, __cpuid_count};
+
+        // SAFETY: CPUID is available on every x86_64 CPU.
+        let leaf0 = unsafe { __cpuid(0) };
+        let mut vendor_id = Vec::with_capacity(12);
+        for reg in [leaf0.ebx, leaf0.edx, leaf0.ecx] {
+            vendor_id.extend_from_slice(&reg.to_le_bytes());
+        }
+        let vendor = x86_vendor(&String::from_utf8_lossy(&vendor_id));
+
+        // SAFETY: leaf 1 is always supported.
+        let eax = unsafe { __cpuid(1) }.eax;
+        let mut family = (eax >> 8) & 0xf;
+        let mut model = (eax >> 4) & 0xf;
+        if family == 0xf {
+            family += (eax >> 20) & 0xff;
+        }
+        if family == 0x6 || family >= 0xf {
+            model += ((eax >> 16) & 0xf) << 4;
+        }
+
+        let mut flags = BTreeSet::new();
+        for feature in FEATURES {
+            if feature.leaf > leaf0.eax {
+                continue;
+            }
+            // SAFETY: the leaf is within the range reported by leaf 0.
+            let regs = unsafe { __cpuid_count(feature.leaf, feature.subleaf) };
+            let reg = match feature.register {
+                CpuidRegister::Eax => regs.eax,
+                CpuidRegister::Ebx => regs.ebx,
+                CpuidRegister::Ecx => regs.ecx,
+                CpuidRegister::Edx => regs.edx,
+            };
+            if reg & (1 << feature.bit) != 0 {
+                flags.insert(CompactString::from(feature.flag));
+            }
+        }
+
+        Ok(HostCpu {
+            vendor,
+            family,
+            model,
+            stepping: eax & 0xf,
+            model_name: None,
+            flags,
+        })
+    }
+}
+
+/// Location of a feature flag in CPUID.
+struct Feature {
+    flag: &'static str,
+    leaf: u32,
+    subleaf: u32,
+    register: CpuidRegister,
+    bit: u8,
+}
+
+const fn leaf7(flag: &'static str, subleaf: u32, register: CpuidRegister, bit: u8) -> Feature {
+    Feature {
+        flag,
+        leaf: 7,
+        subleaf,
+        register,
+        bit,
+    }
+}
+
+/// Features that differ across the x86_64 server CPUs that microVMs commonly migrate across.
+const FEATURES: &[Feature] = {
+    use CpuidRegister::{Eax, Ebx, Ecx, Edx};
+    &[
+        leaf7("bmi1", 0, Ebx, 3),
+        leaf7("avx2", 0, Ebx, 5),
+        leaf7("bmi2", 0, Ebx, 8),
+        leaf7("avx512f", 0, Ebx, 16),
+        leaf7("avx512dq", 0, Ebx, 17),
+        leaf7("adx", 0, Ebx, 19),
+        leaf7("clflushopt", 0, Ebx, 23),
+        leaf7("clwb", 0, Ebx, 24),
+        leaf7("avx512cd", 0, Ebx, 28),
+        leaf7("sha_ni", 0, Ebx, 29),
+        leaf7("avx512bw", 0, Ebx, 30),
+        leaf7("avx512vl", 0, Ebx, 31),
+        leaf7("avx512vbmi", 0, Ecx, 1),
+        leaf7("pku", 0, Ecx, 3),
+        leaf7("avx512_vbmi2", 0, Ecx, 6),
+        leaf7("gfni", 0, Ecx, 8),
+        leaf7("vaes", 0, Ecx, 9),
+        leaf7("vpclmulqdq", 0, Ecx, 10),
+        leaf7("avx512_vnni", 0, Ecx, 11),
+        leaf7("avx512_bitalg", 0, Ecx, 12),
+        leaf7("avx512_vpopcntdq", 0, Ecx, 14),
+        leaf7("la57", 0, Ecx, 16),
+        leaf7("rdpid", 0, Ecx, 22),
+        leaf7("movdiri", 0, Ecx, 27),
+        leaf7("movdir64b", 0, Ecx, 28),
+        leaf7("fsrm", 0, Edx, 4),
+        leaf7("serialize", 0, Edx, 14),
+        leaf7("amx_bf16", 0, Edx, 22),
+        leaf7("amx_tile", 0, Edx, 24),
+        leaf7("amx_int8", 0, Edx, 25),
+        leaf7("avx_vnni", 1, Eax, 4),
+        leaf7("avx512_bf16", 1, Eax, 5),
+    ]
+};
+
+/// Microarchitectures each template can be used on.
+fn supported(template: &CpuTemplate) -> &'static [Microarch] {
+    use Microarch::*;
+    match template {
+        CpuTemplate::C3 | CpuTemplate::T2 => &[Skylake, CascadeLake, IceLake],
+        CpuTemplate::T2S => &[Skylake, CascadeLake],
+        CpuTemplate::T2CL => &[CascadeLake, IceLake],
+        CpuTemplate::T2A => &[Milan, Genoa],
+        // Exposes the Neoverse N1 feature set on Neoverse V1 hosts.
+        CpuTemplate::V1N1 => &[NeoverseV1],
+        CpuTemplate::None | CpuTemplate::Other(_) => &[],
+    }
+}
+
+/// Static templates in order of preference: the ones tailored to a narrower set of
+/// microarchitectures first, as they hide fewer features, then the ones covering older Intel
+/// generations.
+const PREFERENCE: [CpuTemplate; 6] = [
+    CpuTemplate::T2CL,
+    CpuTemplate::T2A,
+    CpuTemplate::V1N1,
+    CpuTemplate::T2S,
+    CpuTemplate::T2,
+    CpuTemplate::C3,
+];
+
+/// Why a template cannot be used across a pool.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Incompatibility {
+    pub template: CpuTemplate,
+    /// Index of the first host of the pool the template cannot be used on.
+    pub host: usize,
+    pub microarch: Microarch,
+}
+
+impl fmt::Display for Incompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is not supported on {} (host {})",
+            self.template, self.microarch, self.host
+        )
+    }
+}
+
+/// The CPU configuration recommended for microVMs migrating across a pool of hosts.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Recommendation {
+    /// The preferred template usable on every host, if any.
+    pub template: Option<CpuTemplate>,
+    /// If no template is usable, a custom template hiding the features that some x86_64
+    /// hosts lack. `None` if the pool has no such differences, or mixes vendors.
+    pub custom: Option<CpuConfig>,
+    /// Why the other templates cannot be used.
+    pub incompatible: Vec<Incompatibility>,
+}
+
+/// Recommend a CPU configuration for microVMs migrating across `pool`.
+///
+/// # Example
+///
+/// ```
+/// use wick::cpu::host::{self, HostCpu, Vendor};
+/// use wick::models::CpuTemplate;
+///
+/// let cascade_lake = HostCpu {
+///     vendor: Vendor::Intel,
+///     family: 6,
+///     model: 0x55,
+///     stepping: 7,
+///     ..Default::default()
+/// };
+/// let ice_lake = HostCpu {
+///     model: 0x6a,
+///     stepping: 6,
+///     ..cascade_lake.clone()
+/// };
+///
+/// let recommendation = host::recommend(&[cascade_lake, ice_lake]);
+/// assert_eq!(recommendation.template, Some(CpuTemplate::T2CL));
+/// assert!(recommendation.incompatible.iter().any(|i| i.template == CpuTemplate::T2S));
+/// ```
+pub fn recommend(pool: &[HostCpu]) -> Recommendation {
+    let microarchs: Vec<Microarch> = pool.iter().map(HostCpu::microarch).collect();
+    let mut recommendation = Recommendation::default();
+    if pool.is_empty() {
+        return recommendation;
+    }
+
+    for template in PREFERENCE {
+        let unsupported = microarchs
+            .iter()
+            .position(|microarch| !supported(&template).contains(microarch));
+        match unsupported {
+            Some(host) => recommendation.incompatible.push(Incompatibility {
+                template,
+                host,
+                microarch: microarchs[host],
+            }),
+            None if recommendation.template.is_none() => recommendation.template = Some(template),
+            None => (),
+        }
+    }
+
+    let same_vendor = pool.iter().all(|cpu| cpu.vendor == pool[0].vendor);
+    if recommendation.template.is_none() && pool[0].is_x86() && same_vendor {
+        recommendation.custom = common_features(pool);
+    }
+    recommendation
+}
+
+/// A custom template clearing the CPUID bits of the features that some hosts of `pool` lack.
+fn common_features(pool: &[HostCpu]) -> Option<CpuConfig> {
+    let mut leaves: Vec<CpuidLeafModifier> = Vec::new();
+    for feature in FEATURES {
+        let on = pool
+            .iter()
+            .filter(|cpu| cpu.flags.contains(feature.flag))
+            .count();
+        if on == 0 || on == pool.len() {
+            continue;
+        }
+
+        // Leaf 7 has subleaves, so the index is significant.
+        let idx = match leaves
+            .iter()
+            .position(|l| (l.leaf, l.subleaf) == (feature.leaf, feature.subleaf))
+        {
+            Some(idx) => idx,
+            None => {
+                leaves.push(CpuidLeafModifier::new(feature.leaf, feature.subleaf, 1));
+                leaves.len() - 1
+            }
+        };
+        let modifiers = &mut leaves[idx].modifiers;
+        let modifier = match modifiers
+            .iter_mut()
+            .position(|m| m.register == feature.register)
+        {
+            Some(idx) => &mut modifiers[idx],
+            None => {
+                modifiers.push(CpuidRegisterModifier::new(
+                    feature.register,
+                    Bitmap::new(32),
+                ));
+                modifiers.last_mut().expect("just pushed")
+            }
+        };
+        modifier
+            .bitmap
+            .set(feature.bit, Some(false))
+            .expect("CPUID bits fit in 32-bit registers");
+    }
+
+    (!leaves.is_empty()).then(|| CpuConfig {
+        cpuid_modifiers: Some(leaves),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CASCADE_LAKE: &str = "\
+processor\t: 0
+vendor_id\t: GenuineIntel
+cpu family\t: 6
+model\t\t: 85
+model name\t: Intel(R) Xeon(R) Platinum 8259CL CPU @ 2.50GHz
+stepping\t: 7
+flags\t\t: fpu vme avx2 bmi1 bmi2 avx512f avx512dq avx512_vnni
+
+processor\t: 1
+vendor_id\t: GenuineIntel
+cpu family\t: 6
+model\t\t: 106
+stepping\t: 6
+";
+
+    const MILAN: &str = "\
+processor\t: 0
+vendor_id\t: AuthenticAMD
+cpu family\t: 25
+model\t\t: 1
+model name\t: AMD EPYC 7R13 Processor
+stepping\t: 1
+flags\t\t: fpu avx2 sha_ni vaes
+";
+
+    const NEOVERSE_V1: &str = "\
+processor\t: 0
+BogoMIPS\t: 2100.00
+Features\t: fp asimd evtstrm aes sve
+CPU implementer\t: 0x41
+CPU architecture: 8
+CPU variant\t: 0x1
+CPU part\t: 0xd40
+CPU revision\t: 1
+";
+
+    fn flags(flags: &[&str]) -> BTreeSet<CompactString> {
+        flags.iter().copied().map(CompactString::from).collect()
+    }
+
+    fn intel(model: u32, stepping: u32) -> HostCpu {
+        HostCpu {
+            vendor: Vendor::Intel,
+            family: 6,
+            model,
+            stepping,
+            ..Default::default()
+        }
+    }
+
+    fn amd(model: u32) -> HostCpu {
+        HostCpu {
+            vendor: Vendor::Amd,
+            family: 0x19,
+            model,
+            ..Default::default()
+        }
+    }
+
+    fn arm(part: u32) -> HostCpu {
+        HostCpu {
+            vendor: Vendor::Arm,
+            model: part,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parses_the_first_x86_processor() {
+        let cpu = parse_cpuinfo(CASCADE_LAKE).unwrap();
+        assert_eq!(
+            cpu,
+            HostCpu {
+                model_name: Some("Intel(R) Xeon(R) Platinum 8259CL CPU @ 2.50GHz".to_owned()),
+                flags: flags(&[
+                    "fpu",
+                    "vme",
+                    "avx2",
+                    "bmi1",
+                    "bmi2",
+                    "avx512f",
+                    "avx512dq",
+                    "avx512_vnni"
+                ]),
+                ..intel(85, 7)
+            }
+        );
+        assert_eq!(cpu.microarch(), Microarch::CascadeLake);
+
+        let cpu = parse_cpuinfo(MILAN).unwrap();
+        assert_eq!(
+            (cpu.vendor.clone(), cpu.family, cpu.model),
+            (Vendor::Amd, 25, 1)
+        );
+        assert_eq!(cpu.flags, flags(&["fpu", "avx2", "sha_ni", "vaes"]));
+        assert_eq!(cpu.microarch(), Microarch::Milan);
+    }
+
+    #[test]
+    fn parses_aarch64_processors() {
+        let cpu = parse_cpuinfo(NEOVERSE_V1).unwrap();
+        assert_eq!(
+            cpu,
+            HostCpu {
+                stepping: 1,
+                flags: flags(&["fp", "asimd", "evtstrm", "aes", "sve"]),
+                ..arm(0xd40)
+            }
+        );
+        assert_eq!(cpu.microarch(), Microarch::NeoverseV1);
+
+        let neoverse_n1 = NEOVERSE_V1.replace("0xd40", "0xd0c");
+        let cpu = parse_cpuinfo(&neoverse_n1).unwrap();
+        assert_eq!(cpu.microarch(), Microarch::NeoverseN1);
+
+        let other = NEOVERSE_V1.replace("0x41", "0x51");
+        let cpu = parse_cpuinfo(&other).unwrap();
+        assert_eq!(cpu.vendor, Vendor::Other("0x51".into()));
+        assert_eq!(cpu.microarch(), Microarch::Unknown);
+    }
+
+    #[test]
+    fn rejects_malformed_cpuinfo() {
+        assert_eq!(parse_cpuinfo(""), None);
+        assert_eq!(
+            parse_cpuinfo(&CASCADE_LAKE.replace("model\t\t:", "mod:")),
+            None
+        );
+        assert_eq!(parse_cpuinfo(&NEOVERSE_V1.replace("0xd40", "V1")), None);
+    }
+
+    #[test]
+    fn maps_models_to_microarchitectures() {
+        let cases = [
+            (intel(0x55, 4), Microarch::Skylake),
+            (intel(0x55, 7), Microarch::CascadeLake),
+            // Cooper Lake.
+            (intel(0x55, 11), Microarch::Unknown),
+            (intel(0x6a, 6), Microarch::IceLake),
+            (intel(0x8f, 8), Microarch::SapphireRapids),
+            (amd(0x01), Microarch::Milan),
+            (amd(0x11), Microarch::Genoa),
+            (amd(0xa0), Microarch::Genoa),
+            (arm(0xd4f), Microarch::NeoverseV2),
+            (HostCpu::default(), Microarch::Unknown),
+        ];
+        for (cpu, microarch) in cases {
+            assert_eq!(cpu.microarch(), microarch, "{cpu:?}");
+        }
+    }
+
+    #[test]
+    fn recommends_the_preferred_template_supported_everywhere() {
+        let template = |pool: &[HostCpu]| recommend(pool).template;
+        assert_eq!(
+            template(&[intel(0x55, 4), intel(0x6a, 6)]),
+            Some(CpuTemplate::T2)
+        );
+        assert_eq!(
+            template(&[intel(0x55, 4), intel(0x55, 7)]),
+            Some(CpuTemplate::T2S)
+        );
+        assert_eq!(template(&[amd(0x01), amd(0x11)]), Some(CpuTemplate::T2A));
+        assert_eq!(template(&[arm(0xd40)]), Some(CpuTemplate::V1N1));
+        assert_eq!(recommend(&[]), Recommendation::default());
+
+        let recommendation = recommend(&[arm(0xd40), arm(0xd0c)]);
+        assert_eq!(recommendation.template, None);
+        assert_eq!(recommendation.custom, None);
+        assert!(recommendation.incompatible.contains(&Incompatibility {
+            template: CpuTemplate::V1N1,
+            host: 1,
+            microarch: Microarch::NeoverseN1,
+        }));
+    }
+
+    #[test]
+    fn hides_the_features_some_hosts_lack() {
+        let skylake = HostCpu {
+            flags: flags(&["avx2", "avx512f"]),
+            ..intel(0x55, 4)
+        };
+        let sapphire_rapids = HostCpu {
+            flags: flags(&["avx2", "avx512f", "amx_tile", "avx_vnni"]),
+            ..intel(0x8f, 8)
+        };
+        let recommendation = recommend(&[skylake.clone(), sapphire_rapids]);
+        assert_eq!(recommendation.template, None);
+        let custom = recommendation.custom.unwrap();
+
+        let leaves = custom.cpuid_modifiers.unwrap();
+        let cleared: Vec<_> = leaves
+            .iter()
+            .flat_map(|leaf| {
+                leaf.modifiers.iter().flat_map(move |modifier| {
+                    (0..32)
+                        .filter(|&bit| modifier.bitmap.get(bit) == Some(false))
+                        .map(move |bit| (leaf.leaf, leaf.subleaf, modifier.register, bit))
+                })
+            })
+            .collect();
+        assert_eq!(
+            cleared,
+            [
+                (7, 0, CpuidRegister::Edx, 24),
+                (7, 1, CpuidRegister::Eax, 4)
+            ]
+        );
+        assert!(leaves.iter().all(|leaf| leaf.flags == 1));
+        assert!(leaves
+            .iter()
+            .flat_map(|leaf| &leaf.modifiers)
+            .all(|modifier| (0..32).all(|bit| modifier.bitmap.get(bit) != Some(true))));
+
+        // Nothing to hide, or hosts of different vendors.
+        assert_eq!(common_features(&[skylake.clone(), skylake.clone()]), None);
+        assert_eq!(recommend(&[skylake, amd(0x01)]).custom, None);
+    }
+
+    #[tokio::test]
+    async fn reads_cpuinfo_files() {
+        let path = std::env::temp_dir().join(format!("wick-cpuinfo-{}", std::process::id()));
+        std::fs::write(&path, MILAN).unwrap();
+        let source = ProcCpuInfo {
+            path: path.clone().try_into().unwrap(),
+        };
+        let cpu = source.read().await;
+        std::fs::write(&path, "").unwrap();
+        let malformed = source.read().await;
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(cpu.unwrap().microarch(), Microarch::Milan);
+        assert_eq!(malformed.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
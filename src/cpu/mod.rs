@@ -0,0 +1,25 @@
+//! Management of custom CPU templates, i.e. [`CpuConfig`](crate::models::CpuConfig)s applied
+//! through [`Api::put_cpu_configuration`](crate::Api::put_cpu_configuration).
+//!
+//! This mirrors the template manipulations of Firecracker's `cpu-template-helper` in-process:
+//! templates can be [loaded](template::load), [merged](template::merge),
+//! [stripped](template::strip) of modifiers that are no-ops on a host, and
+//! [compared](template::diff). The [`host`] module detects the host CPU, and recommends the
+//! template to use across a pool of hosts.
+
+pub mod host;
+pub mod template;
+
+pub use template::{Conflict, Difference, Scope, Target};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("I/O error")]
+    Io(#[source] std::io::Error),
+
+    #[error("(de)serialization error")]
+    Serde(#[source] ::serde_json::Error),
+
+    #[error("templates have {} conflicting modifiers", .0.len())]
+    Conflicts(Vec<Conflict>),
+}
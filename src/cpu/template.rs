@@ -0,0 +1,736 @@
+//! Loading, merging, stripping and comparison of custom CPU templates.
+//!
+//! Operations work on the individual registers a template modifies (see [`Target`]); modifiers
+//! of the same register within a single template are combined, the later one taking precedence.
+//! The KVM CPUID flags of a leaf apply to all of its registers, and are tracked per leaf.
+//! Fields unknown to this crate are carried along with the template, leaf or register modifier
+//! they belong to (see [`Scope`]).
+
+use std::{
+    collections::{btree_map::Entry, BTreeMap, BTreeSet},
+    fmt,
+};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use compact_str::CompactString;
+use serde_json::Value;
+
+use crate::{
+    cpu::Error,
+    models::{
+        Bitmap, CpuConfig, CpuidLeafModifier, CpuidRegister, CpuidRegisterModifier, Extras,
+        KvmCapability, MsrModifier, RegModifier, VcpuFeature,
+    },
+};
+
+/// A register modified by a custom CPU template.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Target {
+    Cpuid {
+        leaf: u32,
+        subleaf: u32,
+        register: CpuidRegister,
+    },
+    Msr(u32),
+    Reg(u64),
+    VcpuFeature(u32),
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cpuid {
+                leaf,
+                subleaf,
+                register,
+            } => write!(f, "CPUID {leaf:#x}:{subleaf:#x} {register}"),
+            Self::Msr(addr) => write!(f, "MSR {addr:#x}"),
+            Self::Reg(addr) => write!(f, "register {addr:#x}"),
+            Self::VcpuFeature(index) => write!(f, "vCPU feature {index}"),
+        }
+    }
+}
+
+/// Where a field unknown to this crate appears in a template.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Scope {
+    /// The template itself.
+    Template,
+    /// A CPUID leaf modifier.
+    CpuidLeaf { leaf: u32, subleaf: u32 },
+    /// The modifier of a single register.
+    Register(Target),
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Template => f.write_str("template"),
+            Self::CpuidLeaf { leaf, subleaf } => write!(f, "CPUID {leaf:#x}:{subleaf:#x}"),
+            Self::Register(target) => write!(f, "{target}"),
+        }
+    }
+}
+
+/// A modification on which two merged templates disagree.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Conflict {
+    /// The templates set the bits of `bits` to different values.
+    Register {
+        target: Target,
+        bits: u128,
+        /// Indices of the conflicting templates.
+        templates: (usize, usize),
+    },
+    /// The templates modify a CPUID leaf with different KVM CPUID flags.
+    CpuidFlags {
+        leaf: u32,
+        subleaf: u32,
+        templates: (usize, usize),
+    },
+    /// One template adds the capability, the other removes it.
+    KvmCapability { cap: u32, templates: (usize, usize) },
+}
+
+/// A difference between two templates.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Difference {
+    /// The register is modified differently, or by a single template.
+    Register {
+        target: Target,
+        left: Option<Bitmap>,
+        right: Option<Bitmap>,
+    },
+    /// Both templates modify the CPUID leaf, with different KVM CPUID flags.
+    CpuidFlags {
+        leaf: u32,
+        subleaf: u32,
+        left: u32,
+        right: u32,
+    },
+    KvmCapability {
+        cap: u32,
+        left: Option<KvmCapability>,
+        right: Option<KvmCapability>,
+    },
+    /// A field unknown to this crate differs, or is only present in a single template.
+    Extra {
+        scope: Scope,
+        name: CompactString,
+        left: Option<Value>,
+        right: Option<Value>,
+    },
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn side(f: &mut fmt::Formatter<'_>, value: Option<impl fmt::Display>) -> fmt::Result {
+            match value {
+                Some(value) => write!(f, "{value}"),
+                None => f.write_str("(none)"),
+            }
+        }
+        match self {
+            Self::Register {
+                target,
+                left,
+                right,
+            } => {
+                write!(f, "{target}: ")?;
+                side(f, *left)?;
+                f.write_str(" -> ")?;
+                side(f, *right)
+            }
+            Self::CpuidFlags {
+                leaf,
+                subleaf,
+                left,
+                right,
+            } => write!(
+                f,
+                "CPUID {leaf:#x}:{subleaf:#x} flags: {left:#x} -> {right:#x}"
+            ),
+            Self::KvmCapability { cap, left, right } => {
+                write!(f, "KVM capability {cap}: ")?;
+                side(f, *left)?;
+                f.write_str(" -> ")?;
+                side(f, *right)
+            }
+            Self::Extra {
+                scope,
+                name,
+                left,
+                right,
+            } => {
+                write!(f, "{scope} field {name:?}: ")?;
+                side(f, left.as_ref())?;
+                f.write_str(" -> ")?;
+                side(f, right.as_ref())
+            }
+        }
+    }
+}
+
+/// A template broken down into the registers and capabilities it modifies.
+#[derive(Clone, Debug, Default)]
+struct Flat {
+    registers: BTreeMap<Target, Bitmap>,
+    /// KVM CPUID flags, by leaf and subleaf.
+    cpuid_flags: BTreeMap<(u32, u32), u32>,
+    /// `true` if the capability is added, `false` if removed.
+    kvm_capabilities: BTreeMap<u32, bool>,
+    /// Fields unknown to this crate, by where they appear and their name.
+    extras: BTreeMap<(Scope, CompactString), Value>,
+}
+
+impl Flat {
+    fn new(config: &CpuConfig) -> Self {
+        let mut flat = Self::default();
+        flat.add_extras(Scope::Template, &config.extras);
+        for leaf in config.cpuid_modifiers.iter().flatten() {
+            let scope = Scope::CpuidLeaf {
+                leaf: leaf.leaf,
+                subleaf: leaf.subleaf,
+            };
+            flat.add_extras(scope, &leaf.extras);
+            flat.cpuid_flags
+                .insert((leaf.leaf, leaf.subleaf), leaf.flags);
+            for modifier in &leaf.modifiers {
+                let target = Target::Cpuid {
+                    leaf: leaf.leaf,
+                    subleaf: leaf.subleaf,
+                    register: modifier.register,
+                };
+                flat.insert(target, modifier.bitmap, &modifier.extras);
+            }
+        }
+        for modifier in config.msr_modifiers.iter().flatten() {
+            let target = Target::Msr(modifier.addr);
+            flat.insert(target, modifier.bitmap, &modifier.extras);
+        }
+        for modifier in config.reg_modifiers.iter().flatten() {
+            let target = Target::Reg(modifier.addr);
+            flat.insert(target, modifier.bitmap, &modifier.extras);
+        }
+        for feature in config.vcpu_features.iter().flatten() {
+            let target = Target::VcpuFeature(feature.index);
+            flat.insert(target, feature.bitmap, &feature.extras);
+        }
+        for cap in config.kvm_capabilities.iter().flatten() {
+            match *cap {
+                KvmCapability::Add(cap) => flat.kvm_capabilities.insert(cap, true),
+                KvmCapability::Remove(cap) => flat.kvm_capabilities.insert(cap, false),
+            };
+        }
+        flat
+    }
+
+    fn insert(&mut self, target: Target, bitmap: Bitmap, extras: &Extras) {
+        self.registers
+            .entry(target)
+            .and_modify(|existing| *existing = existing.merge(&bitmap))
+            .or_insert(bitmap);
+        self.add_extras(Scope::Register(target), extras);
+    }
+
+    fn add_extras(&mut self, scope: Scope, extras: &Extras) {
+        for (name, value) in extras {
+            self.extras.insert((scope, name.clone()), value.clone());
+        }
+    }
+
+    /// Rebuild a template, leaving out empty bitmaps along with their unknown fields, and the
+    /// unknown fields of CPUID leaves left without registers.
+    fn into_config(self) -> CpuConfig {
+        let cpuid_flags = self.cpuid_flags;
+        let extras = self.extras;
+        let extras_of = |scope: Scope| -> Extras {
+            extras
+                .iter()
+                .filter(|((of, _), _)| *of == scope)
+                .map(|((_, name), value)| (name.clone(), value.clone()))
+                .collect()
+        };
+
+        let mut cpuid: Vec<CpuidLeafModifier> = Vec::new();
+        let mut msr = Vec::new();
+        let mut reg = Vec::new();
+        let mut vcpu = Vec::new();
+        for (target, bitmap) in self.registers {
+            if bitmap.is_empty() {
+                continue;
+            }
+            match target {
+                Target::Cpuid {
+                    leaf,
+                    subleaf,
+                    register,
+                } => {
+                    let modifier = CpuidRegisterModifier {
+                        extras: extras_of(Scope::Register(target)),
+                        ..CpuidRegisterModifier::new(register, bitmap)
+                    };
+                    // Targets are sorted, so the registers of a leaf are contiguous.
+                    match cpuid.last_mut() {
+                        Some(last) if (last.leaf, last.subleaf) == (leaf, subleaf) => {
+                            last.modifiers.push(modifier);
+                        }
+                        _ => cpuid.push(CpuidLeafModifier {
+                            modifiers: vec![modifier],
+                            extras: extras_of(Scope::CpuidLeaf { leaf, subleaf }),
+                            ..CpuidLeafModifier::new(
+                                leaf,
+                                subleaf,
+                                cpuid_flags.get(&(leaf, subleaf)).copied().unwrap_or(0),
+                            )
+                        }),
+                    }
+                }
+                Target::Msr(addr) => msr.push(MsrModifier {
+                    extras: extras_of(Scope::Register(target)),
+                    ..MsrModifier::new(addr, bitmap)
+                }),
+                Target::Reg(addr) => reg.push(RegModifier {
+                    extras: extras_of(Scope::Register(target)),
+                    ..RegModifier::new(addr, bitmap)
+                }),
+                Target::VcpuFeature(index) => vcpu.push(VcpuFeature {
+                    extras: extras_of(Scope::Register(target)),
+                    ..VcpuFeature::new(index, bitmap)
+                }),
+            }
+        }
+        let kvm = self
+            .kvm_capabilities
+            .into_iter()
+            .map(|(cap, add)| capability(cap, add))
+            .collect();
+
+        CpuConfig {
+            cpuid_modifiers: non_empty(cpuid),
+            msr_modifiers: non_empty(msr),
+            reg_modifiers: non_empty(reg),
+            vcpu_features: non_empty(vcpu),
+            kvm_capabilities: non_empty(kvm),
+            extras: extras_of(Scope::Template),
+        }
+    }
+}
+
+fn non_empty<T>(items: Vec<T>) -> Option<Vec<T>> {
+    (!items.is_empty()).then_some(items)
+}
+
+fn capability(cap: u32, add: bool) -> KvmCapability {
+    if add {
+        KvmCapability::Add(cap)
+    } else {
+        KvmCapability::Remove(cap)
+    }
+}
+
+/// Parse a custom CPU template.
+#[inline]
+pub fn from_json(json: &str) -> Result<CpuConfig, Error> {
+    ::serde_json::from_str(json).map_err(Error::Serde)
+}
+
+/// Load the custom CPU template at `path`.
+pub async fn load(path: impl AsRef<Utf8Path>) -> Result<CpuConfig, Error> {
+    let json = ::tokio::fs::read_to_string(path.as_ref())
+        .await
+        .map_err(Error::Io)?;
+    from_json(&json)
+}
+
+/// Load every `*.json` template of the directory at `path`, by file stem.
+pub async fn load_dir(
+    path: impl AsRef<Utf8Path>,
+) -> Result<BTreeMap<CompactString, CpuConfig>, Error> {
+    let mut templates = BTreeMap::new();
+    let mut entries = ::tokio::fs::read_dir(path.as_ref())
+        .await
+        .map_err(Error::Io)?;
+    while let Some(entry) = entries.next_entry().await.map_err(Error::Io)? {
+        let Ok(path) = Utf8PathBuf::from_path_buf(entry.path()) else {
+            continue;
+        };
+        if path.extension() != Some("json") {
+            continue;
+        }
+        if let Some(stem) = path.file_stem() {
+            templates.insert(stem.into(), load(&path).await?);
+        }
+    }
+    Ok(templates)
+}
+
+/// Merge `templates` into a single one applying all their modifiers.
+///
+/// Returns [`Error::Conflicts`] listing every bit, CPUID leaf flags and KVM capability that the
+/// templates disagree on. Fields unknown to this crate are kept; where templates disagree on one, the
+/// value of the later template is used.
+///
+/// # Example
+///
+/// ```
+/// use wick::cpu::{template, Error};
+///
+/// let a = template::from_json(r#"{"msr_modifiers": [{"addr": "0x10a", "bitmap": "0bx1"}]}"#)?;
+/// let b = template::from_json(r#"{"msr_modifiers": [{"addr": "0x10a", "bitmap": "0b1x"}]}"#)?;
+/// let merged = template::merge([&a, &b])?;
+/// assert_eq!(merged.msr_modifiers.unwrap()[0].bitmap.to_string(), "0b11");
+///
+/// let c = template::from_json(r#"{"msr_modifiers": [{"addr": "0x10a", "bitmap": "0bx0"}]}"#)?;
+/// assert!(matches!(template::merge([&a, &c]), Err(Error::Conflicts(_))));
+/// # Ok::<(), Error>(())
+/// ```
+pub fn merge<'a>(templates: impl IntoIterator<Item = &'a CpuConfig>) -> Result<CpuConfig, Error> {
+    let mut merged = Flat::default();
+    // Which template each bit of each register, and each capability, comes from.
+    let mut origins: BTreeMap<Target, Vec<(usize, Bitmap)>> = BTreeMap::new();
+    let mut flags_origins: BTreeMap<(u32, u32), usize> = BTreeMap::new();
+    let mut cap_origins: BTreeMap<u32, usize> = BTreeMap::new();
+    let mut conflicts = Vec::new();
+
+    for (idx, template) in templates.into_iter().enumerate() {
+        let flat = Flat::new(template);
+        merged.extras.extend(flat.extras);
+        for (target, bitmap) in flat.registers {
+            let seen = origins.entry(target).or_default();
+            for (other, other_bitmap) in seen.iter() {
+                if other_bitmap.conflicts_with(&bitmap) {
+                    let bits = (other_bitmap.value() ^ bitmap.value())
+                        & other_bitmap.filter()
+                        & bitmap.filter();
+                    conflicts.push(Conflict::Register {
+                        target,
+                        bits,
+                        templates: (*other, idx),
+                    });
+                }
+            }
+            seen.push((idx, bitmap));
+            merged
+                .registers
+                .entry(target)
+                .and_modify(|existing| *existing = existing.merge(&bitmap))
+                .or_insert(bitmap);
+        }
+        for ((leaf, subleaf), flags) in flat.cpuid_flags {
+            match merged.cpuid_flags.entry((leaf, subleaf)) {
+                Entry::Vacant(entry) => {
+                    entry.insert(flags);
+                    flags_origins.insert((leaf, subleaf), idx);
+                }
+                Entry::Occupied(entry) if *entry.get() != flags => {
+                    conflicts.push(Conflict::CpuidFlags {
+                        leaf,
+                        subleaf,
+                        templates: (flags_origins[&(leaf, subleaf)], idx),
+                    });
+                }
+                Entry::Occupied(_) => (),
+            }
+        }
+        for (cap, add) in flat.kvm_capabilities {
+            match merged.kvm_capabilities.entry(cap) {
+                Entry::Vacant(entry) => {
+                    entry.insert(add);
+                    cap_origins.insert(cap, idx);
+                }
+                Entry::Occupied(entry) if *entry.get() != add => {
+                    conflicts.push(Conflict::KvmCapability {
+                        cap,
+                        templates: (cap_origins[&cap], idx),
+                    });
+                }
+                Entry::Occupied(_) => (),
+            }
+        }
+    }
+
+    if conflicts.is_empty() {
+        Ok(merged.into_config())
+    } else {
+        Err(Error::Conflicts(conflicts))
+    }
+}
+
+/// Remove from `template` the modifications that would not change anything on the host whose
+/// CPU configuration was dumped into `host` (e.g. by `cpu-template-helper template dump`).
+///
+/// Modifiers left empty are dropped along with their unknown fields, as are the unknown fields of
+/// CPUID leaves whose registers are all dropped. KVM capabilities are kept, as dumps do not
+/// include them.
+pub fn strip(template: &CpuConfig, host: &CpuConfig) -> CpuConfig {
+    let host = Flat::new(host);
+    let mut flat = Flat::new(template);
+    for (target, bitmap) in &mut flat.registers {
+        let Some(actual) = host.registers.get(target) else {
+            continue;
+        };
+        // Bits known on the host and already holding the value the template sets.
+        let same = actual.filter() & !(actual.value() ^ bitmap.value());
+        let filter = bitmap.filter() & !same;
+        *bitmap = Bitmap::from_parts(bitmap.width(), filter, bitmap.value())
+            .expect("stripping bits keeps the bitmap within its width");
+    }
+    flat.into_config()
+}
+
+/// Compare two templates, listing the registers and KVM capabilities they modify differently,
+/// along with the fields unknown to this crate that differ.
+///
+/// Differences are sorted by register, then by CPUID leaf flags, then by capability, then by
+/// unknown field.
+pub fn diff(left: &CpuConfig, right: &CpuConfig) -> Vec<Difference> {
+    let left = Flat::new(left);
+    let right = Flat::new(right);
+    let mut differences = Vec::new();
+
+    let targets: BTreeSet<_> = left
+        .registers
+        .keys()
+        .chain(right.registers.keys())
+        .collect();
+    for target in targets {
+        let (l, r) = (left.registers.get(target), right.registers.get(target));
+        // Bitmaps of different widths may still set the same bits.
+        let bits = |bitmap: &Bitmap| (bitmap.filter(), bitmap.value());
+        if l.map(bits) != r.map(bits) {
+            differences.push(Difference::Register {
+                target: *target,
+                left: l.copied(),
+                right: r.copied(),
+            });
+        }
+    }
+
+    for (&(leaf, subleaf), &l) in &left.cpuid_flags {
+        match right.cpuid_flags.get(&(leaf, subleaf)) {
+            Some(&r) if l != r => differences.push(Difference::CpuidFlags {
+                leaf,
+                subleaf,
+                left: l,
+                right: r,
+            }),
+            _ => (),
+        }
+    }
+
+    let caps: BTreeSet<_> = left
+        .kvm_capabilities
+        .keys()
+        .chain(right.kvm_capabilities.keys())
+        .collect();
+    for &cap in caps {
+        let (l, r) = (
+            left.kvm_capabilities.get(&cap),
+            right.kvm_capabilities.get(&cap),
+        );
+        if l != r {
+            differences.push(Difference::KvmCapability {
+                cap,
+                left: l.map(|&add| capability(cap, add)),
+                right: r.map(|&add| capability(cap, add)),
+            });
+        }
+    }
+
+    let extras: BTreeSet<_> = left.extras.keys().chain(right.extras.keys()).collect();
+    for key in extras {
+        let (l, r) = (left.extras.get(key), right.extras.get(key));
+        if l != r {
+            let (scope, name) = key.clone();
+            differences.push(Difference::Extra {
+                scope,
+                name,
+                left: l.cloned(),
+                right: r.cloned(),
+            });
+        }
+    }
+    differences
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn template(json: Value) -> CpuConfig {
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn leaf(leaf: &str, flags: u32, modifiers: Value) -> Value {
+        json!({"leaf": leaf, "subleaf": "0x0", "flags": flags, "modifiers": modifiers})
+    }
+
+    fn conflicts(result: Result<CpuConfig, Error>) -> Vec<Conflict> {
+        match result {
+            Err(Error::Conflicts(conflicts)) => conflicts,
+            other => panic!("expected conflicts, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn merges_the_registers_of_a_leaf() {
+        let a = template(json!({"cpuid_modifiers": [
+            leaf("0x1", 1, json!([{"register": "eax", "bitmap": "0bx1"}])),
+        ]}));
+        let b = template(json!({"cpuid_modifiers": [
+            leaf("0x1", 1, json!([
+                {"register": "eax", "bitmap": "0b1x"},
+                {"register": "ebx", "bitmap": "0b0"},
+            ])),
+        ]}));
+        let merged = merge([&a, &b]).unwrap();
+        assert_eq!(
+            serde_json::to_value(merged).unwrap(),
+            json!({"cpuid_modifiers": [
+                leaf("0x1", 1, json!([
+                    {"register": "eax", "bitmap": "0b11"},
+                    {"register": "ebx", "bitmap": "0b0"},
+                ])),
+            ]})
+        );
+    }
+
+    #[test]
+    fn reports_conflicting_bits_and_capabilities() {
+        let a = template(json!({
+            "cpuid_modifiers": [leaf("0x1", 0, json!([{"register": "ecx", "bitmap": "0b1x1"}]))],
+            "kvm_capabilities": ["170"],
+        }));
+        let b = template(json!({
+            "cpuid_modifiers": [leaf("0x1", 0, json!([{"register": "ecx", "bitmap": "0b010"}]))],
+            "kvm_capabilities": ["!170"],
+        }));
+        let target = Target::Cpuid {
+            leaf: 1,
+            subleaf: 0,
+            register: CpuidRegister::Ecx,
+        };
+        assert_eq!(
+            conflicts(merge([&a, &b])),
+            [
+                Conflict::Register {
+                    target,
+                    bits: 0b101,
+                    templates: (0, 1),
+                },
+                Conflict::KvmCapability {
+                    cap: 170,
+                    templates: (0, 1),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_conflicting_cpuid_flags() {
+        let a = template(json!({"cpuid_modifiers": [
+            leaf("0x7", 1, json!([{"register": "eax", "bitmap": "0bx1"}])),
+        ]}));
+        let b = template(json!({"cpuid_modifiers": [
+            leaf("0x7", 0, json!([{"register": "ebx", "bitmap": "0b1"}])),
+        ]}));
+        let c = template(json!({"cpuid_modifiers": [
+            leaf("0x7", 1, json!([{"register": "ebx", "bitmap": "0b1"}])),
+        ]}));
+        assert_eq!(
+            conflicts(merge([&a, &c, &b])),
+            [Conflict::CpuidFlags {
+                leaf: 7,
+                subleaf: 0,
+                templates: (0, 2),
+            }]
+        );
+        assert!(merge([&a, &c]).is_ok());
+    }
+
+    #[test]
+    fn strips_no_ops_on_the_host() {
+        let host = template(json!({
+            "cpuid_modifiers": [
+                leaf("0x1", 0, json!([
+                    {"register": "eax", "bitmap": "0b11"},
+                    {"register": "ebx", "bitmap": "0b00"},
+                ])),
+                leaf("0x2", 0, json!([{"register": "eax", "bitmap": "0b1"}])),
+            ],
+            "msr_modifiers": [{"addr": "0x10a", "bitmap": "0b00"}],
+        }));
+        let template = template(json!({
+            "cpuid_modifiers": [
+                {
+                    "leaf": "0x1",
+                    "subleaf": "0x0",
+                    "flags": 0,
+                    "modifiers": [
+                        {"register": "eax", "bitmap": "0b11"},
+                        {"register": "ebx", "bitmap": "0b1x"},
+                    ],
+                    "comment": "kept",
+                },
+                {
+                    "leaf": "0x2",
+                    "subleaf": "0x0",
+                    "flags": 0,
+                    "modifiers": [{"register": "eax", "bitmap": "0b1"}],
+                    "comment": "dropped with the leaf",
+                },
+            ],
+            "msr_modifiers": [{"addr": "0x10a", "bitmap": "0b10"}],
+            "kvm_capabilities": ["170"],
+        }));
+        assert_eq!(
+            serde_json::to_value(strip(&template, &host)).unwrap(),
+            json!({
+                "cpuid_modifiers": [{
+                    "leaf": "0x1",
+                    "subleaf": "0x0",
+                    "flags": 0,
+                    "modifiers": [{"register": "ebx", "bitmap": "0b1x"}],
+                    "comment": "kept",
+                }],
+                "msr_modifiers": [{"addr": "0x10a", "bitmap": "0b1x"}],
+                "kvm_capabilities": ["170"],
+            })
+        );
+    }
+
+    #[test]
+    fn lists_differences() {
+        let left = template(json!({
+            "cpuid_modifiers": [leaf("0x1", 0, json!([{"register": "eax", "bitmap": "0b1"}]))],
+            "msr_modifiers": [
+                {"addr": "0x10a", "bitmap": "0b1"},
+                {"addr": "0x10b", "bitmap": "0b1"},
+            ],
+            "kvm_capabilities": ["170"],
+            "comment": "left",
+        }));
+        let right = template(json!({
+            "cpuid_modifiers": [leaf("0x1", 1, json!([{"register": "eax", "bitmap": "0b1"}]))],
+            "msr_modifiers": [
+                {"addr": "0x10a", "bitmap": "0b0"},
+                {"addr": "0x10b", "bitmap": "0bxx1"},
+            ],
+        }));
+        let differences = diff(&left, &right);
+        let rendered: Vec<String> = differences.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            rendered,
+            [
+                "MSR 0x10a: 0b1 -> 0b0",
+                "CPUID 0x1:0x0 flags: 0x0 -> 0x1",
+                "KVM capability 170: 170 -> (none)",
+                r#"template field "comment": "left" -> (none)"#,
+            ]
+        );
+        assert_eq!(diff(&left, &left), []);
+    }
+}
@@ -0,0 +1,72 @@
+//! Resizing the hot-pluggable memory of a running guest.
+//!
+//! A [`PATCH /hotplug/memory`](Api::patch_memory_hotplug) only records the requested size; the
+//! guest driver then plugs (or unplugs) memory blocks asynchronously. [`resize`] waits for it to
+//! catch up.
+
+use tokio::time::{sleep, Instant};
+use tracing::{debug, instrument, Level};
+
+use crate::{models, Api, Polling};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("API request failed")]
+    Api(#[source] crate::Error),
+
+    #[error("requested {requested} exceeds the {total} of hot-pluggable memory")]
+    TooLarge {
+        requested: models::MiB,
+        total: models::MiB,
+    },
+
+    #[error("timed out with {} of {} plugged", .0.plugged_size_mib, .0.requested_size_mib)]
+    Timeout(models::MemoryHotplugStatus),
+}
+
+impl From<crate::Error> for Error {
+    #[inline]
+    fn from(err: crate::Error) -> Self {
+        Error::Api(err)
+    }
+}
+
+/// Request the guest to have `requested_size_mib` of hot-pluggable memory plugged, and poll until
+/// it does.
+///
+/// Fails early if the request exceeds the configured total, and with [`Error::Timeout`],
+/// carrying the last status, if the guest does not settle in time; a guest may never plug all of
+/// the memory, e.g. if its driver is not loaded, or never unplug it, e.g. if it is in use.
+#[instrument(level = Level::DEBUG, skip(api))]
+pub async fn resize<A: Api>(
+    api: &A,
+    requested_size_mib: models::MiB,
+    polling: Polling,
+) -> Result<models::MemoryHotplugStatus, Error> {
+    let status = api.get_memory_hotplug().await?;
+    if requested_size_mib > status.total_size_mib {
+        return Err(Error::TooLarge {
+            requested: requested_size_mib,
+            total: status.total_size_mib,
+        });
+    }
+
+    api.patch_memory_hotplug(models::MemoryHotplugSizeUpdate::new(requested_size_mib))
+        .await?;
+
+    let deadline = Instant::now() + polling.timeout;
+    loop {
+        let status = api.get_memory_hotplug().await?;
+        if status.requested_size_mib == requested_size_mib && status.is_settled() {
+            debug!(
+                plugged_size_mib = status.plugged_size_mib.0,
+                "memory resized"
+            );
+            return Ok(status);
+        }
+        if Instant::now() >= deadline {
+            return Err(Error::Timeout(status));
+        }
+        sleep(polling.interval).await;
+    }
+}
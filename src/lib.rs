@@ -1,10 +1,22 @@
 //! An **unofficial** client crate for
-//! [Firecracker](https://github.com/firecracker-microvm/firecracker) v1.13.1.
+//! [Firecracker](https://github.com/firecracker-microvm/firecracker) v1.14.
+//! Endpoints added in v1.14 fail with [`Error::Unsupported`] against older VMMs.
 
 pub mod api;
+pub mod balloon;
+pub mod cpu;
+pub mod hotplug;
+pub mod logs;
+pub mod metrics;
 pub mod models;
+pub mod poll;
+pub mod rate_limit;
+mod tail;
+pub mod vmm;
+pub mod watch;
 
 pub use api::client::Client;
 pub use api::error::ApiError;
 pub use api::error::Error;
 pub use api::Api;
+pub use poll::Polling;
@@ -0,0 +1,159 @@
+//! Parsing of the human readable log Firecracker writes to the path configured through
+//! [`Api::put_logger`](crate::Api::put_logger), and forwarding of it into [`tracing`].
+//!
+//! Firecracker log lines look like
+//!
+//! ```text
+//! 2025-01-31T10:20:30.123456789 [vm0:fc_api:INFO:src/firecracker/src/api_server/mod.rs:123] message
+//! ```
+//!
+//! where the level and the origin (file and line) are only present if enabled through
+//! [`Logger::show_level`] and [`Logger::show_log_origin`] respectively.
+//!
+//! [`Logger::show_level`]: crate::models::Logger::show_level
+//! [`Logger::show_log_origin`]: crate::models::Logger::show_log_origin
+
+pub mod reader;
+
+use compact_str::CompactString;
+use tracing::Level;
+
+pub use reader::LogReader;
+
+/// Target of the [`tracing`] events emitted for forwarded log records.
+pub const TRACING_TARGET: &str = "firecracker";
+
+/// A single record of Firecracker's log.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LogRecord {
+    /// The timestamp, as written by Firecracker (e.g. `2025-01-31T10:20:30.123456789`).
+    pub timestamp: CompactString,
+    /// MicroVM / instance ID.
+    pub instance_id: CompactString,
+    /// Name of the Firecracker thread that logged the record (e.g. `fc_api`, `fc_vcpu 0`).
+    pub thread: CompactString,
+    /// Level of the record, if Firecracker is configured to show it.
+    pub level: Option<Level>,
+    /// Source location of the record, if Firecracker is configured to show it.
+    pub origin: Option<LogOrigin>,
+    /// The logged message.
+    pub message: String,
+}
+
+/// Source location of a [`LogRecord`] in Firecracker's code base.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LogOrigin {
+    pub file: CompactString,
+    pub line: u32,
+}
+
+impl LogRecord {
+    /// A record for a line that is not in Firecracker's log format, e.g. the continuation of a
+    /// multi-line message.
+    #[inline]
+    pub fn unformatted(line: impl Into<String>) -> Self {
+        Self {
+            message: line.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Emit the record as a [`tracing`] event with target [`TRACING_TARGET`], tagged with
+    /// `vm_id`.
+    ///
+    /// Records without a level are emitted at [`Level::INFO`].
+    pub fn emit(&self, vm_id: &str) {
+        macro_rules! emit {
+            ($level:expr) => {
+                ::tracing::event!(
+                    target: TRACING_TARGET,
+                    $level,
+                    vm_id,
+                    timestamp = %self.timestamp,
+                    thread = %self.thread,
+                    file = self.origin.as_ref().map(|o| o.file.as_str()),
+                    line = self.origin.as_ref().map(|o| o.line),
+                    "{}",
+                    self.message,
+                )
+            };
+        }
+
+        match self.level.unwrap_or(Level::INFO) {
+            Level::ERROR => emit!(Level::ERROR),
+            Level::WARN => emit!(Level::WARN),
+            Level::INFO => emit!(Level::INFO),
+            Level::DEBUG => emit!(Level::DEBUG),
+            Level::TRACE => emit!(Level::TRACE),
+        }
+    }
+}
+
+/// Parse a single line of Firecracker's log.
+///
+/// Returns `None` if the line is not in Firecracker's log format.
+///
+/// # Example
+///
+/// ```
+/// use tracing::Level;
+///
+/// let line = "2025-01-31T10:20:30.123456789 [vm0:fc_vcpu 0:WARN:src/vmm/src/lib.rs:42] oops";
+/// let record = wick::logs::parse_line(line).unwrap();
+/// assert_eq!(record.instance_id, "vm0");
+/// assert_eq!(record.thread, "fc_vcpu 0");
+/// assert_eq!(record.level, Some(Level::WARN));
+/// assert_eq!(record.origin.unwrap().line, 42);
+/// assert_eq!(record.message, "oops");
+/// ```
+pub fn parse_line(line: &str) -> Option<LogRecord> {
+    let (timestamp, rest) = line.split_once(' ')?;
+    if !timestamp.starts_with(|c: char| c.is_ascii_digit()) || !timestamp.contains('T') {
+        return None;
+    }
+    let (header, message) = rest.strip_prefix('[')?.split_once(']')?;
+    let message = message.strip_prefix(' ').unwrap_or(message);
+
+    let mut parts: Vec<&str> = header.split(':').collect();
+    let mut origin = None;
+    if parts.len() >= 4 {
+        if let Ok(line) = parts[parts.len() - 1].parse() {
+            origin = Some(LogOrigin {
+                file: parts[parts.len() - 2].into(),
+                line,
+            });
+            parts.truncate(parts.len() - 2);
+        }
+    }
+    let mut level = None;
+    if parts.len() >= 3 {
+        level = parse_level(parts[parts.len() - 1]);
+        if level.is_some() {
+            parts.pop();
+        }
+    }
+    let (instance_id, thread) = match parts.as_slice() {
+        [instance_id, thread @ ..] if !thread.is_empty() => (*instance_id, thread.join(":")),
+        _ => return None,
+    };
+
+    Some(LogRecord {
+        timestamp: timestamp.into(),
+        instance_id: instance_id.into(),
+        thread: thread.into(),
+        level,
+        origin,
+        message: message.to_owned(),
+    })
+}
+
+fn parse_level(level: &str) -> Option<Level> {
+    match level {
+        "ERROR" => Some(Level::ERROR),
+        "WARN" | "WARNING" => Some(Level::WARN),
+        "INFO" => Some(Level::INFO),
+        "DEBUG" => Some(Level::DEBUG),
+        "TRACE" => Some(Level::TRACE),
+        _ => None,
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use camino::Utf8Path;
+use futures_core::Stream;
+use futures_util::StreamExt;
+use tokio::fs::File;
+
+use crate::{
+    logs::{parse_line, LogRecord},
+    tail::LineTailer,
+};
+
+/// Reads [`LogRecord`]s off a Firecracker log file or named pipe.
+///
+/// The reader is a [`Stream`] of records; lines that are not in Firecracker's log format are
+/// yielded as [unformatted](LogRecord::unformatted) records.
+///
+/// By default the stream ends at end-of-file. When [following](Self::follow), it instead waits
+/// for more data to be written, like `tail -f`.
+#[derive(Debug)]
+pub struct LogReader {
+    lines: LineTailer,
+}
+
+impl LogReader {
+    /// Open the log file or named pipe at `path`.
+    ///
+    /// Note that opening a named pipe blocks (a thread of Tokio's blocking pool) until
+    /// Firecracker opens it for writing.
+    pub async fn open(path: impl AsRef<Utf8Path>) -> io::Result<Self> {
+        let file = File::open(path.as_ref()).await?;
+        Ok(Self::new(file))
+    }
+
+    /// Construct a `LogReader` reading from an already opened file.
+    #[inline]
+    pub fn new(file: File) -> Self {
+        Self {
+            lines: LineTailer::new(file),
+        }
+    }
+
+    /// Keep waiting for new lines at end-of-file, checking every `poll_interval`.
+    #[inline]
+    pub fn follow(mut self, poll_interval: Duration) -> Self {
+        self.lines.follow(poll_interval);
+        self
+    }
+
+    /// [Emit](LogRecord::emit) every record as a [`tracing`] event tagged with `vm_id`, until
+    /// end-of-file (or forever, if following).
+    ///
+    /// Unformatted records inherit the level of the record preceding them.
+    pub async fn forward(mut self, vm_id: &str) -> io::Result<()> {
+        let mut level = None;
+        while let Some(record) = self.next().await {
+            let mut record = record?;
+            if record.level.is_none() && record.instance_id.is_empty() {
+                record.level = level;
+            }
+            level = record.level;
+            record.emit(vm_id);
+        }
+        Ok(())
+    }
+}
+
+impl Stream for LogReader {
+    type Item = io::Result<LogRecord>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.lines.poll_next_line(cx).map(|line| {
+            let line = line?;
+            Some(line.map(|line| parse_line(&line).unwrap_or_else(|| LogRecord::unformatted(line))))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn follows_lines_as_they_are_written() {
+        let path = std::env::temp_dir().join(format!("wick-log-{}", std::process::id()));
+        let mut writer = std::fs::File::create(&path).unwrap();
+        writer
+            .write_all(b"2025-01-31T10:20:30.123456789 [vm0:fc_api:INFO] API server started\n")
+            .unwrap();
+
+        let file = File::open(&path).await.unwrap();
+        let mut reader = LogReader::new(file).follow(Duration::from_millis(5));
+        let record = reader.next().await.unwrap().unwrap();
+        assert_eq!(record.instance_id, "vm0");
+        assert_eq!(record.message, "API server started");
+
+        // A partially written line is held back until its newline arrives.
+        writer.write_all(b"not in the log ").unwrap();
+        let next = ::tokio::spawn(async move { reader.next().await });
+        ::tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!next.is_finished());
+        writer.write_all(b"format\n").unwrap();
+        let record = next.await.unwrap().unwrap().unwrap();
+        assert_eq!(record, LogRecord::unformatted("not in the log format"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,39 @@
+//! Parsing of the metrics Firecracker flushes to the path configured through
+//! [`Api::put_metrics`](crate::Api::put_metrics).
+//!
+//! Firecracker writes one JSON object per line, either periodically or upon
+//! [`ActionType::FlushMetrics`](crate::models::instance_action_info::ActionType::FlushMetrics).
+//! Each line can be parsed on its own through [`parse_line`], or a whole file or named pipe can
+//! be followed through a [`MetricsReader`]. With the `prometheus` feature enabled, snapshots can
+//! also be exported to Prometheus through the `prometheus` module.
+
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
+pub mod reader;
+pub mod schema;
+
+pub use reader::MetricsReader;
+pub use schema::MetricsSnapshot;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("I/O error")]
+    Io(#[source] std::io::Error),
+
+    #[error("(de)serialization error")]
+    Serde(#[source] ::serde_json::Error),
+}
+
+/// Parse a single line of Firecracker metrics output.
+///
+/// # Example
+///
+/// ```
+/// let line = r#"{"utc_timestamp_ms":1700000000000,"block_rootfs":{"read_bytes":4096}}"#;
+/// let snapshot = wick::metrics::parse_line(line).unwrap();
+/// assert_eq!(snapshot.devices.block["rootfs"].read_bytes, 4096);
+/// ```
+#[inline]
+pub fn parse_line(line: &str) -> Result<MetricsSnapshot, Error> {
+    ::serde_json::from_str(line.trim_end()).map_err(Error::Serde)
+}
@@ -0,0 +1,415 @@
+//! Prometheus exposition of Firecracker metrics.
+//!
+//! Firecracker counters are deltas since the previous flush; an [`Exporter`] accumulates them
+//! into cumulative counters (suffixed with `_total`), and keeps the latest value of gauges
+//! (latencies, start-up times, the flush timestamp). Every series is labeled with `vm_id`;
+//! per-device series are additionally labeled with `drive` or `iface`. The `block` and `net`
+//! sections, which Firecracker aggregates over all devices, are not exported: summing the
+//! per-device series yields them, and sharing their families would count every event twice.
+
+use std::{
+    collections::BTreeMap,
+    convert::Infallible,
+    fmt::Write,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use compact_str::{format_compact, CompactString};
+use http_body_util::Full;
+use hyper::{
+    body::{Bytes, Incoming},
+    header::{HeaderValue, CONTENT_TYPE},
+    server::conn::http1,
+    service::service_fn,
+    Method, Request, Response, StatusCode,
+};
+use hyper_util::rt::TokioIo;
+use serde_json::{Map, Value};
+use tokio::{
+    net::TcpListener,
+    time::{interval, MissedTickBehavior},
+};
+use tracing::{debug, warn};
+
+use crate::{
+    metrics::{schema::DeviceSections, MetricsSnapshot},
+    models::{instance_action_info::ActionType, InstanceActionInfo},
+    Api,
+};
+
+/// Prefix of every exported metric name.
+const NAMESPACE: &str = "firecracker";
+
+/// `Content-Type` of the Prometheus text exposition format.
+pub const CONTENT_TYPE_TEXT: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Kind {
+    Counter,
+    Gauge,
+}
+
+type Labels = Vec<(&'static str, CompactString)>;
+
+#[derive(Clone, Debug)]
+struct Family {
+    kind: Kind,
+    samples: BTreeMap<Labels, u64>,
+}
+
+/// Accumulates [`MetricsSnapshot`]s of many microVMs and renders them in the Prometheus text
+/// exposition format.
+#[derive(Clone, Debug, Default)]
+pub struct Exporter {
+    families: BTreeMap<CompactString, Family>,
+}
+
+impl Exporter {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Account a snapshot flushed by the microVM identified by `vm_id`.
+    ///
+    /// Sections that [`MetricsSnapshot`] does not model are ignored.
+    pub fn observe(&mut self, vm_id: &str, snapshot: &MetricsSnapshot) {
+        let sections = match serde_json::to_value(snapshot) {
+            Ok(Value::Object(sections)) => sections,
+            Ok(_) => return,
+            Err(err) => {
+                warn!(%vm_id, error = %err, "failed to serialize metrics snapshot");
+                return;
+            }
+        };
+        for (key, value) in &sections {
+            let Some((section, labels)) = classify(vm_id, &snapshot.devices, key) else {
+                continue;
+            };
+            match value {
+                Value::Object(fields) => self.observe_section(&section, &labels, fields),
+                value => {
+                    if let Some(value) = value.as_u64() {
+                        let name = format_compact!("{NAMESPACE}_{section}");
+                        self.record(name, Kind::Gauge, labels, value);
+                    }
+                }
+            }
+        }
+    }
+
+    fn observe_section(&mut self, section: &str, labels: &Labels, fields: &Map<String, Value>) {
+        for (field, value) in fields {
+            match value {
+                // Latency aggregates, e.g. `{"min_us": 1, "max_us": 9, "sum_us": 20}`
+                Value::Object(stats) => {
+                    for (stat, value) in stats {
+                        let Some(value) = value.as_u64() else {
+                            continue;
+                        };
+                        let kind = if stat == "sum_us" {
+                            Kind::Counter
+                        } else {
+                            Kind::Gauge
+                        };
+                        let name = metric_name(section, &format_compact!("{field}_{stat}"), kind);
+                        self.record(name, kind, labels.clone(), value);
+                    }
+                }
+                value => {
+                    if let Some(value) = value.as_u64() {
+                        let kind = field_kind(section, field);
+                        let name = metric_name(section, field, kind);
+                        self.record(name, kind, labels.clone(), value);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Forget every series of the microVM identified by `vm_id`, e.g., once it has exited.
+    pub fn remove_vm(&mut self, vm_id: &str) {
+        for family in self.families.values_mut() {
+            family
+                .samples
+                .retain(|labels, _| !labels.iter().any(|(k, v)| *k == "vm_id" && v == vm_id));
+        }
+        self.families.retain(|_, family| !family.samples.is_empty());
+    }
+
+    /// Render all series in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for (name, family) in &self.families {
+            let kind = match family.kind {
+                Kind::Counter => "counter",
+                Kind::Gauge => "gauge",
+            };
+            let _ = writeln!(out, "# TYPE {name} {kind}");
+            for (labels, value) in &family.samples {
+                out.push_str(name);
+                out.push('{');
+                for (i, (key, val)) in labels.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    let _ = write!(out, "{key}=\"{}\"", escape_label_value(val));
+                }
+                let _ = writeln!(out, "}} {value}");
+            }
+        }
+        out
+    }
+
+    fn record(&mut self, name: CompactString, kind: Kind, labels: Labels, value: u64) {
+        let family = self.families.entry(name).or_insert_with(|| Family {
+            kind,
+            samples: BTreeMap::new(),
+        });
+        let sample = family.samples.entry(labels).or_default();
+        match kind {
+            Kind::Counter => *sample = sample.saturating_add(value),
+            Kind::Gauge => *sample = value,
+        }
+    }
+}
+
+/// The section name and labels of the series of the top-level section `key` of a snapshot of the
+/// microVM identified by `vm_id`, or `None` if the section is not exported.
+fn classify(vm_id: &str, devices: &DeviceSections, key: &str) -> Option<(CompactString, Labels)> {
+    let mut labels = vec![("vm_id", CompactString::from(vm_id))];
+    // Unparsable device sections end up in `other`, and are not exported either.
+    let (section, label, id) = if let Some(id) = key.strip_prefix("vhost_user_block_") {
+        ("vhost_user_block", "drive", id)
+    } else if let Some(id) = key.strip_prefix("block_") {
+        ("block", "drive", id)
+    } else if let Some(id) = key.strip_prefix("net_") {
+        ("net", "iface", id)
+    } else {
+        return match key {
+            "block" | "net" => None,
+            _ if devices.other.contains_key(key) => None,
+            _ => Some((key.into(), labels)),
+        };
+    };
+    let known = match section {
+        "vhost_user_block" => devices.vhost_user_block.contains_key(id),
+        "block" => devices.block.contains_key(id),
+        _ => devices.net.contains_key(id),
+    };
+    if !known {
+        return None;
+    }
+    labels.push((label, id.into()));
+    Some((section.into(), labels))
+}
+
+/// Whether a field holds a point-in-time value, rather than a delta since the previous flush.
+fn field_kind(section: &str, field: &str) -> Kind {
+    if matches!(section, "latencies_us" | "api_server") || field.ends_with("_time_us") {
+        Kind::Gauge
+    } else {
+        Kind::Counter
+    }
+}
+
+fn metric_name(section: &str, field: &str, kind: Kind) -> CompactString {
+    match kind {
+        Kind::Counter => format_compact!("{NAMESPACE}_{section}_{field}_total"),
+        Kind::Gauge => format_compact!("{NAMESPACE}_{section}_{field}"),
+    }
+}
+
+fn escape_label_value(value: &str) -> CompactString {
+    let mut escaped = CompactString::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Serve `exporter` over HTTP at `GET /metrics`, accepting connections on `listener` until an
+/// error occurs.
+pub async fn serve(listener: TcpListener, exporter: Arc<Mutex<Exporter>>) -> std::io::Result<()> {
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let exporter = Arc::clone(&exporter);
+        ::tokio::spawn(async move {
+            let service = service_fn(move |req| handle(req, Arc::clone(&exporter)));
+            if let Err(err) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                debug!(%peer, error = %err, "metrics connection failed");
+            }
+        });
+    }
+}
+
+async fn handle(
+    req: Request<Incoming>,
+    exporter: Arc<Mutex<Exporter>>,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    if req.method() != Method::GET || req.uri().path() != "/metrics" {
+        let mut resp = Response::new(Full::default());
+        *resp.status_mut() = StatusCode::NOT_FOUND;
+        return Ok(resp);
+    }
+
+    let body = exporter.lock().expect("exporter lock poisoned").render();
+    let mut resp = Response::new(Full::new(Bytes::from(body)));
+    resp.headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static(CONTENT_TYPE_TEXT));
+    Ok(resp)
+}
+
+/// Ask the VMM behind `api` to flush its metrics every `period`, until a request fails.
+///
+/// Returns the error of the failed request; this typically means that the VMM has exited.
+pub async fn flush_periodically<A: Api>(api: &A, period: Duration) -> crate::Error {
+    let mut ticks = interval(period);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        ticks.tick().await;
+        if let Err(err) = api
+            .create_sync_action(InstanceActionInfo::new(ActionType::FlushMetrics))
+            .await
+        {
+            warn!(error = %err, "failed to flush metrics");
+            return err;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::parse_line;
+
+    fn sample(exporter: &Exporter, name: &str, labels: &[(&str, &str)]) -> Option<u64> {
+        let family = exporter.families.get(name)?;
+        family.samples.iter().find_map(|(key, value)| {
+            let matches = key.len() == labels.len()
+                && key
+                    .iter()
+                    .zip(labels)
+                    .all(|((k, v), (l, w))| k == l && v == w);
+            matches.then_some(*value)
+        })
+    }
+
+    #[test]
+    fn accumulates_counters_and_keeps_the_latest_gauges() {
+        let mut exporter = Exporter::new();
+        let first = r#"{"utc_timestamp_ms":1000,"vmm":{"panic_count":1},"api_server":{"process_startup_time_us":300},"latencies_us":{"pause_vm":40},"vcpu":{"exit_io_in_agg":{"min_us":4,"max_us":9,"sum_us":13}}}"#;
+        let second = r#"{"utc_timestamp_ms":2000,"vmm":{"panic_count":2},"api_server":{"process_startup_time_us":200},"latencies_us":{"pause_vm":30},"vcpu":{"exit_io_in_agg":{"min_us":1,"max_us":2,"sum_us":3}}}"#;
+        exporter.observe("vm0", &parse_line(first).unwrap());
+        exporter.observe("vm0", &parse_line(second).unwrap());
+
+        let vm0 = [("vm_id", "vm0")];
+        let get = |name| sample(&exporter, name, &vm0);
+        assert_eq!(get("firecracker_vmm_panic_count_total"), Some(3));
+        assert_eq!(get("firecracker_utc_timestamp_ms"), Some(2000));
+        assert_eq!(
+            get("firecracker_api_server_process_startup_time_us"),
+            Some(200)
+        );
+        assert_eq!(get("firecracker_latencies_us_pause_vm"), Some(30));
+        assert_eq!(get("firecracker_vcpu_exit_io_in_agg_min_us"), Some(1));
+        assert_eq!(get("firecracker_vcpu_exit_io_in_agg_max_us"), Some(2));
+        assert_eq!(
+            get("firecracker_vcpu_exit_io_in_agg_sum_us_total"),
+            Some(16)
+        );
+    }
+
+    #[test]
+    fn exports_devices_but_not_their_aggregates() {
+        let mut exporter = Exporter::new();
+        let line = r#"{"block":{"read_bytes":4096},"block_rootfs":{"read_bytes":4096},"net":{"rx_bytes_count":10},"net_eth0":{"rx_bytes_count":10},"net_eth1":"oops"}"#;
+        exporter.observe("vm0", &parse_line(line).unwrap());
+
+        assert_eq!(
+            sample(
+                &exporter,
+                "firecracker_block_read_bytes_total",
+                &[("vm_id", "vm0"), ("drive", "rootfs")]
+            ),
+            Some(4096)
+        );
+        assert_eq!(
+            sample(
+                &exporter,
+                "firecracker_net_rx_bytes_count_total",
+                &[("vm_id", "vm0"), ("iface", "eth0")]
+            ),
+            Some(10)
+        );
+        for family in [
+            "firecracker_block_read_bytes_total",
+            "firecracker_net_rx_bytes_count_total",
+        ] {
+            assert_eq!(exporter.families[family].samples.len(), 1, "{family}");
+        }
+    }
+
+    /// Whether every family has a series of the microVM identified by `vm_id`.
+    fn labeled(exporter: &Exporter, vm_id: &str) -> bool {
+        exporter.families.values().all(|family| {
+            family
+                .samples
+                .keys()
+                .any(|labels| labels.contains(&("vm_id", vm_id.into())))
+        })
+    }
+
+    #[test]
+    fn labels_every_series_with_its_vm() {
+        let mut exporter = Exporter::new();
+        let line = r#"{"utc_timestamp_ms":1,"block_rootfs":{"read_bytes":1}}"#;
+        exporter.observe("vm0", &parse_line(line).unwrap());
+        exporter.observe("vm1", &parse_line(line).unwrap());
+
+        assert!(labeled(&exporter, "vm0") && labeled(&exporter, "vm1"));
+
+        exporter.remove_vm("vm0");
+        assert!(!exporter.families.is_empty());
+        assert!(labeled(&exporter, "vm1"));
+        assert!(exporter.families.values().all(|family| family
+            .samples
+            .keys()
+            .all(|labels| !labels.contains(&("vm_id", "vm0".into())))));
+    }
+
+    #[test]
+    fn escapes_label_values() {
+        assert_eq!(escape_label_value("plain"), "plain");
+        assert_eq!(escape_label_value("a\\b\"c\nd"), r#"a\\b\"c\nd"#);
+    }
+
+    #[test]
+    fn renders_the_text_exposition_format() {
+        let mut exporter = Exporter::new();
+        let vm = |id: &str| vec![("vm_id", CompactString::from(id))];
+        let mut drive = vm("vm\"0");
+        drive.push(("drive", "rootfs".into()));
+        exporter.record("firecracker_b_total".into(), Kind::Counter, drive, 7);
+        exporter.record("firecracker_a".into(), Kind::Gauge, vm("vm1"), 2);
+        exporter.record("firecracker_a".into(), Kind::Gauge, vm("vm0"), 1);
+
+        assert_eq!(
+            exporter.render(),
+            "# TYPE firecracker_a gauge\n\
+             firecracker_a{vm_id=\"vm0\"} 1\n\
+             firecracker_a{vm_id=\"vm1\"} 2\n\
+             # TYPE firecracker_b_total counter\n\
+             firecracker_b_total{vm_id=\"vm\\\"0\",drive=\"rootfs\"} 7\n"
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use camino::Utf8Path;
+use futures_core::Stream;
+use tokio::fs::File;
+
+use crate::{
+    metrics::{parse_line, Error, MetricsSnapshot},
+    tail::LineTailer,
+};
+
+/// Reads [`MetricsSnapshot`]s off a Firecracker metrics file or named pipe.
+///
+/// The reader is a [`Stream`] of snapshots. Empty lines are skipped, and a line that fails to
+/// parse is reported as an error without ending the stream.
+///
+/// By default the stream ends at end-of-file. When [following](Self::follow), it instead waits
+/// for more data to be written, like `tail -f`; this is also what keeps a reader of a named pipe
+/// alive across Firecracker restarts.
+#[derive(Debug)]
+pub struct MetricsReader {
+    lines: LineTailer,
+}
+
+impl MetricsReader {
+    /// Open the metrics file or named pipe at `path`.
+    ///
+    /// Note that opening a named pipe blocks (a thread of Tokio's blocking pool) until
+    /// Firecracker opens it for writing.
+    pub async fn open(path: impl AsRef<Utf8Path>) -> Result<Self, Error> {
+        let file = File::open(path.as_ref()).await.map_err(Error::Io)?;
+        Ok(Self::new(file))
+    }
+
+    /// Construct a `MetricsReader` reading from an already opened file.
+    #[inline]
+    pub fn new(file: File) -> Self {
+        Self {
+            lines: LineTailer::new(file),
+        }
+    }
+
+    /// Keep waiting for new lines at end-of-file, checking every `poll_interval`.
+    #[inline]
+    pub fn follow(mut self, poll_interval: Duration) -> Self {
+        self.lines.follow(poll_interval);
+        self
+    }
+}
+
+impl Stream for MetricsReader {
+    type Item = Result<MetricsSnapshot, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.lines.poll_next_line(cx).map(|line| match line? {
+            Ok(line) => Some(parse_line(&line)),
+            Err(err) => Some(Err(Error::Io(err))),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn streams_snapshots_until_end_of_file() {
+        let path = std::env::temp_dir().join(format!("wick-metrics-{}", std::process::id()));
+        let lines = "{\"utc_timestamp_ms\":1}\n\n{oops}\r\n{\"utc_timestamp_ms\":2}";
+        std::fs::write(&path, lines).unwrap();
+
+        let file = File::open(&path).await.unwrap();
+        let items: Vec<_> = MetricsReader::new(file).collect().await;
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].as_ref().unwrap().utc_timestamp_ms, 1);
+        assert!(matches!(items[1], Err(Error::Serde(_))));
+        assert_eq!(items[2].as_ref().unwrap().utc_timestamp_ms, 2);
+    }
+}
@@ -0,0 +1,479 @@
+//! Models of the JSON objects Firecracker flushes to its metrics file.
+//!
+//! Counters ([`u64`] fields that are not documented as gauges) hold the number of events since
+//! the previous flush, not since the VMM started. Every struct is tolerant to missing and unknown
+//! fields, so that metrics of older and newer Firecracker releases can still be parsed.
+
+use std::collections::BTreeMap;
+
+use compact_str::CompactString;
+use serde::{
+    de::{MapAccess, Visitor},
+    ser::SerializeMap,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+/// A single flush of Firecracker metrics.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetricsSnapshot {
+    /// Wall-clock time of the flush, in milliseconds since the UNIX epoch.
+    pub utc_timestamp_ms: u64,
+    pub api_server: ApiServerMetrics,
+    pub balloon: BalloonDeviceMetrics,
+    /// Metrics aggregated over all block devices.
+    pub block: BlockDeviceMetrics,
+    pub deprecated_api: DeprecatedApiMetrics,
+    pub get_api_requests: GetRequestsMetrics,
+    pub i8042: I8042DeviceMetrics,
+    pub latencies_us: PerformanceMetrics,
+    pub logger: LoggerSystemMetrics,
+    pub mmds: MmdsMetrics,
+    /// Metrics aggregated over all network interfaces.
+    pub net: NetDeviceMetrics,
+    pub patch_api_requests: PatchRequestsMetrics,
+    pub put_api_requests: PutRequestsMetrics,
+    /// RTC device metrics; only emitted on aarch64.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rtc: Option<RtcDeviceMetrics>,
+    pub seccomp: SeccompMetrics,
+    pub vcpu: VcpuMetrics,
+    pub vmm: VmmMetrics,
+    pub uart: SerialDeviceMetrics,
+    pub signals: SignalMetrics,
+    pub vsock: VsockDeviceMetrics,
+    pub entropy: EntropyDeviceMetrics,
+    pub interrupts: InterruptMetrics,
+    /// Per-device sections, along with any top-level section that is not modeled.
+    #[serde(flatten)]
+    pub devices: DeviceSections,
+}
+
+/// The per-device sections of a [`MetricsSnapshot`], keyed by device ID.
+///
+/// Firecracker emits these as top-level `block_<drive_id>`, `net_<iface_id>` and
+/// `vhost_user_block_<drive_id>` objects.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DeviceSections {
+    /// Block device metrics, keyed by drive ID.
+    pub block: BTreeMap<CompactString, BlockDeviceMetrics>,
+    /// Network device metrics, keyed by interface ID.
+    pub net: BTreeMap<CompactString, NetDeviceMetrics>,
+    /// vhost-user block device metrics, keyed by drive ID.
+    pub vhost_user_block: BTreeMap<CompactString, VhostUserDeviceMetrics>,
+    /// Top-level sections that are not modeled, or failed to parse, kept verbatim.
+    pub other: BTreeMap<CompactString, serde_json::Value>,
+}
+
+const BLOCK_PREFIX: &str = "block_";
+const NET_PREFIX: &str = "net_";
+const VHOST_USER_BLOCK_PREFIX: &str = "vhost_user_block_";
+
+impl DeviceSections {
+    fn insert(&mut self, key: CompactString, value: serde_json::Value) {
+        fn parse<T: for<'de> Deserialize<'de>>(
+            map: &mut BTreeMap<CompactString, T>,
+            id: &str,
+            value: &serde_json::Value,
+        ) -> bool {
+            match T::deserialize(value) {
+                Ok(metrics) => {
+                    map.insert(id.into(), metrics);
+                    true
+                }
+                Err(_) => false,
+            }
+        }
+
+        let parsed = if let Some(id) = key.strip_prefix(VHOST_USER_BLOCK_PREFIX) {
+            parse(&mut self.vhost_user_block, id, &value)
+        } else if let Some(id) = key.strip_prefix(BLOCK_PREFIX) {
+            parse(&mut self.block, id, &value)
+        } else if let Some(id) = key.strip_prefix(NET_PREFIX) {
+            parse(&mut self.net, id, &value)
+        } else {
+            false
+        };
+        if !parsed {
+            self.other.insert(key, value);
+        }
+    }
+}
+
+impl Serialize for DeviceSections {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let len =
+            self.block.len() + self.net.len() + self.vhost_user_block.len() + self.other.len();
+        let mut map = serializer.serialize_map(Some(len))?;
+        for (id, metrics) in &self.block {
+            map.serialize_entry(&format!("{BLOCK_PREFIX}{id}"), metrics)?;
+        }
+        for (id, metrics) in &self.net {
+            map.serialize_entry(&format!("{NET_PREFIX}{id}"), metrics)?;
+        }
+        for (id, metrics) in &self.vhost_user_block {
+            map.serialize_entry(&format!("{VHOST_USER_BLOCK_PREFIX}{id}"), metrics)?;
+        }
+        for (key, value) in &self.other {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for DeviceSections {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct SectionsVisitor;
+
+        impl<'de> Visitor<'de> for SectionsVisitor {
+            type Value = DeviceSections;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a map of metrics sections")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+                let mut sections = DeviceSections::default();
+                while let Some((key, value)) = access.next_entry()? {
+                    sections.insert(key, value);
+                }
+                Ok(sections)
+            }
+        }
+
+        deserializer.deserialize_map(SectionsVisitor)
+    }
+}
+
+/// Minimum, maximum and sum of the latencies observed since the previous flush.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LatencyAggregateMetrics {
+    pub min_us: u64,
+    pub max_us: u64,
+    pub sum_us: u64,
+}
+
+/// API server metrics.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiServerMetrics {
+    /// Wall-clock time it took the process to start, in microseconds (gauge).
+    pub process_startup_time_us: u64,
+    /// CPU time it took the process to start, in microseconds (gauge).
+    pub process_startup_time_cpu_us: u64,
+}
+
+/// Balloon device metrics.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BalloonDeviceMetrics {
+    pub activate_fails: u64,
+    pub inflate_count: u64,
+    pub stats_updates_count: u64,
+    pub stats_update_fails: u64,
+    pub deflate_count: u64,
+    pub event_fails: u64,
+}
+
+/// Block device metrics, either for a single drive or aggregated over all of them.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BlockDeviceMetrics {
+    pub activate_fails: u64,
+    pub cfg_fails: u64,
+    pub no_avail_buffer: u64,
+    pub event_fails: u64,
+    pub execute_fails: u64,
+    pub invalid_reqs_count: u64,
+    pub flush_count: u64,
+    pub queue_event_count: u64,
+    pub rate_limiter_event_count: u64,
+    pub update_count: u64,
+    pub update_fails: u64,
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+    pub read_count: u64,
+    pub write_count: u64,
+    pub read_agg: LatencyAggregateMetrics,
+    pub write_agg: LatencyAggregateMetrics,
+    pub rate_limiter_throttled_events: u64,
+    pub io_engine_throttled_events: u64,
+    pub remaining_reqs_count: u64,
+}
+
+/// Metrics of deprecated API usage.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeprecatedApiMetrics {
+    pub deprecated_http_api_calls: u64,
+    pub deprecated_cmd_line_api_calls: u64,
+}
+
+/// Metrics of `GET` API requests.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GetRequestsMetrics {
+    pub instance_info_count: u64,
+    pub machine_cfg_count: u64,
+    pub mmds_count: u64,
+    pub vmm_version_count: u64,
+}
+
+/// i8042 device metrics.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct I8042DeviceMetrics {
+    pub error_count: u64,
+    pub missed_read_count: u64,
+    pub missed_write_count: u64,
+    pub read_count: u64,
+    pub reset_count: u64,
+    pub write_count: u64,
+}
+
+/// Latencies of snapshot and pause/resume operations, in microseconds (gauges).
+///
+/// The `vmm_`-prefixed variants only account for the time spent in the VMM thread.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PerformanceMetrics {
+    pub full_create_snapshot: u64,
+    pub diff_create_snapshot: u64,
+    pub load_snapshot: u64,
+    pub pause_vm: u64,
+    pub resume_vm: u64,
+    pub vmm_full_create_snapshot: u64,
+    pub vmm_diff_create_snapshot: u64,
+    pub vmm_load_snapshot: u64,
+    pub vmm_pause_vm: u64,
+    pub vmm_resume_vm: u64,
+}
+
+/// Metrics of the logging subsystem.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggerSystemMetrics {
+    pub missed_metrics_count: u64,
+    pub metrics_fails: u64,
+    pub missed_log_count: u64,
+    pub log_fails: u64,
+}
+
+/// MMDS metrics.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MmdsMetrics {
+    pub rx_accepted: u64,
+    pub rx_accepted_err: u64,
+    pub rx_accepted_unusual: u64,
+    pub rx_bad_eth: u64,
+    pub rx_invalid_token: u64,
+    pub rx_no_token: u64,
+    pub rx_count: u64,
+    pub tx_bytes: u64,
+    pub tx_count: u64,
+    pub tx_errors: u64,
+    pub tx_frames: u64,
+    pub connections_created: u64,
+    pub connections_destroyed: u64,
+}
+
+/// Network device metrics, either for a single interface or aggregated over all of them.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetDeviceMetrics {
+    pub activate_fails: u64,
+    pub cfg_fails: u64,
+    pub mac_address_updates: u64,
+    pub no_rx_avail_buffer: u64,
+    pub no_tx_avail_buffer: u64,
+    pub event_fails: u64,
+    pub rx_queue_event_count: u64,
+    pub rx_event_rate_limiter_count: u64,
+    pub rx_rate_limiter_throttled: u64,
+    pub rx_tap_event_count: u64,
+    pub rx_bytes_count: u64,
+    pub rx_packets_count: u64,
+    pub rx_fails: u64,
+    pub rx_count: u64,
+    pub tap_read_fails: u64,
+    pub tap_write_fails: u64,
+    pub tap_write_agg: LatencyAggregateMetrics,
+    pub tx_bytes_count: u64,
+    pub tx_malformed_frames: u64,
+    pub tx_fails: u64,
+    pub tx_count: u64,
+    pub tx_packets_count: u64,
+    pub tx_partial_reads: u64,
+    pub tx_queue_event_count: u64,
+    pub tx_rate_limiter_event_count: u64,
+    pub tx_rate_limiter_throttled: u64,
+    pub tx_spoofed_mac_count: u64,
+    pub tx_remaining_reqs_count: u64,
+}
+
+/// Metrics of `PATCH` API requests.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PatchRequestsMetrics {
+    pub drive_count: u64,
+    pub drive_fails: u64,
+    pub network_count: u64,
+    pub network_fails: u64,
+    pub machine_cfg_count: u64,
+    pub machine_cfg_fails: u64,
+    pub mmds_count: u64,
+    pub mmds_fails: u64,
+}
+
+/// Metrics of `PUT` API requests.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PutRequestsMetrics {
+    pub actions_count: u64,
+    pub actions_fails: u64,
+    pub boot_source_count: u64,
+    pub boot_source_fails: u64,
+    pub drive_count: u64,
+    pub drive_fails: u64,
+    pub logger_count: u64,
+    pub logger_fails: u64,
+    pub machine_cfg_count: u64,
+    pub machine_cfg_fails: u64,
+    pub cpu_cfg_count: u64,
+    pub cpu_cfg_fails: u64,
+    pub metrics_count: u64,
+    pub metrics_fails: u64,
+    pub network_count: u64,
+    pub network_fails: u64,
+    pub mmds_count: u64,
+    pub mmds_fails: u64,
+    pub vsock_count: u64,
+    pub vsock_fails: u64,
+}
+
+/// RTC device metrics (aarch64).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RtcDeviceMetrics {
+    pub error_count: u64,
+    pub missed_read_count: u64,
+    pub missed_write_count: u64,
+}
+
+/// Seccomp filtering metrics.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SeccompMetrics {
+    pub num_faults: u64,
+}
+
+/// vCPU metrics, aggregated over all vCPUs.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VcpuMetrics {
+    pub exit_io_in: u64,
+    pub exit_io_out: u64,
+    pub exit_mmio_read: u64,
+    pub exit_mmio_write: u64,
+    pub failures: u64,
+    pub exit_io_in_agg: LatencyAggregateMetrics,
+    pub exit_io_out_agg: LatencyAggregateMetrics,
+    pub exit_mmio_read_agg: LatencyAggregateMetrics,
+    pub exit_mmio_write_agg: LatencyAggregateMetrics,
+}
+
+/// VMM thread metrics.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VmmMetrics {
+    pub device_events: u64,
+    pub panic_count: u64,
+}
+
+/// Serial (UART) device metrics.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SerialDeviceMetrics {
+    pub error_count: u64,
+    pub flush_count: u64,
+    pub missed_read_count: u64,
+    pub missed_write_count: u64,
+    pub read_count: u64,
+    pub write_count: u64,
+}
+
+/// Number of signals caught by the VMM.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SignalMetrics {
+    pub sigbus: u64,
+    pub sigsegv: u64,
+    pub sigxfsz: u64,
+    pub sigxcpu: u64,
+    pub sigpipe: u64,
+    pub sighup: u64,
+    pub sigill: u64,
+}
+
+/// Vsock device metrics.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VsockDeviceMetrics {
+    pub activate_fails: u64,
+    pub cfg_fails: u64,
+    pub rx_queue_event_fails: u64,
+    pub tx_queue_event_fails: u64,
+    pub ev_queue_event_fails: u64,
+    pub muxer_event_fails: u64,
+    pub conn_event_fails: u64,
+    pub rx_queue_event_count: u64,
+    pub tx_queue_event_count: u64,
+    pub rx_bytes_count: u64,
+    pub tx_bytes_count: u64,
+    pub rx_packets_count: u64,
+    pub tx_packets_count: u64,
+    pub conns_added: u64,
+    pub conns_killed: u64,
+    pub conns_removed: u64,
+    pub killq_resync: u64,
+    pub tx_flush_fails: u64,
+    pub tx_write_fails: u64,
+    pub rx_read_fails: u64,
+}
+
+/// Entropy device metrics.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EntropyDeviceMetrics {
+    pub activate_fails: u64,
+    pub entropy_event_fails: u64,
+    pub entropy_event_count: u64,
+    pub entropy_bytes: u64,
+    pub host_rng_fails: u64,
+    pub entropy_rate_limiter_throttled: u64,
+    pub rate_limiter_event_count: u64,
+}
+
+/// Interrupt metrics.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InterruptMetrics {
+    pub triggers: u64,
+    pub config_updates: u64,
+}
+
+/// vhost-user device metrics.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VhostUserDeviceMetrics {
+    pub activate_fails: u64,
+    pub cfg_fails: u64,
+    /// Time it took to initialize the device, in microseconds (gauge).
+    pub init_time_us: u64,
+    /// Time it took to activate the device, in microseconds (gauge).
+    pub activate_time_us: u64,
+    /// Time it took to apply the latest configuration change, in microseconds (gauge).
+    pub config_change_time_us: u64,
+}
@@ -1,25 +1,41 @@
 use serde::{Deserialize, Serialize};
 
+use crate::models::{Extras, MiB};
+
 /// Balloon device descriptor.
-#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Balloon {
     /// Target balloon size in MiB.
-    pub amount_mib: i32,
+    pub amount_mib: MiB,
     /// Whether the balloon should deflate when the guest has memory pressure.
     pub deflate_on_oom: bool,
     /// Interval in seconds between refreshing statistics. A non-zero value will enable the
     /// statistics. Defaults to 0.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stats_polling_interval_s: Option<i32>,
+    /// Whether the guest should hint free pages to the device during hinting runs, so that they
+    /// can be reclaimed by the host. Requires Firecracker v1.14.0 or later.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub free_page_hinting: Option<bool>,
+    /// Whether the guest should continuously report free pages to the device, so that they can be
+    /// reclaimed by the host. Requires Firecracker v1.14.0 or later.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub free_page_reporting: Option<bool>,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
 }
 
 impl Balloon {
     #[inline]
-    pub fn new(amount_mib: i32, deflate_on_oom: bool) -> Self {
+    pub fn new(amount_mib: MiB, deflate_on_oom: bool) -> Self {
         Self {
             amount_mib,
             deflate_on_oom,
             stats_polling_interval_s: None,
+            free_page_hinting: None,
+            free_page_reporting: None,
+            extras: Default::default(),
         }
     }
 }
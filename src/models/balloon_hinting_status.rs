@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::Extras;
+
+/// Describes the progress of the current free page hinting run.
+///
+/// Requires Firecracker v1.14.0 or later.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BalloonHintingStatus {
+    /// The command ID last sent by the device to the guest.
+    pub host_cmd: u32,
+    /// The command ID last acknowledged by the guest, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guest_cmd: Option<u32>,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
+}
+
+impl BalloonHintingStatus {
+    /// The command ID the guest reports once it has stopped hinting.
+    pub const CMD_ID_STOP: u32 = 0;
+
+    /// Whether the guest has acknowledged the current command, i.e. started hinting.
+    #[inline]
+    pub fn is_acknowledged(&self) -> bool {
+        self.guest_cmd == Some(self.host_cmd)
+    }
+
+    /// Whether the guest reports that it stopped hinting.
+    ///
+    /// A run is only done if the guest stops after acknowledging its command; a guest that has
+    /// not acknowledged the current command yet may still report having stopped a previous run.
+    #[inline]
+    pub fn is_stopped(&self) -> bool {
+        self.guest_cmd == Some(Self::CMD_ID_STOP)
+    }
+}
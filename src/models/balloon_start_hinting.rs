@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::Extras;
+
+/// Starts a free page hinting run.
+///
+/// Requires Firecracker v1.14.0 or later.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BalloonStartHinting {
+    /// Whether the device should acknowledge the pages hinted by the guest, i.e. let the guest
+    /// reuse them, only once the run is stopped. Defaults to true.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acknowledge_on_stop: Option<bool>,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
+}
+
+impl BalloonStartHinting {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
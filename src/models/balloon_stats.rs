@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
 
+use crate::models::Extras;
+
 /// Describes the balloon device statistics.
-#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct BalloonStats {
     /// Target number of pages the device aims to hold.
     pub target_pages: i32,
@@ -43,6 +45,9 @@ pub struct BalloonStats {
     /// The number of failed hugetlb page allocations in the guest.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub hugetlb_failures: Option<i64>,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
 }
 
 impl BalloonStats {
@@ -62,6 +67,7 @@ impl BalloonStats {
             disk_caches: None,
             hugetlb_allocations: None,
             hugetlb_failures: None,
+            extras: Default::default(),
         }
     }
 }
@@ -1,11 +1,16 @@
 use serde::{Deserialize, Serialize};
 
+use crate::models::Extras;
+
 /// Update the statistics polling interval, with the first statistics update scheduled immediately.
 /// Statistics cannot be turned on/off after boot.
-#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct BalloonStatsUpdate {
     /// Interval in seconds between refreshing statistics.
     pub stats_polling_interval_s: i32,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
 }
 
 impl BalloonStatsUpdate {
@@ -13,6 +18,7 @@ impl BalloonStatsUpdate {
     pub fn new(stats_polling_interval_s: i32) -> Self {
         Self {
             stats_polling_interval_s,
+            extras: Default::default(),
         }
     }
 }
@@ -1,15 +1,23 @@
 use serde::{Deserialize, Serialize};
 
+use crate::models::{Extras, MiB};
+
 /// Balloon device descriptor.
-#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct BalloonUpdate {
     /// Target balloon size in MiB.
-    pub amount_mib: i32,
+    pub amount_mib: MiB,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
 }
 
 impl BalloonUpdate {
     #[inline]
-    pub fn new(amount_mib: i32) -> Self {
-        Self { amount_mib }
+    pub fn new(amount_mib: MiB) -> Self {
+        Self {
+            amount_mib,
+            extras: Default::default(),
+        }
     }
 }
@@ -2,6 +2,8 @@ use camino::Utf8PathBuf;
 use compact_str::CompactString;
 use serde::{Deserialize, Serialize};
 
+use crate::models::Extras;
+
 /// Boot source descriptor.
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct BootSource {
@@ -13,6 +15,9 @@ pub struct BootSource {
     pub initrd_path: Option<Utf8PathBuf>,
     /// Host level path to the kernel image used to boot the guest
     pub kernel_image_path: Utf8PathBuf,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
 }
 
 impl BootSource {
@@ -22,6 +27,7 @@ impl BootSource {
             boot_args: None,
             initrd_path: None,
             kernel_image_path: kernel_image_path.into(),
+            extras: Default::default(),
         }
     }
 }
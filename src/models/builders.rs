@@ -0,0 +1,440 @@
+//! Fluent builders for the models.
+//!
+//! Each builder starts from the fields Firecracker requires, passed to `builder()`, and sets the
+//! optional ones one at a time. Unlike a struct literal, a chain of setters keeps compiling when a
+//! newer Firecracker release adds a field to the model. Setters that take a model also take its
+//! builder, so nested models need no `build()` of their own.
+//!
+//! [`RateLimiter`]s are built out of rates in human units by
+//! [`RateLimiterBuilder`](crate::rate_limit::RateLimiterBuilder) instead.
+//!
+//! # Example
+//!
+//! ```
+//! use wick::models::{
+//!     Drive, FullVmConfiguration, MachineConfiguration, MiB, NetworkInterface, VcpuCount,
+//! };
+//!
+//! # fn main() -> Result<(), wick::models::types::InvalidValue> {
+//! let config = FullVmConfiguration::builder()
+//!     .machine_config(MachineConfiguration::builder(MiB(1024), VcpuCount::new(2)?).smt(false))
+//!     .drive(
+//!         Drive::builder("rootfs".parse()?, true)
+//!             .path_on_host("/srv/vm/rootfs.ext4")
+//!             .is_read_only(false),
+//!     )
+//!     .network_interface(
+//!         NetworkInterface::builder("tap0", "eth0".parse()?).guest_mac([6, 0, 172, 16, 0, 2]),
+//!     )
+//!     .build();
+//!
+//! let drives = config.drives.as_deref().unwrap_or_default();
+//! assert_eq!(drives[0].path_on_host.as_deref(), Some("/srv/vm/rootfs.ext4".into()));
+//! assert_eq!(config.machine_config.unwrap().smt, Some(false));
+//! # Ok(())
+//! # }
+//! ```
+
+use camino::Utf8PathBuf;
+use compact_str::CompactString;
+use serde_json::Value;
+
+use crate::models::{
+    self, drive::CacheType, drive::IoEngine, logger::Level, machine_configuration::HugePages,
+    mmds_config::Version, snapshot_create_params::SnapshotType, Balloon, BalloonStartHinting,
+    BalloonStats, BalloonStatsUpdate, BalloonUpdate, BootSource, CpuConfig, CpuTemplate,
+    CpuidLeafModifier, CpuidRegisterModifier, Drive, EntropyDevice, FirecrackerVersion,
+    FullVmConfiguration, InstanceActionInfo, InstanceInfo, KvmCapability, Logger, MacAddress,
+    MachineConfiguration, MemoryBackend, MemoryHotplugConfig, MemoryHotplugSizeUpdate, Metrics,
+    MiB, MmdsConfig, MsrModifier, NetworkInterface, NetworkOverride, PartialDrive,
+    PartialNetworkInterface, Pmem, RateLimiter, RegModifier, Serial, SnapshotCreateParams,
+    SnapshotLoadParams, TokenBucket, VcpuFeature, VhostUserBlock, VirtioBlock, Vm, Vsock,
+};
+
+/// A setter of a builder, by the kind of field it sets:
+///
+/// - `opt`: an `Option<T>`;
+/// - `boxed`: an `Option<Box<T>>`;
+/// - `set`: a plain `T`;
+/// - `push`: a `Vec<T>`, one element at a time;
+/// - `each`: an `Option<Vec<T>>`, one element at a time.
+macro_rules! setter {
+    ($model:ident, opt $field:ident: $ty:ty) => {
+        #[doc = concat!("Sets [`", stringify!($model), "::", stringify!($field), "`].")]
+        #[inline]
+        pub fn $field(mut self, $field: impl Into<$ty>) -> Self {
+            self.0.$field = Some($field.into());
+            self
+        }
+    };
+    ($model:ident, boxed $field:ident: $ty:ty) => {
+        #[doc = concat!("Sets [`", stringify!($model), "::", stringify!($field), "`].")]
+        #[inline]
+        pub fn $field(mut self, $field: impl Into<$ty>) -> Self {
+            self.0.$field = Some(Box::new($field.into()));
+            self
+        }
+    };
+    ($model:ident, set $field:ident: $ty:ty) => {
+        #[doc = concat!("Sets [`", stringify!($model), "::", stringify!($field), "`].")]
+        #[inline]
+        pub fn $field(mut self, $field: impl Into<$ty>) -> Self {
+            self.0.$field = $field.into();
+            self
+        }
+    };
+    ($model:ident, push $field:ident => $setter:ident: $ty:ty) => {
+        #[doc = concat!("Appends to [`", stringify!($model), "::", stringify!($field), "`].")]
+        #[inline]
+        pub fn $setter(mut self, $setter: impl Into<$ty>) -> Self {
+            self.0.$field.push($setter.into());
+            self
+        }
+    };
+    ($model:ident, each $field:ident => $setter:ident: $ty:ty) => {
+        #[doc = concat!("Appends to [`", stringify!($model), "::", stringify!($field), "`].")]
+        #[inline]
+        pub fn $setter(mut self, $setter: impl Into<$ty>) -> Self {
+            self.0
+                .$field
+                .get_or_insert_with(Vec::new)
+                .push($setter.into());
+            self
+        }
+    };
+}
+
+/// Define the builder of a model, its `builder()` constructor out of the required fields, and a
+/// setter per optional field.
+macro_rules! builder {
+    (
+        $model:ident => $builder:ident($($arg:ident: $arg_ty:ty),*) = $init:expr;
+        $($kind:ident $field:ident $(=> $setter:ident)?: $ty:ty),* $(,)?
+    ) => {
+        #[doc = concat!("Builds a [`", stringify!($model), "`]; see [`", stringify!($model), "::builder`].")]
+        #[derive(Clone, Debug)]
+        #[must_use]
+        pub struct $builder($model);
+
+        impl $model {
+            #[doc = concat!("Start building a [`", stringify!($model), "`] out of its required fields.")]
+            #[inline]
+            pub fn builder($($arg: $arg_ty),*) -> $builder {
+                $builder($init)
+            }
+        }
+
+        impl $builder {
+            $(setter!($model, $kind $field $(=> $setter)?: $ty);)*
+
+            /// Sets a field unknown to this crate, e.g. one added by a newer Firecracker release.
+            #[inline]
+            pub fn extra(mut self, name: impl Into<CompactString>, value: impl Into<Value>) -> Self {
+                self.0.extras.insert(name, value.into());
+                self
+            }
+
+            #[inline]
+            pub fn build(self) -> $model {
+                self.0
+            }
+        }
+
+        impl From<$builder> for $model {
+            #[inline]
+            fn from(builder: $builder) -> Self {
+                builder.0
+            }
+        }
+    };
+}
+
+builder! {
+    Balloon => BalloonBuilder(amount_mib: MiB, deflate_on_oom: bool)
+        = Balloon::new(amount_mib, deflate_on_oom);
+    opt stats_polling_interval_s: i32,
+    opt free_page_hinting: bool,
+    opt free_page_reporting: bool,
+}
+
+builder! {
+    BalloonStartHinting => BalloonStartHintingBuilder() = BalloonStartHinting::new();
+    opt acknowledge_on_stop: bool,
+}
+
+builder! {
+    BalloonStats => BalloonStatsBuilder(
+        target_pages: i32,
+        actual_pages: i32,
+        target_mib: i32,
+        actual_mib: i32
+    ) = BalloonStats::new(target_pages, actual_pages, target_mib, actual_mib);
+    opt swap_in: i64,
+    opt swap_out: i64,
+    opt major_faults: i64,
+    opt minor_faults: i64,
+    opt free_memory: i64,
+    opt total_memory: i64,
+    opt available_memory: i64,
+    opt disk_caches: i64,
+    opt hugetlb_allocations: i64,
+    opt hugetlb_failures: i64,
+}
+
+builder! {
+    BalloonStatsUpdate => BalloonStatsUpdateBuilder(stats_polling_interval_s: i32)
+        = BalloonStatsUpdate::new(stats_polling_interval_s);
+}
+
+builder! {
+    BalloonUpdate => BalloonUpdateBuilder(amount_mib: MiB) = BalloonUpdate::new(amount_mib);
+}
+
+builder! {
+    BootSource => BootSourceBuilder(kernel_image_path: impl Into<Utf8PathBuf>)
+        = BootSource::new(kernel_image_path);
+    opt boot_args: CompactString,
+    opt initrd_path: Utf8PathBuf,
+}
+
+builder! {
+    CpuConfig => CpuConfigBuilder() = CpuConfig::default();
+    each cpuid_modifiers => cpuid_modifier: CpuidLeafModifier,
+    each msr_modifiers => msr_modifier: MsrModifier,
+    each reg_modifiers => reg_modifier: RegModifier,
+    each vcpu_features => vcpu_feature: VcpuFeature,
+    each kvm_capabilities => kvm_capability: KvmCapability,
+}
+
+builder! {
+    CpuidLeafModifier => CpuidLeafModifierBuilder(leaf: u32, subleaf: u32, flags: u32)
+        = CpuidLeafModifier::new(leaf, subleaf, flags);
+    push modifiers => modifier: CpuidRegisterModifier,
+}
+
+builder! {
+    CpuidRegisterModifier => CpuidRegisterModifierBuilder(
+        register: models::CpuidRegister,
+        bitmap: models::Bitmap
+    ) = CpuidRegisterModifier::new(register, bitmap);
+}
+
+builder! {
+    Drive => DriveBuilder(drive_id: models::DriveId, is_root_device: bool)
+        = Drive::new(drive_id, is_root_device);
+    opt partuuid: String,
+    opt cache_type: CacheType,
+    opt is_read_only: bool,
+    opt path_on_host: Utf8PathBuf,
+    boxed rate_limiter: RateLimiter,
+    opt io_engine: IoEngine,
+    opt socket: Utf8PathBuf,
+}
+
+builder! {
+    EntropyDevice => EntropyDeviceBuilder() = EntropyDevice::default();
+    boxed rate_limiter: RateLimiter,
+}
+
+builder! {
+    FirecrackerVersion => FirecrackerVersionBuilder(
+        firecracker_version: impl Into<CompactString>
+    ) = FirecrackerVersion::new(firecracker_version);
+}
+
+builder! {
+    FullVmConfiguration => FullVmConfigurationBuilder() = FullVmConfiguration::default();
+    boxed balloon: Balloon,
+    each drives => drive: Drive,
+    boxed boot_source: BootSource,
+    boxed cpu_config: CpuConfig,
+    boxed logger: Logger,
+    boxed machine_config: MachineConfiguration,
+    boxed metrics: Metrics,
+    boxed mmds_config: MmdsConfig,
+    each network_interfaces => network_interface: NetworkInterface,
+    boxed vsock: Vsock,
+    boxed entropy: EntropyDevice,
+    boxed serial: Serial,
+    boxed memory_hotplug: MemoryHotplugConfig,
+    each pmem => pmem: Pmem,
+}
+
+builder! {
+    InstanceActionInfo => InstanceActionInfoBuilder(
+        action_type: models::instance_action_info::ActionType
+    ) = InstanceActionInfo::new(action_type);
+}
+
+builder! {
+    InstanceInfo => InstanceInfoBuilder(
+        app_name: impl Into<CompactString>,
+        id: impl Into<CompactString>,
+        state: models::instance_info::State,
+        vmm_version: impl Into<CompactString>
+    ) = InstanceInfo::new(app_name, id, state, vmm_version);
+}
+
+builder! {
+    Logger => LoggerBuilder() = Logger::default();
+    opt level: Level,
+    opt log_path: Utf8PathBuf,
+    opt show_level: bool,
+    opt show_log_origin: bool,
+    opt module: CompactString,
+}
+
+builder! {
+    MachineConfiguration => MachineConfigurationBuilder(
+        mem_size_mib: MiB,
+        vcpu_count: models::VcpuCount
+    ) = MachineConfiguration::new(mem_size_mib, vcpu_count);
+    opt cpu_template: CpuTemplate,
+    opt smt: bool,
+    opt track_dirty_pages: bool,
+    opt huge_pages: HugePages,
+}
+
+builder! {
+    MemoryBackend => MemoryBackendBuilder(
+        backend_type: models::memory_backend::BackendType,
+        backend_path: impl Into<Utf8PathBuf>
+    ) = MemoryBackend::new(backend_type, backend_path);
+}
+
+builder! {
+    MemoryHotplugConfig => MemoryHotplugConfigBuilder(total_size_mib: MiB)
+        = MemoryHotplugConfig::new(total_size_mib);
+    opt slot_size_mib: MiB,
+    opt block_size_mib: MiB,
+}
+
+builder! {
+    MemoryHotplugSizeUpdate => MemoryHotplugSizeUpdateBuilder(requested_size_mib: MiB)
+        = MemoryHotplugSizeUpdate::new(requested_size_mib);
+}
+
+builder! {
+    Metrics => MetricsBuilder(metrics_path: impl Into<Utf8PathBuf>) = Metrics::new(metrics_path);
+}
+
+builder! {
+    MmdsConfig => MmdsConfigBuilder(network_interfaces: Vec<models::IfaceId>)
+        = MmdsConfig::new(network_interfaces);
+    opt version: Version,
+    set imds_compat: bool,
+}
+
+builder! {
+    MsrModifier => MsrModifierBuilder(addr: u32, bitmap: models::Bitmap)
+        = MsrModifier::new(addr, bitmap);
+}
+
+builder! {
+    NetworkInterface => NetworkInterfaceBuilder(
+        host_dev_name: impl Into<CompactString>,
+        iface_id: models::IfaceId
+    ) = NetworkInterface::new(host_dev_name, iface_id);
+    opt guest_mac: MacAddress,
+    boxed rx_rate_limiter: RateLimiter,
+    boxed tx_rate_limiter: RateLimiter,
+}
+
+builder! {
+    NetworkOverride => NetworkOverrideBuilder(
+        iface_id: models::IfaceId,
+        host_dev_name: impl Into<CompactString>
+    ) = NetworkOverride::new(iface_id, host_dev_name);
+}
+
+builder! {
+    PartialDrive => PartialDriveBuilder(drive_id: models::DriveId) = PartialDrive::new(drive_id);
+    opt path_on_host: Utf8PathBuf,
+    boxed rate_limiter: RateLimiter,
+}
+
+builder! {
+    PartialNetworkInterface => PartialNetworkInterfaceBuilder(iface_id: models::IfaceId)
+        = PartialNetworkInterface::new(iface_id);
+    boxed rx_rate_limiter: RateLimiter,
+    boxed tx_rate_limiter: RateLimiter,
+}
+
+builder! {
+    Pmem => PmemBuilder(id: models::PmemId, path_on_host: impl Into<Utf8PathBuf>)
+        = Pmem::new(id, path_on_host);
+    opt root_device: bool,
+    opt read_only: bool,
+}
+
+builder! {
+    RegModifier => RegModifierBuilder(addr: u64, bitmap: models::Bitmap)
+        = RegModifier::new(addr, bitmap);
+}
+
+builder! {
+    Serial => SerialBuilder() = Serial::new();
+    opt serial_out_path: Utf8PathBuf,
+}
+
+builder! {
+    SnapshotCreateParams => SnapshotCreateParamsBuilder(
+        mem_file_path: impl Into<Utf8PathBuf>,
+        snapshot_path: impl Into<Utf8PathBuf>
+    ) = SnapshotCreateParams::new(mem_file_path, snapshot_path);
+    opt snapshot_type: SnapshotType,
+}
+
+builder! {
+    SnapshotLoadParams => SnapshotLoadParamsBuilder(snapshot_path: impl Into<Utf8PathBuf>)
+        = SnapshotLoadParams::new(snapshot_path);
+    opt track_dirty_pages: bool,
+    opt mem_file_path: Utf8PathBuf,
+    opt mem_backend: MemoryBackend,
+    opt resume_vm: bool,
+    each network_overrides => network_override: NetworkOverride,
+}
+
+builder! {
+    TokenBucket => TokenBucketBuilder(refill_time: i64, size: i64)
+        = TokenBucket::new(refill_time, size);
+    opt one_time_burst: i64,
+}
+
+builder! {
+    VcpuFeature => VcpuFeatureBuilder(index: u32, bitmap: models::Bitmap)
+        = VcpuFeature::new(index, bitmap);
+}
+
+builder! {
+    VhostUserBlock => VhostUserBlockBuilder(
+        drive_id: models::DriveId,
+        socket: impl Into<Utf8PathBuf>,
+        is_root_device: bool
+    ) = VhostUserBlock::new(drive_id, socket, is_root_device);
+    opt partuuid: String,
+    opt cache_type: CacheType,
+}
+
+builder! {
+    VirtioBlock => VirtioBlockBuilder(
+        drive_id: models::DriveId,
+        path_on_host: impl Into<Utf8PathBuf>,
+        is_root_device: bool,
+        is_read_only: bool
+    ) = VirtioBlock::new(drive_id, path_on_host, is_root_device, is_read_only);
+    opt partuuid: String,
+    opt cache_type: CacheType,
+    boxed rate_limiter: RateLimiter,
+    opt io_engine: IoEngine,
+}
+
+builder! {
+    Vm => VmBuilder(state: models::vm::State) = Vm::new(state);
+}
+
+builder! {
+    Vsock => VsockBuilder(guest_cid: models::VsockCid, uds_path: impl Into<Utf8PathBuf>)
+        = Vsock::new(guest_cid, uds_path);
+    opt vsock_id: CompactString,
+}
@@ -0,0 +1,243 @@
+use std::{fmt, str::FromStr};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Largest register modified by a custom CPU template, in bits (aarch64 vector registers).
+pub const MAX_WIDTH: u8 = 128;
+
+/// A bitmap of a custom CPU template, e.g. `0bxxxx_xx1x_0xxx`, that sets the bits written as
+/// `0` or `1` and leaves the ones written as `x` untouched. The rightmost character is bit 0, and
+/// `_` may be used as a separator.
+///
+/// # Example
+///
+/// ```
+/// use wick::models::Bitmap;
+///
+/// # fn main() -> Result<(), wick::models::cpu_bitmap::BitmapError> {
+/// let bitmap: Bitmap = "0bxx1x_0xxx".parse()?;
+/// assert_eq!(bitmap.get(5), Some(true));
+/// assert_eq!(bitmap.get(3), Some(false));
+/// assert_eq!(bitmap.apply(0b1111_1111), 0b1111_0111);
+///
+/// let merged = bitmap.merge(&"0bxx0x_xxx1".parse()?);
+/// assert_eq!(merged.to_string(), "0bxx0x0xx1");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Bitmap {
+    width: u8,
+    /// Bits that the bitmap sets.
+    filter: u128,
+    /// Values of the bits that the bitmap sets.
+    value: u128,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BitmapError {
+    #[error("bitmap must start with '0b'")]
+    MissingPrefix,
+
+    #[error("invalid bitmap character '{0}', expected '0', '1', 'x' or '_'")]
+    InvalidChar(char),
+
+    #[error("bitmap of {width} bits does not fit in a {max}-bit register")]
+    TooWide { width: usize, max: u8 },
+
+    #[error("bit {bit} is out of the bitmap's {width} bits")]
+    OutOfRange { bit: u8, width: u8 },
+}
+
+impl Bitmap {
+    /// A bitmap of `width` bits that leaves every bit untouched.
+    ///
+    /// # Panics
+    ///
+    /// If `width` exceeds [`MAX_WIDTH`].
+    #[inline]
+    pub const fn new(width: u8) -> Self {
+        assert!(width <= MAX_WIDTH, "bitmap is too wide");
+        Self {
+            width,
+            filter: 0,
+            value: 0,
+        }
+    }
+
+    /// A bitmap of `width` bits setting the bits of `filter` to their value in `value`.
+    pub fn from_parts(width: u8, filter: u128, value: u128) -> Result<Self, BitmapError> {
+        if width > MAX_WIDTH {
+            return Err(BitmapError::TooWide {
+                width: width.into(),
+                max: MAX_WIDTH,
+            });
+        }
+        if let Some(bit) = highest_bit(filter).filter(|&bit| bit >= width) {
+            return Err(BitmapError::OutOfRange { bit, width });
+        }
+        Ok(Self {
+            width,
+            filter,
+            value: value & filter,
+        })
+    }
+
+    #[inline]
+    pub fn width(&self) -> u8 {
+        self.width
+    }
+
+    /// Mask of the bits that the bitmap sets.
+    #[inline]
+    pub fn filter(&self) -> u128 {
+        self.filter
+    }
+
+    /// Values of the bits that the bitmap sets; bits left untouched are zero.
+    #[inline]
+    pub fn value(&self) -> u128 {
+        self.value
+    }
+
+    /// Whether the bitmap leaves every bit untouched.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.filter == 0
+    }
+
+    /// The value the bitmap sets `bit` to, if any.
+    #[inline]
+    pub fn get(&self, bit: u8) -> Option<bool> {
+        let mask = 1u128.checked_shl(bit.into())?;
+        (self.filter & mask != 0).then_some(self.value & mask != 0)
+    }
+
+    /// Set `bit` to `value`, or leave it untouched if `value` is `None`.
+    pub fn set(&mut self, bit: u8, value: Option<bool>) -> Result<(), BitmapError> {
+        if bit >= self.width {
+            return Err(BitmapError::OutOfRange {
+                bit,
+                width: self.width,
+            });
+        }
+        let mask = 1u128 << bit;
+        match value {
+            Some(value) => {
+                self.filter |= mask;
+                self.value = (self.value & !mask) | if value { mask } else { 0 };
+            }
+            None => {
+                self.filter &= !mask;
+                self.value &= !mask;
+            }
+        }
+        Ok(())
+    }
+
+    /// The value of a register after applying the bitmap to `register`.
+    #[inline]
+    pub fn apply(&self, register: u128) -> u128 {
+        (register & !self.filter) | self.value
+    }
+
+    /// Apply `other` on top of this bitmap: bits that `other` sets take its value, the others
+    /// keep this bitmap's.
+    #[inline]
+    pub fn merge(&self, other: &Self) -> Self {
+        Self {
+            width: self.width.max(other.width),
+            filter: self.filter | other.filter,
+            value: (self.value & !other.filter) | other.value,
+        }
+    }
+
+    /// Whether both bitmaps set some bit to different values.
+    #[inline]
+    pub fn conflicts_with(&self, other: &Self) -> bool {
+        (self.value ^ other.value) & self.filter & other.filter != 0
+    }
+
+    /// Check that the bitmap fits in a register of `bits` bits.
+    pub fn check_width(&self, bits: u8) -> Result<(), BitmapError> {
+        if self.width > bits {
+            return Err(BitmapError::TooWide {
+                width: self.width.into(),
+                max: bits,
+            });
+        }
+        Ok(())
+    }
+}
+
+fn highest_bit(mask: u128) -> Option<u8> {
+    (mask != 0).then(|| (127 - mask.leading_zeros()) as u8)
+}
+
+impl FromStr for Bitmap {
+    type Err = BitmapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s.strip_prefix("0b").ok_or(BitmapError::MissingPrefix)?;
+        let width = digits.chars().filter(|&c| c != '_').count();
+        if width > usize::from(MAX_WIDTH) {
+            return Err(BitmapError::TooWide {
+                width,
+                max: MAX_WIDTH,
+            });
+        }
+
+        let mut bitmap = Self::new(width as u8);
+        let bits = digits.chars().rev().filter(|&c| c != '_');
+        for (bit, c) in bits.enumerate() {
+            let mask = 1u128 << bit;
+            match c {
+                '0' => bitmap.filter |= mask,
+                '1' => {
+                    bitmap.filter |= mask;
+                    bitmap.value |= mask;
+                }
+                'x' | 'X' => (),
+                c => return Err(BitmapError::InvalidChar(c)),
+            }
+        }
+        Ok(bitmap)
+    }
+}
+
+impl fmt::Display for Bitmap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("0b")?;
+        for bit in (0..self.width).rev() {
+            let c = match self.get(bit) {
+                Some(true) => '1',
+                Some(false) => '0',
+                None => 'x',
+            };
+            write!(f, "{c}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Bitmap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Bitmap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+/// Deserialize a [`Bitmap`] that must fit in a register of `BITS` bits.
+pub(crate) fn deserialize_bitmap<'de, D: Deserializer<'de>, const BITS: u8>(
+    deserializer: D,
+) -> Result<Bitmap, D::Error> {
+    let bitmap = Bitmap::deserialize(deserializer)?;
+    bitmap.check_width(BITS).map_err(de::Error::custom)?;
+    Ok(bitmap)
+}
@@ -1,4 +1,11 @@
-use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::models::{
+    cpu_bitmap::{deserialize_bitmap, Bitmap},
+    Extras,
+};
 
 /// The CPU configuration template defines a set of bit maps as modifiers of flags accessed by
 /// register to be disabled/enabled for the microvm.
@@ -6,17 +13,267 @@ use serde::{Deserialize, Serialize};
 pub struct CpuConfig {
     /// A collection of CPUIDs to be modified. (x86_64)
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub cpuid_modifiers: Option<serde_json::Value>,
+    pub cpuid_modifiers: Option<Vec<CpuidLeafModifier>>,
     /// A collection of model specific registers to be modified. (x86_64)
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub msr_modifiers: Option<serde_json::Value>,
+    pub msr_modifiers: Option<Vec<MsrModifier>>,
     /// A collection of registers to be modified. (aarch64)
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub reg_modifiers: Option<serde_json::Value>,
+    pub reg_modifiers: Option<Vec<RegModifier>>,
     /// A collection of vcpu features to be modified. (aarch64)
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub vcpu_features: Option<serde_json::Value>,
+    pub vcpu_features: Option<Vec<VcpuFeature>>,
     /// A collection of kvm capabilities to be modified. (aarch64)
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub kvm_capabilities: Option<serde_json::Value>,
+    pub kvm_capabilities: Option<Vec<KvmCapability>>,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
+}
+
+/// Modifiers of the registers of a CPUID leaf. (x86_64)
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct CpuidLeafModifier {
+    /// CPUID leaf, i.e. the value of EAX when executing CPUID, e.g. `"0x80000001"`.
+    #[serde(with = "int_str")]
+    pub leaf: u32,
+    /// CPUID subleaf, i.e. the value of ECX when executing CPUID.
+    #[serde(with = "int_str")]
+    pub subleaf: u32,
+    /// KVM CPUID entry flags; `1` (`KVM_CPUID_FLAG_SIGNIFCANT_INDEX`) if the subleaf is
+    /// significant.
+    pub flags: u32,
+    pub modifiers: Vec<CpuidRegisterModifier>,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
+}
+
+impl CpuidLeafModifier {
+    #[inline]
+    pub fn new(leaf: u32, subleaf: u32, flags: u32) -> Self {
+        Self {
+            leaf,
+            subleaf,
+            flags,
+            modifiers: Vec::new(),
+            extras: Default::default(),
+        }
+    }
+}
+
+/// A modifier of a single register of a CPUID leaf.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct CpuidRegisterModifier {
+    pub register: CpuidRegister,
+    /// Bitmap of at most 32 bits.
+    #[serde(deserialize_with = "deserialize_bitmap::<_, 32>")]
+    pub bitmap: Bitmap,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
+}
+
+impl CpuidRegisterModifier {
+    #[inline]
+    pub fn new(register: CpuidRegister, bitmap: Bitmap) -> Self {
+        Self {
+            register,
+            bitmap,
+            extras: Default::default(),
+        }
+    }
+}
+
+/// A register of a CPUID leaf. Unlike other model enums, this one has no fallback for unknown
+/// values, as CPUID only ever returns these four registers.
+#[derive(
+    Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum CpuidRegister {
+    #[default]
+    Eax,
+    Ebx,
+    Ecx,
+    Edx,
+}
+
+impl ::std::fmt::Display for CpuidRegister {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match self {
+            Self::Eax => write!(f, "eax"),
+            Self::Ebx => write!(f, "ebx"),
+            Self::Ecx => write!(f, "ecx"),
+            Self::Edx => write!(f, "edx"),
+        }
+    }
+}
+
+/// A modifier of a model specific register. (x86_64)
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct MsrModifier {
+    /// Address of the MSR, e.g. `"0x10a"`.
+    #[serde(with = "int_str")]
+    pub addr: u32,
+    /// Bitmap of at most 64 bits.
+    #[serde(deserialize_with = "deserialize_bitmap::<_, 64>")]
+    pub bitmap: Bitmap,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
+}
+
+impl MsrModifier {
+    #[inline]
+    pub fn new(addr: u32, bitmap: Bitmap) -> Self {
+        Self {
+            addr,
+            bitmap,
+            extras: Default::default(),
+        }
+    }
+}
+
+/// A modifier of a register, identified by its KVM register ID. (aarch64)
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct RegModifier {
+    /// KVM ID of the register, e.g. `"0x603000000013c020"`.
+    #[serde(with = "int_str")]
+    pub addr: u64,
+    /// Bitmap of at most 128 bits.
+    pub bitmap: Bitmap,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
+}
+
+impl RegModifier {
+    #[inline]
+    pub fn new(addr: u64, bitmap: Bitmap) -> Self {
+        Self {
+            addr,
+            bitmap,
+            extras: Default::default(),
+        }
+    }
+}
+
+/// A modifier of a word of the vCPU features passed to `KVM_ARM_VCPU_INIT`. (aarch64)
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct VcpuFeature {
+    /// Index of the word in the features array.
+    pub index: u32,
+    /// Bitmap of at most 32 bits.
+    #[serde(deserialize_with = "deserialize_bitmap::<_, 32>")]
+    pub bitmap: Bitmap,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
+}
+
+impl VcpuFeature {
+    #[inline]
+    pub fn new(index: u32, bitmap: Bitmap) -> Self {
+        Self {
+            index,
+            bitmap,
+            extras: Default::default(),
+        }
+    }
+}
+
+/// A KVM capability to check for, or to stop checking for, e.g. `"170"` or `"!56"`. (aarch64)
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum KvmCapability {
+    Add(u32),
+    Remove(u32),
+}
+
+impl fmt::Display for KvmCapability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Add(cap) => write!(f, "{cap}"),
+            Self::Remove(cap) => write!(f, "!{cap}"),
+        }
+    }
+}
+
+impl Serialize for KvmCapability {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for KvmCapability {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        let (remove, cap) = match s.strip_prefix('!') {
+            Some(cap) => (true, cap),
+            None => (false, &*s),
+        };
+        let cap = cap.parse().map_err(|_| {
+            de::Error::invalid_value(
+                de::Unexpected::Str(&s),
+                &"a KVM capability like 170 or !170",
+            )
+        })?;
+        Ok(if remove {
+            Self::Remove(cap)
+        } else {
+            Self::Add(cap)
+        })
+    }
+}
+
+/// Integers written as strings in hexadecimal (`0x`), binary (`0b`) or decimal, or as numbers.
+/// They are serialized in hexadecimal.
+mod int_str {
+    use std::fmt;
+
+    use serde::{de, Deserializer, Serializer};
+
+    pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: fmt::LowerHex,
+    {
+        serializer.collect_str(&format_args!("{value:#x}"))
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: TryFrom<u64>,
+    {
+        struct Visitor;
+
+        impl<'de> de::Visitor<'de> for Visitor {
+            type Value = u64;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an integer, or a string like \"0x1f\", \"0b11\" or \"31\"")
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<u64, E> {
+                Ok(v)
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<u64, E> {
+                let parsed = if let Some(hex) = v.strip_prefix("0x") {
+                    u64::from_str_radix(hex, 16)
+                } else if let Some(bin) = v.strip_prefix("0b") {
+                    u64::from_str_radix(bin, 2)
+                } else {
+                    v.parse()
+                };
+                parsed.map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+            }
+        }
+
+        let value = deserializer.deserialize_any(Visitor)?;
+        T::try_from(value).map_err(|_| {
+            de::Error::invalid_value(de::Unexpected::Unsigned(value), &"a smaller integer")
+        })
+    }
 }
@@ -1,11 +1,10 @@
+use compact_str::CompactString;
 use serde::{Deserialize, Serialize};
 
 /// The CPU Template defines a set of flags to be disabled from the microvm so that the features
 /// exposed to the guest are the same as in the selected instance type. This parameter has been
 /// deprecated and it will be removed in future Firecracker release.
-#[derive(
-    Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize,
-)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 pub enum CpuTemplate {
     #[default]
     C3,
@@ -15,6 +14,9 @@ pub enum CpuTemplate {
     T2A,
     V1N1,
     None,
+    /// A value unknown to this crate, e.g. added by a newer Firecracker release.
+    #[serde(untagged)]
+    Other(CompactString),
 }
 
 impl ::std::fmt::Display for CpuTemplate {
@@ -27,6 +29,7 @@ impl ::std::fmt::Display for CpuTemplate {
             Self::T2A => write!(f, "T2A"),
             Self::V1N1 => write!(f, "V1N1"),
             Self::None => write!(f, "None"),
+            Self::Other(other) => write!(f, "{other}"),
         }
     }
 }
@@ -4,9 +4,9 @@ use serde::{Deserialize, Serialize};
 
 use crate::models;
 
-#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Drive {
-    pub drive_id: CompactString,
+    pub drive_id: models::DriveId,
     /// Represents the unique id of the boot partition of this device. It is optional and it will
     /// be taken into account only if the is_root_device field is true.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -34,12 +34,15 @@ pub struct Drive {
     /// config should be omitted for virtio-block configuration.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub socket: Option<Utf8PathBuf>,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: models::Extras,
 }
 
 impl Drive {
-    pub fn new(drive_id: impl Into<CompactString>, is_root_device: bool) -> Self {
+    pub fn new(drive_id: models::DriveId, is_root_device: bool) -> Self {
         Self {
-            drive_id: drive_id.into(),
+            drive_id,
             partuuid: None,
             is_root_device,
             cache_type: None,
@@ -48,28 +51,31 @@ impl Drive {
             rate_limiter: None,
             io_engine: None,
             socket: None,
+            extras: Default::default(),
         }
     }
 }
 
 /// Represents the caching strategy for the block device.
-#[derive(
-    Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize,
-)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 pub enum CacheType {
     #[default]
     Unsafe,
     Writeback,
+    /// A value unknown to this crate, e.g. added by a newer Firecracker release.
+    #[serde(untagged)]
+    Other(CompactString),
 }
 
 /// Type of the IO engine used by the device. "Async" is supported on host kernels newer than
 /// 5.10.51. This field is optional for virtio-block config and should be omitted for
 /// vhost-user-block configuration.
-#[derive(
-    Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize,
-)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 pub enum IoEngine {
     #[default]
     Sync,
     Async,
+    /// A value unknown to this crate, e.g. added by a newer Firecracker release.
+    #[serde(untagged)]
+    Other(CompactString),
 }
@@ -0,0 +1,321 @@
+use camino::Utf8PathBuf;
+
+use crate::models::{
+    self,
+    drive::{CacheType, IoEngine},
+    Drive, DriveId,
+};
+
+/// A [`Drive`] whose fields are restricted to the combinations valid for its backend.
+///
+/// Converts from a [`Drive`] through [`TryFrom`], and into one through [`From`], e.g. to pass it
+/// to [`Api::put_guest_drive_by_id`](crate::Api::put_guest_drive_by_id).
+#[derive(Clone, Debug, PartialEq)]
+pub enum DriveKind {
+    VirtioBlock(VirtioBlock),
+    VhostUser(VhostUserBlock),
+}
+
+impl DriveKind {
+    #[inline]
+    pub fn drive_id(&self) -> &str {
+        match self {
+            Self::VirtioBlock(drive) => &drive.drive_id,
+            Self::VhostUser(drive) => &drive.drive_id,
+        }
+    }
+}
+
+/// A virtio-block drive, backed by a file on the host and emulated by Firecracker.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VirtioBlock {
+    pub drive_id: DriveId,
+    /// The unique id of the boot partition of this device; only used if it is the root device.
+    pub partuuid: Option<String>,
+    pub is_root_device: bool,
+    pub cache_type: Option<CacheType>,
+    pub is_read_only: bool,
+    /// Host level path for the guest drive.
+    pub path_on_host: Utf8PathBuf,
+    pub rate_limiter: Option<Box<models::RateLimiter>>,
+    pub io_engine: Option<IoEngine>,
+    /// Fields unknown to this crate, preserved across round-trips.
+    pub extras: models::Extras,
+}
+
+impl VirtioBlock {
+    #[inline]
+    pub fn new(
+        drive_id: DriveId,
+        path_on_host: impl Into<Utf8PathBuf>,
+        is_root_device: bool,
+        is_read_only: bool,
+    ) -> Self {
+        Self {
+            drive_id,
+            partuuid: None,
+            is_root_device,
+            cache_type: None,
+            is_read_only,
+            path_on_host: path_on_host.into(),
+            rate_limiter: None,
+            io_engine: None,
+            extras: Default::default(),
+        }
+    }
+}
+
+/// A vhost-user-block drive, emulated by a backend process listening on a Unix socket; see
+/// [`vmm::vhost_user`](crate::vmm::vhost_user).
+///
+/// Access mode, rate limiting and I/O are up to the backend, hence the lack of the respective
+/// fields.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VhostUserBlock {
+    pub drive_id: DriveId,
+    /// The unique id of the boot partition of this device; only used if it is the root device.
+    pub partuuid: Option<String>,
+    pub is_root_device: bool,
+    pub cache_type: Option<CacheType>,
+    /// Path to the socket of the vhost-user-block backend.
+    pub socket: Utf8PathBuf,
+    /// Fields unknown to this crate, preserved across round-trips.
+    pub extras: models::Extras,
+}
+
+impl VhostUserBlock {
+    #[inline]
+    pub fn new(drive_id: DriveId, socket: impl Into<Utf8PathBuf>, is_root_device: bool) -> Self {
+        Self {
+            drive_id,
+            partuuid: None,
+            is_root_device,
+            cache_type: None,
+            socket: socket.into(),
+            extras: Default::default(),
+        }
+    }
+}
+
+/// A [`Drive`] that is valid for neither backend.
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum InvalidDrive {
+    #[error("drive {0:?} has both a path on host and a vhost-user socket")]
+    BothBackends(DriveId),
+
+    #[error("drive {0:?} has neither a path on host nor a vhost-user socket")]
+    NoBackend(DriveId),
+
+    #[error("virtio-block drive {0:?} does not specify whether it is read-only")]
+    MissingReadOnly(DriveId),
+
+    #[error("vhost-user drive {drive_id:?} sets {field}, which is up to its backend")]
+    VhostUserField {
+        drive_id: DriveId,
+        field: &'static str,
+    },
+}
+
+impl TryFrom<Drive> for DriveKind {
+    type Error = InvalidDrive;
+
+    fn try_from(drive: Drive) -> Result<Self, Self::Error> {
+        match (drive.path_on_host, drive.socket) {
+            (Some(_), Some(_)) => Err(InvalidDrive::BothBackends(drive.drive_id)),
+            (None, None) => Err(InvalidDrive::NoBackend(drive.drive_id)),
+            (Some(path_on_host), None) => {
+                let Some(is_read_only) = drive.is_read_only else {
+                    return Err(InvalidDrive::MissingReadOnly(drive.drive_id));
+                };
+                Ok(Self::VirtioBlock(VirtioBlock {
+                    drive_id: drive.drive_id,
+                    partuuid: drive.partuuid,
+                    is_root_device: drive.is_root_device,
+                    cache_type: drive.cache_type,
+                    is_read_only,
+                    path_on_host,
+                    rate_limiter: drive.rate_limiter,
+                    io_engine: drive.io_engine,
+                    extras: drive.extras,
+                }))
+            }
+            (None, Some(socket)) => {
+                let field = if drive.is_read_only.is_some() {
+                    Some("is_read_only")
+                } else if drive.rate_limiter.is_some() {
+                    Some("rate_limiter")
+                } else if drive.io_engine.is_some() {
+                    Some("io_engine")
+                } else {
+                    None
+                };
+                if let Some(field) = field {
+                    return Err(InvalidDrive::VhostUserField {
+                        drive_id: drive.drive_id,
+                        field,
+                    });
+                }
+                Ok(Self::VhostUser(VhostUserBlock {
+                    drive_id: drive.drive_id,
+                    partuuid: drive.partuuid,
+                    is_root_device: drive.is_root_device,
+                    cache_type: drive.cache_type,
+                    socket,
+                    extras: drive.extras,
+                }))
+            }
+        }
+    }
+}
+
+impl From<VirtioBlock> for Drive {
+    fn from(drive: VirtioBlock) -> Self {
+        Self {
+            drive_id: drive.drive_id,
+            partuuid: drive.partuuid,
+            is_root_device: drive.is_root_device,
+            cache_type: drive.cache_type,
+            is_read_only: Some(drive.is_read_only),
+            path_on_host: Some(drive.path_on_host),
+            rate_limiter: drive.rate_limiter,
+            io_engine: drive.io_engine,
+            socket: None,
+            extras: drive.extras,
+        }
+    }
+}
+
+impl From<VhostUserBlock> for Drive {
+    fn from(drive: VhostUserBlock) -> Self {
+        Self {
+            drive_id: drive.drive_id,
+            partuuid: drive.partuuid,
+            is_root_device: drive.is_root_device,
+            cache_type: drive.cache_type,
+            is_read_only: None,
+            path_on_host: None,
+            rate_limiter: None,
+            io_engine: None,
+            socket: Some(drive.socket),
+            extras: drive.extras,
+        }
+    }
+}
+
+impl From<DriveKind> for Drive {
+    #[inline]
+    fn from(drive: DriveKind) -> Self {
+        match drive {
+            DriveKind::VirtioBlock(drive) => drive.into(),
+            DriveKind::VhostUser(drive) => drive.into(),
+        }
+    }
+}
+
+impl From<VirtioBlock> for DriveKind {
+    #[inline]
+    fn from(drive: VirtioBlock) -> Self {
+        Self::VirtioBlock(drive)
+    }
+}
+
+impl From<VhostUserBlock> for DriveKind {
+    #[inline]
+    fn from(drive: VhostUserBlock) -> Self {
+        Self::VhostUser(drive)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    fn drive(json: Value) -> Drive {
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn id() -> DriveId {
+        DriveId::new("data").unwrap()
+    }
+
+    #[test]
+    fn rejects_drives_valid_for_neither_backend() {
+        let both = json!({
+            "drive_id": "data",
+            "is_root_device": false,
+            "is_read_only": true,
+            "path_on_host": "/data.ext4",
+            "socket": "/vhost.sock",
+        });
+        let neither = json!({"drive_id": "data", "is_root_device": false, "is_read_only": true});
+        let no_mode =
+            json!({"drive_id": "data", "is_root_device": false, "path_on_host": "/data.ext4"});
+        let cases = [
+            (both, InvalidDrive::BothBackends(id())),
+            (neither, InvalidDrive::NoBackend(id())),
+            (no_mode, InvalidDrive::MissingReadOnly(id())),
+        ];
+        for (json, error) in cases {
+            assert_eq!(DriveKind::try_from(drive(json)), Err(error));
+        }
+
+        for (field, value) in [
+            ("is_read_only", json!(false)),
+            ("rate_limiter", json!({})),
+            ("io_engine", json!("Sync")),
+        ] {
+            let mut json =
+                json!({"drive_id": "data", "is_root_device": false, "socket": "/vhost.sock"});
+            json[field] = value;
+            assert_eq!(
+                DriveKind::try_from(drive(json)),
+                Err(InvalidDrive::VhostUserField {
+                    drive_id: id(),
+                    field,
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn converts_virtio_block_drives_back_and_forth() {
+        let json = json!({
+            "drive_id": "data",
+            "partuuid": "0eaa91a0-01",
+            "is_root_device": true,
+            "cache_type": "Writeback",
+            "is_read_only": false,
+            "path_on_host": "/data.ext4",
+            "rate_limiter": {"ops": {"size": 100, "refill_time": 1000}},
+            "io_engine": "Async",
+            "discard": true,
+        });
+        let original = drive(json);
+        let DriveKind::VirtioBlock(virtio) = DriveKind::try_from(original.clone()).unwrap() else {
+            panic!("expected a virtio-block drive");
+        };
+        assert_eq!(virtio.extras.get("discard"), Some(&json!(true)));
+        assert_eq!(Drive::from(DriveKind::from(virtio)), original);
+    }
+
+    #[test]
+    fn converts_vhost_user_drives_back_and_forth() {
+        let json = json!({
+            "drive_id": "data",
+            "is_root_device": false,
+            "cache_type": "Unsafe",
+            "socket": "/vhost.sock",
+            "queue_size": 256,
+        });
+        let original = drive(json);
+        let kind = DriveKind::try_from(original.clone()).unwrap();
+        assert_eq!(kind.drive_id(), "data");
+        let DriveKind::VhostUser(vhost_user) = kind else {
+            panic!("expected a vhost-user drive");
+        };
+        assert_eq!(vhost_user.extras.get("queue_size"), Some(&json!(256)));
+        assert_eq!(Drive::from(DriveKind::from(vhost_user)), original);
+    }
+}
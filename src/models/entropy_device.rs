@@ -7,4 +7,7 @@ use crate::models;
 pub struct EntropyDevice {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub rate_limiter: Option<Box<models::RateLimiter>>,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: models::Extras,
 }
@@ -1,8 +1,13 @@
 use serde::{Deserialize, Serialize};
 
+use crate::models::Extras;
+
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Error {
     /// A description of the error condition
     #[serde(skip_serializing_if = "Option::is_none")]
     pub fault_message: Option<String>,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
 }
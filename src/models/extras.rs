@@ -0,0 +1,86 @@
+use std::{
+    collections::BTreeMap,
+    hash::{Hash, Hasher},
+};
+
+use compact_str::CompactString;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Fields of a model that are unknown to this crate, e.g. added by a newer Firecracker release.
+///
+/// Every model flattens its extras into itself, so that they survive a round-trip: a
+/// configuration fetched from a newer VMM and applied again keeps all of its fields.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Extras(BTreeMap<CompactString, Value>);
+
+impl Extras {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.0.get(name)
+    }
+
+    #[inline]
+    pub fn insert(&mut self, name: impl Into<CompactString>, value: Value) -> Option<Value> {
+        self.0.insert(name.into(), value)
+    }
+
+    #[inline]
+    pub fn remove(&mut self, name: &str) -> Option<Value> {
+        self.0.remove(name)
+    }
+
+    /// Names of the unknown fields.
+    #[inline]
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(CompactString::as_str)
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.0.iter().map(|(name, value)| (name.as_str(), value))
+    }
+}
+
+impl Hash for Extras {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.len().hash(state);
+        for (name, value) in &self.0 {
+            name.hash(state);
+            // `Value` is not `Hash`; its canonical JSON text is.
+            value.to_string().hash(state);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Extras {
+    type Item = (&'a CompactString, &'a Value);
+    type IntoIter = std::collections::btree_map::Iter<'a, CompactString, Value>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl FromIterator<(CompactString, Value)> for Extras {
+    fn from_iter<T: IntoIterator<Item = (CompactString, Value)>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
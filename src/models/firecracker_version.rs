@@ -1,11 +1,16 @@
 use compact_str::CompactString;
 use serde::{Deserialize, Serialize};
 
+use crate::models::Extras;
+
 /// Describes the Firecracker version.
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct FirecrackerVersion {
     /// Firecracker build version.
     pub firecracker_version: CompactString,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
 }
 
 impl FirecrackerVersion {
@@ -13,6 +18,22 @@ impl FirecrackerVersion {
     pub fn new(firecracker_version: impl Into<CompactString>) -> Self {
         Self {
             firecracker_version: firecracker_version.into(),
+            extras: Default::default(),
+        }
+    }
+
+    /// The `(major, minor, patch)` release of this version, ignoring a leading `v` and any
+    /// pre-release or build suffix (e.g. `1.14.0-dev`), or `None` if it cannot be parsed.
+    pub fn release(&self) -> Option<(u16, u16, u16)> {
+        let version = self.firecracker_version.trim();
+        let version = version.strip_prefix('v').unwrap_or(version);
+        let end = version
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(version.len());
+        let mut parts = version[..end].splitn(3, '.').map(str::parse);
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(major)), Some(Ok(minor)), Some(Ok(patch))) => Some((major, minor, patch)),
+            _ => None,
         }
     }
 }
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-use crate::models;
+use crate::models::{self, validate};
 
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct FullVmConfiguration {
@@ -28,4 +29,204 @@ pub struct FullVmConfiguration {
     pub vsock: Option<Box<models::Vsock>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub entropy: Option<Box<models::EntropyDevice>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub serial: Option<Box<models::Serial>>,
+    #[serde(rename = "memory-hotplug", skip_serializing_if = "Option::is_none")]
+    pub memory_hotplug: Option<Box<models::MemoryHotplugConfig>>,
+    /// Configurations for all pmem devices.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pmem: Option<Vec<models::Pmem>>,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: models::Extras,
+}
+
+/// More than one device, pmem or block, is configured as the root device.
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+#[error("multiple root devices: {}", .0.join(", "))]
+pub struct RootDeviceConflict(pub Vec<String>);
+
+impl FullVmConfiguration {
+    /// Check that at most one device, among the block and the pmem devices, is the root device.
+    ///
+    /// The conflict lists the flags of all root devices, with the same paths as the
+    /// [`Violation`](models::Violation)s of [`validate`](models::Validate::validate), e.g.
+    /// `drives[0].is_root_device` or `pmem[1].root_device`.
+    pub fn check_root_device(&self) -> Result<(), RootDeviceConflict> {
+        let roots = validate::root_devices(self, "");
+        if roots.len() > 1 {
+            return Err(RootDeviceConflict(roots));
+        }
+        Ok(())
+    }
+
+    /// Every field unknown to this crate, in this configuration and in the models it nests, by
+    /// path, e.g. `drives[0].rate_limiter.bandwidth.burst_mode`.
+    pub fn unknown_fields(&self) -> Vec<(String, &Value)> {
+        let mut unknown = Unknown::default();
+        unknown.add("", &self.extras);
+        if let Some(balloon) = &self.balloon {
+            unknown.add("balloon", &balloon.extras);
+        }
+        for (i, drive) in self.drives.iter().flatten().enumerate() {
+            let path = format!("drives[{i}]");
+            unknown.add(&path, &drive.extras);
+            unknown.rate_limiter(&format!("{path}.rate_limiter"), &drive.rate_limiter);
+        }
+        if let Some(boot_source) = &self.boot_source {
+            unknown.add("boot-source", &boot_source.extras);
+        }
+        if let Some(cpu_config) = &self.cpu_config {
+            unknown.add("cpu-config", &cpu_config.extras);
+            for (i, leaf) in cpu_config.cpuid_modifiers.iter().flatten().enumerate() {
+                let path = format!("cpu-config.cpuid_modifiers[{i}]");
+                unknown.add(&path, &leaf.extras);
+                for (j, modifier) in leaf.modifiers.iter().enumerate() {
+                    unknown.add(&format!("{path}.modifiers[{j}]"), &modifier.extras);
+                }
+            }
+            for (i, modifier) in cpu_config.msr_modifiers.iter().flatten().enumerate() {
+                unknown.add(&format!("cpu-config.msr_modifiers[{i}]"), &modifier.extras);
+            }
+            for (i, modifier) in cpu_config.reg_modifiers.iter().flatten().enumerate() {
+                unknown.add(&format!("cpu-config.reg_modifiers[{i}]"), &modifier.extras);
+            }
+            for (i, feature) in cpu_config.vcpu_features.iter().flatten().enumerate() {
+                unknown.add(&format!("cpu-config.vcpu_features[{i}]"), &feature.extras);
+            }
+        }
+        if let Some(logger) = &self.logger {
+            unknown.add("logger", &logger.extras);
+        }
+        if let Some(machine_config) = &self.machine_config {
+            unknown.add("machine-config", &machine_config.extras);
+        }
+        if let Some(metrics) = &self.metrics {
+            unknown.add("metrics", &metrics.extras);
+        }
+        if let Some(mmds_config) = &self.mmds_config {
+            unknown.add("mmds-config", &mmds_config.extras);
+        }
+        for (i, iface) in self.network_interfaces.iter().flatten().enumerate() {
+            let path = format!("network-interfaces[{i}]");
+            unknown.add(&path, &iface.extras);
+            unknown.rate_limiter(&format!("{path}.rx_rate_limiter"), &iface.rx_rate_limiter);
+            unknown.rate_limiter(&format!("{path}.tx_rate_limiter"), &iface.tx_rate_limiter);
+        }
+        if let Some(vsock) = &self.vsock {
+            unknown.add("vsock", &vsock.extras);
+        }
+        if let Some(entropy) = &self.entropy {
+            unknown.add("entropy", &entropy.extras);
+            unknown.rate_limiter("entropy.rate_limiter", &entropy.rate_limiter);
+        }
+        if let Some(serial) = &self.serial {
+            unknown.add("serial", &serial.extras);
+        }
+        if let Some(memory_hotplug) = &self.memory_hotplug {
+            unknown.add("memory-hotplug", &memory_hotplug.extras);
+        }
+        for (i, pmem) in self.pmem.iter().flatten().enumerate() {
+            unknown.add(&format!("pmem[{i}]"), &pmem.extras);
+        }
+        unknown.0
+    }
+}
+
+#[derive(Default)]
+struct Unknown<'a>(Vec<(String, &'a Value)>);
+
+impl<'a> Unknown<'a> {
+    fn add(&mut self, path: &str, extras: &'a models::Extras) {
+        for (name, value) in extras.iter() {
+            let field = if path.is_empty() {
+                name.to_owned()
+            } else {
+                format!("{path}.{name}")
+            };
+            self.0.push((field, value));
+        }
+    }
+
+    fn rate_limiter(&mut self, path: &str, limiter: &'a Option<Box<models::RateLimiter>>) {
+        let Some(limiter) = limiter else {
+            return;
+        };
+        self.add(path, &limiter.extras);
+        if let Some(bandwidth) = &limiter.bandwidth {
+            self.add(&format!("{path}.bandwidth"), &bandwidth.extras);
+        }
+        if let Some(ops) = &limiter.ops {
+            self.add(&format!("{path}.ops"), &ops.extras);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn config() -> Value {
+        json!({
+            "boot-source": {"kernel_image_path": "/vmlinux", "kernel_hash": "sha256:..."},
+            "drives": [{
+                "drive_id": "rootfs",
+                "is_root_device": true,
+                "path_on_host": "/rootfs.ext4",
+                "is_read_only": false,
+                "rate_limiter": {
+                    "bandwidth": {"size": 1000, "refill_time": 1000, "burst_mode": "smooth"},
+                },
+                "discard": true,
+            }],
+            "machine-config": {"vcpu_count": 2, "mem_size_mib": 1024, "cpu_template": "V2N2"},
+            "network-interfaces": [{
+                "iface_id": "eth0",
+                "host_dev_name": "tap0",
+                "rx_rate_limiter": {"ops": {"size": 10, "refill_time": 100}, "shared": true},
+            }],
+            "cpu-config": {
+                "cpuid_modifiers": [{
+                    "leaf": "0x1",
+                    "subleaf": "0x0",
+                    "flags": 0,
+                    "modifiers": [{"register": "eax", "bitmap": "0b1", "comment": "why"}],
+                }],
+            },
+            "pmem": [{"id": "pmem0", "path_on_host": "/pmem.img", "numa_node": 1}],
+            "gpu": {"enabled": false},
+        })
+    }
+
+    #[test]
+    fn keeps_unknown_fields_across_round_trips() {
+        let config: FullVmConfiguration = serde_json::from_value(config()).unwrap();
+        assert_eq!(config.extras.get("gpu"), Some(&json!({"enabled": false})));
+        let drive = &config.drives.as_ref().unwrap()[0];
+        assert_eq!(drive.extras.get("discard"), Some(&json!(true)));
+        assert_eq!(serde_json::to_value(&config).unwrap(), self::config());
+    }
+
+    #[test]
+    fn lists_unknown_fields_by_path() {
+        let config: FullVmConfiguration = serde_json::from_value(config()).unwrap();
+        let unknown: Vec<(String, &Value)> = config.unknown_fields();
+        let paths: Vec<&str> = unknown.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "gpu",
+                "drives[0].discard",
+                "drives[0].rate_limiter.bandwidth.burst_mode",
+                "boot-source.kernel_hash",
+                "cpu-config.cpuid_modifiers[0].modifiers[0].comment",
+                "network-interfaces[0].rx_rate_limiter.shared",
+                "pmem[0].numa_node",
+            ]
+        );
+        assert_eq!(unknown[2].1, "smooth");
+        assert_eq!(FullVmConfiguration::default().unknown_fields(), []);
+    }
 }
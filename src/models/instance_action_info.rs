@@ -1,26 +1,36 @@
+use compact_str::CompactString;
 use serde::{Deserialize, Serialize};
 
+use crate::models::Extras;
+
 /// Variant wrapper containing the real action.
-#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct InstanceActionInfo {
     /// Enumeration indicating what type of action is contained in the payload
     pub action_type: ActionType,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
 }
 
 impl InstanceActionInfo {
     #[inline]
     pub fn new(action_type: ActionType) -> Self {
-        Self { action_type }
+        Self {
+            action_type,
+            extras: Default::default(),
+        }
     }
 }
 
 /// Enumeration indicating what type of action is contained in the payload
-#[derive(
-    Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize,
-)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 pub enum ActionType {
     #[default]
     FlushMetrics,
     InstanceStart,
     SendCtrlAltDel,
+    /// A value unknown to this crate, e.g. added by a newer Firecracker release.
+    #[serde(untagged)]
+    Other(CompactString),
 }
@@ -1,6 +1,8 @@
 use compact_str::CompactString;
 use serde::{Deserialize, Serialize};
 
+use crate::models::Extras;
+
 /// Describes MicroVM instance information.
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct InstanceInfo {
@@ -13,6 +15,9 @@ pub struct InstanceInfo {
     pub state: State,
     /// MicroVM hypervisor build version.
     pub vmm_version: CompactString,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
 }
 
 impl InstanceInfo {
@@ -28,19 +33,21 @@ impl InstanceInfo {
             id: id.into(),
             state,
             vmm_version: vmm_version.into(),
+            extras: Default::default(),
         }
     }
 }
 
 /// The current detailed state (Not started, Running, Paused) of the Firecracker instance.
 /// This value is read-only for the control-plane.
-#[derive(
-    Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize,
-)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 pub enum State {
     #[serde(rename = "Not started")]
     #[default]
     NotStarted,
     Running,
     Paused,
+    /// A value unknown to this crate, e.g. added by a newer Firecracker release.
+    #[serde(untagged)]
+    Other(CompactString),
 }
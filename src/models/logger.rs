@@ -2,6 +2,8 @@ use camino::Utf8PathBuf;
 use compact_str::CompactString;
 use serde::{Deserialize, Serialize};
 
+use crate::models::Extras;
+
 /// Describes the configuration option for the logging capability.
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Logger {
@@ -20,12 +22,13 @@ pub struct Logger {
     /// The module path to filter log messages by.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub module: Option<CompactString>,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
 }
 
 /// Set the level. The possible values are case-insensitive.
-#[derive(
-    Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize,
-)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 pub enum Level {
     #[default]
     Error,
@@ -33,4 +36,7 @@ pub enum Level {
     Info,
     Debug,
     Trace,
+    /// A value unknown to this crate, e.g. added by a newer Firecracker release.
+    #[serde(untagged)]
+    Other(CompactString),
 }
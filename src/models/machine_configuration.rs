@@ -1,10 +1,11 @@
+use compact_str::CompactString;
 use serde::{Deserialize, Serialize};
 
 use crate::models;
 
 /// Describes the number of vCPUs, memory size, SMT capabilities, huge page configuration and the
 /// CPU template.
-#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct MachineConfiguration {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cpu_template: Option<models::CpuTemplate>,
@@ -12,7 +13,7 @@ pub struct MachineConfiguration {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub smt: Option<bool>,
     /// Memory size of VM
-    pub mem_size_mib: i32,
+    pub mem_size_mib: models::MiB,
     /// Enable dirty page tracking. If this is enabled, then incremental guest memory snapshots
     /// can be created. These belong to diff snapshots, which contain, besides the microVM state,
     /// only the memory dirtied since a previous snapshot. Full snapshots each contain a full copy
@@ -20,15 +21,18 @@ pub struct MachineConfiguration {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub track_dirty_pages: Option<bool>,
     /// Number of vCPUs (either 1 or an even number)
-    pub vcpu_count: i32,
+    pub vcpu_count: models::VcpuCount,
     /// Which huge pages configuration (if any) should be used to back guest memory.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub huge_pages: Option<HugePages>,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: models::Extras,
 }
 
 impl MachineConfiguration {
     #[inline]
-    pub fn new(mem_size_mib: i32, vcpu_count: i32) -> Self {
+    pub fn new(mem_size_mib: models::MiB, vcpu_count: models::VcpuCount) -> Self {
         Self {
             cpu_template: None,
             smt: None,
@@ -36,17 +40,19 @@ impl MachineConfiguration {
             track_dirty_pages: None,
             vcpu_count,
             huge_pages: None,
+            extras: Default::default(),
         }
     }
 }
 
 /// Which huge pages configuration (if any) should be used to back guest memory.
-#[derive(
-    Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize,
-)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 pub enum HugePages {
     #[default]
     None,
     #[serde(rename = "2M")]
     TwoM,
+    /// A value unknown to this crate, e.g. added by a newer Firecracker release.
+    #[serde(untagged)]
+    Other(CompactString),
 }
@@ -1,6 +1,9 @@
 use camino::Utf8PathBuf;
+use compact_str::CompactString;
 use serde::{Deserialize, Serialize};
 
+use crate::models::Extras;
+
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct MemoryBackend {
     pub backend_type: BackendType,
@@ -10,6 +13,9 @@ pub struct MemoryBackend {
     ///     and open file descriptor that it can use to serve this process's guest memory page
     ///     faults
     pub backend_path: Utf8PathBuf,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
 }
 
 impl MemoryBackend {
@@ -18,15 +24,17 @@ impl MemoryBackend {
         Self {
             backend_type,
             backend_path: backend_path.into(),
+            extras: Default::default(),
         }
     }
 }
 
-#[derive(
-    Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize,
-)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 pub enum BackendType {
     #[default]
     File,
     Uffd,
+    /// A value unknown to this crate, e.g. added by a newer Firecracker release.
+    #[serde(untagged)]
+    Other(CompactString),
 }
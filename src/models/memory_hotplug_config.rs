@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Extras, MiB};
+
+/// Configures the hot-pluggable memory of the guest, backed by a virtio-mem device. Can only be
+/// set pre-boot.
+///
+/// Requires Firecracker v1.14.0 or later.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MemoryHotplugConfig {
+    /// Total size of the hot-pluggable memory region, in MiB; the most memory that can be plugged
+    /// into the guest on top of `mem_size_mib`.
+    pub total_size_mib: MiB,
+    /// Size of the KVM memory slots the region is split into, in MiB.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot_size_mib: Option<MiB>,
+    /// Granularity at which memory is plugged and unplugged, in MiB.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_size_mib: Option<MiB>,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
+}
+
+impl MemoryHotplugConfig {
+    #[inline]
+    pub fn new(total_size_mib: MiB) -> Self {
+        Self {
+            total_size_mib,
+            slot_size_mib: None,
+            block_size_mib: None,
+            extras: Default::default(),
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Extras, MiB};
+
+/// Requests the guest to resize its hot-pluggable memory.
+///
+/// Requires Firecracker v1.14.0 or later.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MemoryHotplugSizeUpdate {
+    /// Amount of hot-pluggable memory the guest should have plugged, in MiB.
+    pub requested_size_mib: MiB,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
+}
+
+impl MemoryHotplugSizeUpdate {
+    #[inline]
+    pub fn new(requested_size_mib: MiB) -> Self {
+        Self {
+            requested_size_mib,
+            extras: Default::default(),
+        }
+    }
+}
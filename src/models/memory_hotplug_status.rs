@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Extras, MiB};
+
+/// Describes the configuration and the current size of the hot-pluggable memory.
+///
+/// Requires Firecracker v1.14.0 or later.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MemoryHotplugStatus {
+    /// Total size of the hot-pluggable memory region, in MiB.
+    pub total_size_mib: MiB,
+    /// Size of the KVM memory slots the region is split into, in MiB.
+    pub slot_size_mib: MiB,
+    /// Granularity at which memory is plugged and unplugged, in MiB.
+    pub block_size_mib: MiB,
+    /// Amount of memory currently plugged into the guest, in MiB.
+    pub plugged_size_mib: MiB,
+    /// Amount of memory last requested to be plugged into the guest, in MiB.
+    pub requested_size_mib: MiB,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
+}
+
+impl MemoryHotplugStatus {
+    /// Whether the guest has plugged as much memory as was last requested.
+    #[inline]
+    pub fn is_settled(&self) -> bool {
+        self.plugged_size_mib == self.requested_size_mib
+    }
+}
@@ -1,11 +1,16 @@
 use camino::Utf8PathBuf;
 use serde::{Deserialize, Serialize};
 
+use crate::models::Extras;
+
 /// Describes the configuration option for the metrics capability.
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Metrics {
     /// Path to the named pipe or file where the JSON-formatted metrics are flushed.
     pub metrics_path: Utf8PathBuf,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
 }
 
 impl Metrics {
@@ -13,6 +18,7 @@ impl Metrics {
     pub fn new(metrics_path: impl Into<Utf8PathBuf>) -> Self {
         Self {
             metrics_path: metrics_path.into(),
+            extras: Default::default(),
         }
     }
 }
@@ -1,6 +1,8 @@
 use compact_str::CompactString;
 use serde::{Deserialize, Serialize};
 
+use crate::models::{Extras, IfaceId};
+
 /// Defines the MMDS configuration.
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct MmdsConfig {
@@ -12,33 +14,38 @@ pub struct MmdsConfig {
     /// will reply to HTTP GET requests sent to the MMDS address via the interfaces mentioned.
     /// In this case, both ARP requests and TCP segments heading to `ipv4_address` are intercepted
     /// by the device model, and do not reach the associated TAP device.
-    pub network_interfaces: Vec<CompactString>,
+    pub network_interfaces: Vec<IfaceId>,
     /// A valid IPv4 link-local address.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ipv4_address: Option<CompactString>,
     /// MMDS operates compatibly with EC2 IMDS (i.e. responds "text/plain" content regardless of
     /// `Accept` header in requests).
     pub imds_compat: bool,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
 }
 
 impl MmdsConfig {
     #[inline]
-    pub fn new(network_interfaces: Vec<CompactString>) -> Self {
+    pub fn new(network_interfaces: Vec<IfaceId>) -> Self {
         Self {
             version: None,
             network_interfaces,
             ipv4_address: None,
             imds_compat: false,
+            extras: Default::default(),
         }
     }
 }
 
 /// Enumeration indicating the MMDS version to be configured.
-#[derive(
-    Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize,
-)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 pub enum Version {
     #[default]
     V1,
     V2,
+    /// A value unknown to this crate, e.g. added by a newer Firecracker release.
+    #[serde(untagged)]
+    Other(CompactString),
 }
@@ -1,5 +1,9 @@
 pub mod balloon;
 pub use balloon::Balloon;
+pub mod balloon_hinting_status;
+pub use balloon_hinting_status::BalloonHintingStatus;
+pub mod balloon_start_hinting;
+pub use balloon_start_hinting::BalloonStartHinting;
 pub mod balloon_stats;
 pub use balloon_stats::BalloonStats;
 pub mod balloon_stats_update;
@@ -8,16 +12,26 @@ pub mod balloon_update;
 pub use balloon_update::BalloonUpdate;
 pub mod boot_source;
 pub use boot_source::BootSource;
+pub mod builders;
+pub mod cpu_bitmap;
+pub use cpu_bitmap::Bitmap;
 pub mod cpu_config;
-pub use cpu_config::CpuConfig;
+pub use cpu_config::{
+    CpuConfig, CpuidLeafModifier, CpuidRegister, CpuidRegisterModifier, KvmCapability, MsrModifier,
+    RegModifier, VcpuFeature,
+};
 pub mod cpu_template;
 pub use cpu_template::CpuTemplate;
 pub mod drive;
 pub use drive::Drive;
+pub mod drive_kind;
+pub use drive_kind::{DriveKind, VhostUserBlock, VirtioBlock};
 pub mod entropy_device;
 pub use entropy_device::EntropyDevice;
 pub mod error;
 pub use error::Error;
+pub mod extras;
+pub use extras::Extras;
 pub mod firecracker_version;
 pub use firecracker_version::FirecrackerVersion;
 pub mod full_vm_configuration;
@@ -32,6 +46,12 @@ pub mod machine_configuration;
 pub use machine_configuration::MachineConfiguration;
 pub mod memory_backend;
 pub use memory_backend::MemoryBackend;
+pub mod memory_hotplug_config;
+pub use memory_hotplug_config::MemoryHotplugConfig;
+pub mod memory_hotplug_size_update;
+pub use memory_hotplug_size_update::MemoryHotplugSizeUpdate;
+pub mod memory_hotplug_status;
+pub use memory_hotplug_status::MemoryHotplugStatus;
 pub mod metrics;
 pub use metrics::Metrics;
 pub mod mmds_config;
@@ -44,15 +64,91 @@ pub mod partial_drive;
 pub use partial_drive::PartialDrive;
 pub mod partial_network_interface;
 pub use partial_network_interface::PartialNetworkInterface;
+pub mod pmem;
+pub use pmem::Pmem;
 pub mod rate_limiter;
 pub use rate_limiter::RateLimiter;
+pub mod serial;
+pub use serial::Serial;
 pub mod snapshot_create_params;
 pub use snapshot_create_params::SnapshotCreateParams;
 pub mod snapshot_load_params;
 pub use snapshot_load_params::SnapshotLoadParams;
 pub mod token_bucket;
 pub use token_bucket::TokenBucket;
+pub mod types;
+pub use types::{DriveId, IfaceId, MacAddress, MiB, PmemId, VcpuCount, VsockCid};
+pub mod validate;
+pub use validate::{Validate, Violation, Violations};
 pub mod vm;
 pub use vm::Vm;
 pub mod vsock;
 pub use vsock::Vsock;
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+
+    use serde::{de::DeserializeOwned, Serialize};
+
+    use super::*;
+
+    /// Check that `json` deserializes into `expected`, and serializes back unchanged.
+    fn round_trip<T>(json: &str, expected: T)
+    where
+        T: Serialize + DeserializeOwned + PartialEq + Debug,
+    {
+        let value: T = serde_json::from_str(json).unwrap();
+        assert_eq!(value, expected, "{json}");
+        assert_eq!(serde_json::to_string(&value).unwrap(), json);
+    }
+
+    #[test]
+    fn enums_round_trip_unknown_variants() {
+        use cpu_template::CpuTemplate;
+        use drive::{CacheType, IoEngine};
+        use instance_action_info::ActionType;
+        use logger::Level;
+        use machine_configuration::HugePages;
+        use memory_backend::BackendType;
+        use mmds_config::Version;
+        use snapshot_create_params::SnapshotType;
+
+        round_trip(r#""Diff""#, SnapshotType::Diff);
+        round_trip(
+            r#""Incremental""#,
+            SnapshotType::Other("Incremental".into()),
+        );
+        round_trip(r#""Writeback""#, CacheType::Writeback);
+        round_trip(r#""Writethrough""#, CacheType::Other("Writethrough".into()));
+        round_trip(r#""Async""#, IoEngine::Async);
+        round_trip(r#""IoUring""#, IoEngine::Other("IoUring".into()));
+        round_trip(r#""Resumed""#, vm::State::Resumed);
+        round_trip(r#""Suspended""#, vm::State::Other("Suspended".into()));
+        round_trip(r#""2M""#, HugePages::TwoM);
+        round_trip(r#""1G""#, HugePages::Other("1G".into()));
+        round_trip(r#""Uffd""#, BackendType::Uffd);
+        round_trip(r#""Memfd""#, BackendType::Other("Memfd".into()));
+        round_trip(r#""Trace""#, Level::Trace);
+        round_trip(r#""Off""#, Level::Other("Off".into()));
+        round_trip(r#""V2""#, Version::V2);
+        round_trip(r#""V3""#, Version::Other("V3".into()));
+        round_trip(r#""T2CL""#, CpuTemplate::T2CL);
+        round_trip(r#""V2N2""#, CpuTemplate::Other("V2N2".into()));
+        round_trip(r#""SendCtrlAltDel""#, ActionType::SendCtrlAltDel);
+        round_trip(r#""Reboot""#, ActionType::Other("Reboot".into()));
+        round_trip(r#""Not started""#, instance_info::State::NotStarted);
+        round_trip(r#""Halted""#, instance_info::State::Other("Halted".into()));
+    }
+
+    #[test]
+    fn unknown_variants_survive_in_their_models() {
+        let json = r#"{"drive_id":"rootfs","is_root_device":true,"cache_type":"Writethrough","path_on_host":"/rootfs.ext4","io_engine":"IoUring"}"#;
+        let drive: Drive = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            drive.cache_type,
+            Some(drive::CacheType::Other("Writethrough".into()))
+        );
+        assert_eq!(serde_json::to_string(&drive).unwrap(), json);
+    }
+}
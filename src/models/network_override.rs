@@ -1,24 +1,27 @@
 use compact_str::CompactString;
 use serde::{Deserialize, Serialize};
 
+use crate::models::{Extras, IfaceId};
+
 /// Allows for changing the backing TAP device of a network interface during snapshot restore.
-#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct NetworkOverride {
     /// The name of the interface to modify
-    pub iface_id: CompactString,
+    pub iface_id: IfaceId,
     /// The new host device of the interface
     pub host_dev_name: CompactString,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
 }
 
 impl NetworkOverride {
     #[inline]
-    pub fn new(
-        iface_id: impl Into<CompactString>,
-        host_dev_name: impl Into<CompactString>,
-    ) -> Self {
+    pub fn new(iface_id: IfaceId, host_dev_name: impl Into<CompactString>) -> Self {
         Self {
-            iface_id: iface_id.into(),
+            iface_id,
             host_dev_name: host_dev_name.into(),
+            extras: Default::default(),
         }
     }
 }
@@ -1,27 +1,30 @@
 use camino::Utf8PathBuf;
-use compact_str::CompactString;
 use serde::{Deserialize, Serialize};
 
 use crate::models;
 
-#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct PartialDrive {
-    pub drive_id: CompactString,
+    pub drive_id: models::DriveId,
     /// Host level path for the guest drive. This field is optional for virtio-block config and
     /// should be omitted for vhost-user-block configuration.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub path_on_host: Option<Utf8PathBuf>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub rate_limiter: Option<Box<models::RateLimiter>>,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: models::Extras,
 }
 
 impl PartialDrive {
     #[inline]
-    pub fn new(drive_id: impl Into<CompactString>) -> Self {
+    pub fn new(drive_id: models::DriveId) -> Self {
         Self {
-            drive_id: drive_id.into(),
+            drive_id,
             path_on_host: None,
             rate_limiter: None,
+            extras: Default::default(),
         }
     }
 }
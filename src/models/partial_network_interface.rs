@@ -1,26 +1,29 @@
-use compact_str::CompactString;
 use serde::{Deserialize, Serialize};
 
 use crate::models;
 
 /// Defines a partial network interface structure, used to update the rate limiters for that
 /// interface, after microvm start.
-#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct PartialNetworkInterface {
-    pub iface_id: CompactString,
+    pub iface_id: models::IfaceId,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub rx_rate_limiter: Option<Box<models::RateLimiter>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tx_rate_limiter: Option<Box<models::RateLimiter>>,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: models::Extras,
 }
 
 impl PartialNetworkInterface {
     #[inline]
-    pub fn new(iface_id: impl Into<CompactString>) -> Self {
+    pub fn new(iface_id: models::IfaceId) -> Self {
         Self {
-            iface_id: iface_id.into(),
+            iface_id,
             rx_rate_limiter: None,
             tx_rate_limiter: None,
+            extras: Default::default(),
         }
     }
 }
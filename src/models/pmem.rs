@@ -0,0 +1,43 @@
+use camino::Utf8PathBuf;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{self, Extras};
+
+/// Defines a virtio-pmem device, backed by a file on the host that is mapped into the guest
+/// memory, so that its page cache can be bypassed (DAX) and shared across VMs.
+///
+/// Requires Firecracker v1.14.0 or later.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Pmem {
+    pub id: models::PmemId,
+    /// Host level path for the backing file of the device.
+    pub path_on_host: Utf8PathBuf,
+    /// Whether the guest should mount the device as its root filesystem. At most one device,
+    /// pmem or block, can be the root device.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root_device: Option<bool>,
+    /// Whether the device is read-only to the guest.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_only: Option<bool>,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
+}
+
+impl Pmem {
+    #[inline]
+    pub fn new(id: models::PmemId, path_on_host: impl Into<Utf8PathBuf>) -> Self {
+        Self {
+            id,
+            path_on_host: path_on_host.into(),
+            root_device: None,
+            read_only: None,
+            extras: Default::default(),
+        }
+    }
+
+    #[inline]
+    pub fn is_root_device(&self) -> bool {
+        self.root_device.unwrap_or(false)
+    }
+}
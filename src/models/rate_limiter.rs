@@ -11,4 +11,7 @@ pub struct RateLimiter {
     pub bandwidth: Option<Box<models::TokenBucket>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ops: Option<Box<models::TokenBucket>>,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: models::Extras,
 }
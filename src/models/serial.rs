@@ -0,0 +1,38 @@
+use camino::Utf8PathBuf;
+use serde::{Deserialize, Serialize};
+
+use crate::models::Extras;
+
+/// Defines where the output of the guest serial console goes.
+///
+/// Firecracker v1.14 offers no way to disable the serial console through the API; leave
+/// `console=ttyS0` out of the kernel boot arguments to keep the guest from writing to it, or
+/// redirect it to `/dev/null` to discard its output.
+///
+/// Requires Firecracker v1.14.0 or later.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Serial {
+    /// Path to a file or named pipe on the host to which the guest serial output is written. If
+    /// omitted, it is written to Firecracker's stdout.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub serial_out_path: Option<Utf8PathBuf>,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
+}
+
+impl Serial {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Redirect the guest serial output to the file or named pipe at `path`.
+    #[inline]
+    pub fn to_file(path: impl Into<Utf8PathBuf>) -> Self {
+        Self {
+            serial_out_path: Some(path.into()),
+            extras: Default::default(),
+        }
+    }
+}
@@ -1,6 +1,9 @@
 use camino::Utf8PathBuf;
+use compact_str::CompactString;
 use serde::{Deserialize, Serialize};
 
+use crate::models::Extras;
+
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct SnapshotCreateParams {
     /// Path to the file that will contain the guest memory.
@@ -10,6 +13,9 @@ pub struct SnapshotCreateParams {
     /// Type of snapshot to create. It is optional and by default, a full snapshot is created.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub snapshot_type: Option<SnapshotType>,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
 }
 
 impl SnapshotCreateParams {
@@ -22,16 +28,18 @@ impl SnapshotCreateParams {
             mem_file_path: mem_file_path.into(),
             snapshot_path: snapshot_path.into(),
             snapshot_type: None,
+            extras: Default::default(),
         }
     }
 }
 
 /// Type of snapshot to create. It is optional and by default, a full snapshot is created.
-#[derive(
-    Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize,
-)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 pub enum SnapshotType {
     #[default]
     Full,
     Diff,
+    /// A value unknown to this crate, e.g. added by a newer Firecracker release.
+    #[serde(untagged)]
+    Other(CompactString),
 }
@@ -25,6 +25,9 @@ pub struct SnapshotLoadParams {
     /// Network host device names to override
     #[serde(skip_serializing_if = "Option::is_none")]
     pub network_overrides: Option<Vec<models::NetworkOverride>>,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: models::Extras,
 }
 
 impl SnapshotLoadParams {
@@ -37,6 +40,7 @@ impl SnapshotLoadParams {
             snapshot_path: snapshot_path.into(),
             resume_vm: None,
             network_overrides: None,
+            extras: Default::default(),
         }
     }
 }
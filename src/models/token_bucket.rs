@@ -1,12 +1,14 @@
 use serde::{Deserialize, Serialize};
 
+use crate::models::Extras;
+
 /// Defines a token bucket with a maximum capacity (size), an initial burst size (one_time_burst)
 /// and an interval for refilling purposes (refill_time). The refill-rate is derived from size and
 /// refill_time, and it is the constant rate at which the tokens replenish. The refill process only
 /// starts happening after the initial burst budget is consumed. Consumption from the token bucket
 /// is unbounded in speed which allows for bursts bound in size by the amount of tokens available.
 /// Once the token bucket is empty, consumption speed is bound by the refill_rate.
-#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct TokenBucket {
     /// The initial size of a token bucket.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -15,6 +17,9 @@ pub struct TokenBucket {
     pub refill_time: i64,
     /// The total number of tokens this bucket can hold.
     pub size: i64,
+    /// Fields unknown to this crate, preserved across round-trips.
+    #[serde(flatten)]
+    pub extras: Extras,
 }
 
 impl TokenBucket {
@@ -24,6 +29,7 @@ impl TokenBucket {
             one_time_burst: None,
             refill_time,
             size,
+            extras: Default::default(),
         }
     }
 }
@@ -0,0 +1,545 @@
+//! Validated newtypes for the identifiers, sizes and addresses used across the models.
+//!
+//! Each serializes exactly like the primitive it wraps, so that the models stay wire-compatible,
+//! but can only hold values that Firecracker accepts.
+
+use std::{borrow::Borrow, fmt, ops::Deref, str::FromStr};
+
+use compact_str::{format_compact, CompactString};
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of vCPUs Firecracker supports.
+pub const MAX_VCPUS: u8 = 32;
+
+/// Maximum length of a drive, network interface or pmem device ID.
+pub const MAX_ID_LEN: usize = 64;
+
+/// A value that does not fit one of the newtypes of this module.
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum InvalidValue {
+    #[error("invalid MAC address {0:?}")]
+    MacAddress(CompactString),
+
+    #[error("invalid ID {id:?}: {reason}")]
+    Id {
+        id: CompactString,
+        reason: CompactString,
+    },
+
+    #[error("vsock CID {0} is reserved")]
+    ReservedCid(u32),
+
+    #[error("vCPU count {0} is not between 1 and {MAX_VCPUS}")]
+    VcpuCount(u32),
+
+    #[error("invalid number {0:?}")]
+    Number(CompactString),
+}
+
+/// Check that `id` is non-empty, alphanumeric or `_`, and up to [`MAX_ID_LEN`] characters long,
+/// as Firecracker requires of device IDs.
+pub(crate) fn check_id(id: &str) -> Result<(), CompactString> {
+    if id.is_empty() {
+        Err("must not be empty".into())
+    } else if id.len() > MAX_ID_LEN {
+        Err(format_compact!(
+            "must be at most {MAX_ID_LEN} characters long"
+        ))
+    } else if !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        Err("must be alphanumeric or '_'".into())
+    } else {
+        Ok(())
+    }
+}
+
+macro_rules! id_type {
+    ($(#[$attr:meta])* $name:ident) => {
+        $(#[$attr])*
+        #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+        #[serde(try_from = "CompactString", into = "CompactString")]
+        pub struct $name(CompactString);
+
+        impl $name {
+            #[inline]
+            pub fn new(id: impl Into<CompactString>) -> Result<Self, InvalidValue> {
+                let id = id.into();
+                match check_id(&id) {
+                    Ok(()) => Ok(Self(id)),
+                    Err(reason) => Err(InvalidValue::Id { id, reason }),
+                }
+            }
+
+            #[inline]
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl Deref for $name {
+            type Target = str;
+
+            #[inline]
+            fn deref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl AsRef<str> for $name {
+            #[inline]
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl Borrow<str> for $name {
+            #[inline]
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            #[inline]
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            #[inline]
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl fmt::Display for $name {
+            #[inline]
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = InvalidValue;
+
+            #[inline]
+            fn from_str(id: &str) -> Result<Self, Self::Err> {
+                Self::new(id)
+            }
+        }
+
+        impl TryFrom<CompactString> for $name {
+            type Error = InvalidValue;
+
+            #[inline]
+            fn try_from(id: CompactString) -> Result<Self, Self::Error> {
+                Self::new(id)
+            }
+        }
+
+        impl TryFrom<&str> for $name {
+            type Error = InvalidValue;
+
+            #[inline]
+            fn try_from(id: &str) -> Result<Self, Self::Error> {
+                Self::new(id)
+            }
+        }
+
+        impl TryFrom<String> for $name {
+            type Error = InvalidValue;
+
+            #[inline]
+            fn try_from(id: String) -> Result<Self, Self::Error> {
+                Self::new(id)
+            }
+        }
+
+        impl From<$name> for CompactString {
+            #[inline]
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+    };
+}
+
+id_type!(
+    /// The ID of a block device, e.g. `rootfs`.
+    DriveId
+);
+
+id_type!(
+    /// The ID of a network interface, e.g. `eth0`.
+    IfaceId
+);
+
+id_type!(
+    /// The ID of a pmem device, e.g. `pmem0`.
+    PmemId
+);
+
+/// A MAC address, written as six pairs of hex digits separated by `:`, e.g. `06:00:ac:10:00:02`.
+#[derive(
+    Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize,
+)]
+#[serde(try_from = "CompactString", into = "CompactString")]
+pub struct MacAddress(pub [u8; 6]);
+
+impl fmt::Display for MacAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(f, "{a:02x}:{b:02x}:{c:02x}:{d:02x}:{e:02x}:{g:02x}")
+    }
+}
+
+impl FromStr for MacAddress {
+    type Err = InvalidValue;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidValue::MacAddress(input.into());
+        let mut octets = [0; 6];
+        let mut parts = input.split(':');
+        for octet in &mut octets {
+            // `from_str_radix` would also accept a sign, e.g. `+f`.
+            let part = parts
+                .next()
+                .filter(|part| part.len() == 2 && part.bytes().all(|b| b.is_ascii_hexdigit()))
+                .ok_or_else(invalid)?;
+            *octet = u8::from_str_radix(part, 16).map_err(|_| invalid())?;
+        }
+        match parts.next() {
+            Some(_) => Err(invalid()),
+            None => Ok(Self(octets)),
+        }
+    }
+}
+
+impl TryFrom<CompactString> for MacAddress {
+    type Error = InvalidValue;
+
+    #[inline]
+    fn try_from(input: CompactString) -> Result<Self, Self::Error> {
+        input.parse()
+    }
+}
+
+impl From<MacAddress> for CompactString {
+    #[inline]
+    fn from(mac: MacAddress) -> Self {
+        format_compact!("{mac}")
+    }
+}
+
+impl From<[u8; 6]> for MacAddress {
+    #[inline]
+    fn from(octets: [u8; 6]) -> Self {
+        Self(octets)
+    }
+}
+
+/// The context ID of a guest's vsock device; CIDs 0, 1 and 2 are reserved for the hypervisor,
+/// local loopback and the host.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[serde(try_from = "u32", into = "u32")]
+pub struct VsockCid(u32);
+
+impl VsockCid {
+    /// The lowest CID that can be assigned to a guest.
+    pub const MIN: Self = Self(3);
+
+    #[inline]
+    pub fn new(cid: u32) -> Result<Self, InvalidValue> {
+        if cid < Self::MIN.0 {
+            return Err(InvalidValue::ReservedCid(cid));
+        }
+        Ok(Self(cid))
+    }
+
+    #[inline]
+    pub fn get(self) -> u32 {
+        self.0
+    }
+}
+
+impl TryFrom<u32> for VsockCid {
+    type Error = InvalidValue;
+
+    #[inline]
+    fn try_from(cid: u32) -> Result<Self, Self::Error> {
+        Self::new(cid)
+    }
+}
+
+impl From<VsockCid> for u32 {
+    #[inline]
+    fn from(cid: VsockCid) -> Self {
+        cid.0
+    }
+}
+
+impl fmt::Display for VsockCid {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for VsockCid {
+    type Err = InvalidValue;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let cid = input
+            .trim()
+            .parse()
+            .map_err(|_| InvalidValue::Number(input.into()))?;
+        Self::new(cid)
+    }
+}
+
+/// A number of vCPUs, between 1 and [`MAX_VCPUS`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[serde(try_from = "u32", into = "u32")]
+pub struct VcpuCount(u8);
+
+impl VcpuCount {
+    pub const MIN: Self = Self(1);
+    pub const MAX: Self = Self(MAX_VCPUS);
+
+    #[inline]
+    pub fn new(count: u32) -> Result<Self, InvalidValue> {
+        match u8::try_from(count) {
+            Ok(count @ 1..=MAX_VCPUS) => Ok(Self(count)),
+            _ => Err(InvalidValue::VcpuCount(count)),
+        }
+    }
+
+    #[inline]
+    pub fn get(self) -> u8 {
+        self.0
+    }
+}
+
+impl Default for VcpuCount {
+    #[inline]
+    fn default() -> Self {
+        Self::MIN
+    }
+}
+
+impl TryFrom<u32> for VcpuCount {
+    type Error = InvalidValue;
+
+    #[inline]
+    fn try_from(count: u32) -> Result<Self, Self::Error> {
+        Self::new(count)
+    }
+}
+
+impl TryFrom<u8> for VcpuCount {
+    type Error = InvalidValue;
+
+    #[inline]
+    fn try_from(count: u8) -> Result<Self, Self::Error> {
+        Self::new(count.into())
+    }
+}
+
+impl From<VcpuCount> for u32 {
+    #[inline]
+    fn from(count: VcpuCount) -> Self {
+        count.0.into()
+    }
+}
+
+impl fmt::Display for VcpuCount {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for VcpuCount {
+    type Err = InvalidValue;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let count = input
+            .trim()
+            .parse()
+            .map_err(|_| InvalidValue::Number(input.into()))?;
+        Self::new(count)
+    }
+}
+
+/// An amount of memory in MiB.
+#[derive(
+    Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct MiB(pub u32);
+
+impl MiB {
+    #[inline]
+    pub const fn bytes(self) -> u64 {
+        self.0 as u64 * (1 << 20)
+    }
+}
+
+impl From<u32> for MiB {
+    #[inline]
+    fn from(mib: u32) -> Self {
+        Self(mib)
+    }
+}
+
+impl From<MiB> for u32 {
+    #[inline]
+    fn from(MiB(mib): MiB) -> Self {
+        mib
+    }
+}
+
+impl From<MiB> for i64 {
+    #[inline]
+    fn from(MiB(mib): MiB) -> Self {
+        mib.into()
+    }
+}
+
+impl TryFrom<i64> for MiB {
+    type Error = InvalidValue;
+
+    #[inline]
+    fn try_from(mib: i64) -> Result<Self, Self::Error> {
+        u32::try_from(mib)
+            .map(Self)
+            .map_err(|_| InvalidValue::Number(format_compact!("{mib}")))
+    }
+}
+
+impl fmt::Display for MiB {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} MiB", self.0)
+    }
+}
+
+impl FromStr for MiB {
+    type Err = InvalidValue;
+
+    /// Parse a plain number of MiB, optionally followed by `MiB`, e.g. `"1024"` or `"1024 MiB"`.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let number = input.trim();
+        let number = number.strip_suffix("MiB").unwrap_or(number);
+        number
+            .trim()
+            .parse()
+            .map(Self)
+            .map_err(|_| InvalidValue::Number(input.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_are_short_and_alphanumeric() {
+        assert_eq!(DriveId::new("rootfs_1").unwrap().as_str(), "rootfs_1");
+        assert_eq!("eth0".parse::<IfaceId>().unwrap(), "eth0");
+        assert_eq!(
+            serde_json::from_str::<PmemId>(r#""pmem0""#).unwrap(),
+            "pmem0"
+        );
+        assert!(serde_json::from_str::<PmemId>(r#""pmem-0""#).is_err());
+        assert!(DriveId::new("a".repeat(MAX_ID_LEN)).is_ok());
+
+        for (id, reason) in [
+            ("", "must not be empty"),
+            ("root-fs", "must be alphanumeric or '_'"),
+            ("eth 0", "must be alphanumeric or '_'"),
+            ("rootfé", "must be alphanumeric or '_'"),
+        ] {
+            assert_eq!(
+                IfaceId::new(id),
+                Err(InvalidValue::Id {
+                    id: id.into(),
+                    reason: reason.into(),
+                })
+            );
+        }
+        let long = "a".repeat(MAX_ID_LEN + 1);
+        assert_eq!(
+            DriveId::new(long.as_str()).unwrap_err().to_string(),
+            format!("invalid ID {long:?}: must be at most {MAX_ID_LEN} characters long")
+        );
+    }
+
+    #[test]
+    fn mac_addresses_are_six_pairs_of_hex_digits() {
+        let mac: MacAddress = "06:00:AC:10:00:02".parse().unwrap();
+        assert_eq!(mac, MacAddress([0x06, 0x00, 0xac, 0x10, 0x00, 0x02]));
+        assert_eq!(mac.to_string(), "06:00:ac:10:00:02");
+
+        for input in [
+            "",
+            "06:00:ac:10:00",
+            "06:00:ac:10:00:02:03",
+            "06:00:ac:10:00:2",
+            "06:00:ac:10:00:002",
+            "06-00-ac-10-00-02",
+            "06:00:ac:10:00:0g",
+            "+6:00:ac:10:00:02",
+            "06:00:ac:10:00:-2",
+            "06:00:ac:10:00:02:",
+        ] {
+            assert_eq!(
+                input.parse::<MacAddress>(),
+                Err(InvalidValue::MacAddress(input.into())),
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn vsock_cids_skip_the_reserved_ones() {
+        assert_eq!("3".parse::<VsockCid>().unwrap().get(), 3);
+        assert_eq!(" 42 ".parse::<VsockCid>().unwrap().get(), 42);
+        assert_eq!("2".parse::<VsockCid>(), Err(InvalidValue::ReservedCid(2)));
+        assert_eq!(
+            "three".parse::<VsockCid>(),
+            Err(InvalidValue::Number("three".into()))
+        );
+    }
+
+    #[test]
+    fn vcpu_counts_are_within_bounds() {
+        assert_eq!("1".parse::<VcpuCount>(), Ok(VcpuCount::MIN));
+        assert_eq!("32".parse::<VcpuCount>(), Ok(VcpuCount::MAX));
+        assert_eq!("0".parse::<VcpuCount>(), Err(InvalidValue::VcpuCount(0)));
+        assert_eq!("33".parse::<VcpuCount>(), Err(InvalidValue::VcpuCount(33)));
+        assert_eq!(
+            "300".parse::<VcpuCount>(),
+            Err(InvalidValue::VcpuCount(300))
+        );
+        assert_eq!(
+            "-1".parse::<VcpuCount>(),
+            Err(InvalidValue::Number("-1".into()))
+        );
+    }
+
+    #[test]
+    fn mib_accepts_an_optional_unit() {
+        assert_eq!("1024".parse::<MiB>(), Ok(MiB(1024)));
+        assert_eq!(" 1024 MiB ".parse::<MiB>(), Ok(MiB(1024)));
+        assert_eq!("1024MiB".parse::<MiB>(), Ok(MiB(1024)));
+        assert_eq!(MiB(1024).to_string(), "1024 MiB");
+        for input in ["", "MiB", "1 GiB", "-1"] {
+            assert_eq!(
+                input.parse::<MiB>(),
+                Err(InvalidValue::Number(input.into())),
+                "{input:?}"
+            );
+        }
+    }
+}
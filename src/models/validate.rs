@@ -0,0 +1,690 @@
+//! Client-side validation of models, to catch requests that Firecracker would reject before
+//! sending them.
+//!
+//! Checks are limited to what can be decided from the models alone; e.g., whether a path on the
+//! host exists is left to Firecracker.
+
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fmt,
+    net::Ipv4Addr,
+};
+
+use compact_str::{format_compact, CompactString, ToCompactString};
+
+use crate::models::{
+    self, drive_kind::InvalidDrive, machine_configuration::HugePages, DriveKind, MiB,
+    VhostUserBlock, VirtioBlock,
+};
+
+/// Maximum length of a host network interface name, excluding the terminating NUL (`IFNAMSIZ`).
+const MAX_HOST_DEV_NAME_LEN: usize = 15;
+
+/// A field that Firecracker would reject.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Violation {
+    /// Path to the field, e.g. `drives[1].partuuid`, with the same field names as the JSON.
+    pub path: String,
+    pub message: CompactString,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// All the [`Violation`]s of a model, in field order.
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub struct Violations(pub Vec<Violation>);
+
+impl fmt::Display for Violations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, violation) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{violation}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A model that can be checked for values Firecracker would reject.
+///
+/// # Example
+///
+/// ```
+/// use wick::models::{MachineConfiguration, MiB, Validate, VcpuCount};
+///
+/// let mut config = MachineConfiguration::new(MiB(1024), VcpuCount::new(3).unwrap());
+/// config.smt = Some(true);
+/// let violations = config.validate().unwrap_err();
+/// assert_eq!(violations.0[0].path, "vcpu_count");
+/// ```
+pub trait Validate {
+    /// Append the violations of `self` to `violations`, with paths relative to `path`, i.e. the
+    /// path of `self` in an enclosing model, or `""`.
+    fn check(&self, path: &str, violations: &mut Vec<Violation>);
+
+    /// All the violations of `self`, if any.
+    fn validate(&self) -> Result<(), Violations> {
+        let mut violations = Vec::new();
+        self.check("", &mut violations);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(Violations(violations))
+        }
+    }
+}
+
+impl<T: Validate + ?Sized> Validate for Box<T> {
+    #[inline]
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        (**self).check(path, violations);
+    }
+}
+
+impl<T: Validate> Validate for Option<T> {
+    #[inline]
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        if let Some(value) = self {
+            value.check(path, violations);
+        }
+    }
+}
+
+impl<T: Validate> Validate for [T] {
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        for (i, value) in self.iter().enumerate() {
+            value.check(&format!("{path}[{i}]"), violations);
+        }
+    }
+}
+
+impl<T: Validate> Validate for Vec<T> {
+    #[inline]
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        self.as_slice().check(path, violations);
+    }
+}
+
+/// Path of `field` in the model at `path`.
+fn at(path: &str, field: &str) -> String {
+    if path.is_empty() {
+        field.to_owned()
+    } else {
+        format!("{path}.{field}")
+    }
+}
+
+fn push(violations: &mut Vec<Violation>, path: String, message: impl Into<CompactString>) {
+    violations.push(Violation {
+        path,
+        message: message.into(),
+    });
+}
+
+fn non_negative(violations: &mut Vec<Violation>, path: &str, field: &str, value: i64) {
+    if value < 0 {
+        push(violations, at(path, field), "must not be negative");
+    }
+}
+
+fn host_dev_name(violations: &mut Vec<Violation>, path: &str, name: &str) {
+    if name.is_empty() {
+        push(violations, at(path, "host_dev_name"), "must not be empty");
+    } else if name.len() > MAX_HOST_DEV_NAME_LEN {
+        let message = format_compact!("must be at most {MAX_HOST_DEV_NAME_LEN} characters long");
+        push(violations, at(path, "host_dev_name"), message);
+    }
+}
+
+/// A partition UUID is either a GPT GUID (`xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx`) or an MBR
+/// disk signature and partition number (`xxxxxxxx-pp`), in hex.
+fn is_partuuid(partuuid: &str) -> bool {
+    let groups: Vec<_> = partuuid.split('-').map(str::len).collect();
+    let hex = partuuid.chars().all(|c| c.is_ascii_hexdigit() || c == '-');
+    hex && (groups == [8, 4, 4, 4, 12] || groups == [8, 2])
+}
+
+fn partuuid(violations: &mut Vec<Violation>, path: &str, partuuid: Option<&str>) {
+    if partuuid.is_some_and(|partuuid| !is_partuuid(partuuid)) {
+        push(
+            violations,
+            at(path, "partuuid"),
+            "must be a GPT GUID or an MBR 'SSSSSSSS-PP' partition UUID",
+        );
+    }
+}
+
+impl Validate for models::TokenBucket {
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        non_negative(violations, path, "size", self.size);
+        non_negative(violations, path, "refill_time", self.refill_time);
+        if let Some(one_time_burst) = self.one_time_burst {
+            non_negative(violations, path, "one_time_burst", one_time_burst);
+        }
+    }
+}
+
+impl Validate for models::RateLimiter {
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        self.bandwidth.check(&at(path, "bandwidth"), violations);
+        self.ops.check(&at(path, "ops"), violations);
+    }
+}
+
+impl Validate for models::Balloon {
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        if let Some(interval) = self.stats_polling_interval_s {
+            non_negative(
+                violations,
+                path,
+                "stats_polling_interval_s",
+                interval.into(),
+            );
+        }
+    }
+}
+
+impl Validate for models::BalloonStatsUpdate {
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        let interval = self.stats_polling_interval_s.into();
+        non_negative(violations, path, "stats_polling_interval_s", interval);
+    }
+}
+
+impl Validate for models::BootSource {
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        if self.kernel_image_path.as_str().is_empty() {
+            push(
+                violations,
+                at(path, "kernel_image_path"),
+                "must not be empty",
+            );
+        }
+    }
+}
+
+impl Validate for models::CpuConfig {
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        let mut width = |path: String, bitmap: &models::Bitmap, bits: u8| {
+            if let Err(err) = bitmap.check_width(bits) {
+                push(violations, path, err.to_compact_string());
+            }
+        };
+        for (i, leaf) in self.cpuid_modifiers.iter().flatten().enumerate() {
+            let path = at(path, &format!("cpuid_modifiers[{i}]"));
+            for (j, modifier) in leaf.modifiers.iter().enumerate() {
+                width(
+                    at(&path, &format!("modifiers[{j}].bitmap")),
+                    &modifier.bitmap,
+                    32,
+                );
+            }
+        }
+        for (i, modifier) in self.msr_modifiers.iter().flatten().enumerate() {
+            width(
+                at(path, &format!("msr_modifiers[{i}].bitmap")),
+                &modifier.bitmap,
+                64,
+            );
+        }
+        for (i, feature) in self.vcpu_features.iter().flatten().enumerate() {
+            width(
+                at(path, &format!("vcpu_features[{i}].bitmap")),
+                &feature.bitmap,
+                32,
+            );
+        }
+    }
+}
+
+impl Validate for models::Drive {
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        partuuid(violations, path, self.partuuid.as_deref());
+        if let Err(err) = DriveKind::try_from(self.clone()) {
+            let field = match &err {
+                InvalidDrive::BothBackends(_) => "socket",
+                InvalidDrive::NoBackend(_) => "path_on_host",
+                InvalidDrive::MissingReadOnly(_) => "is_read_only",
+                InvalidDrive::VhostUserField { field, .. } => field,
+            };
+            push(violations, at(path, field), err.to_compact_string());
+        }
+        self.rate_limiter
+            .check(&at(path, "rate_limiter"), violations);
+    }
+}
+
+impl Validate for VirtioBlock {
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        partuuid(violations, path, self.partuuid.as_deref());
+        if self.path_on_host.as_str().is_empty() {
+            push(violations, at(path, "path_on_host"), "must not be empty");
+        }
+        self.rate_limiter
+            .check(&at(path, "rate_limiter"), violations);
+    }
+}
+
+impl Validate for VhostUserBlock {
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        partuuid(violations, path, self.partuuid.as_deref());
+        if self.socket.as_str().is_empty() {
+            push(violations, at(path, "socket"), "must not be empty");
+        }
+    }
+}
+
+impl Validate for DriveKind {
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        match self {
+            Self::VirtioBlock(drive) => drive.check(path, violations),
+            Self::VhostUser(drive) => drive.check(path, violations),
+        }
+    }
+}
+
+impl Validate for models::EntropyDevice {
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        self.rate_limiter
+            .check(&at(path, "rate_limiter"), violations);
+    }
+}
+
+impl Validate for models::MachineConfiguration {
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        let vcpus = self.vcpu_count.get();
+        if self.smt == Some(true) && vcpus > 1 && vcpus % 2 != 0 {
+            let message = "must be 1 or even with SMT enabled";
+            push(violations, at(path, "vcpu_count"), message);
+        }
+        let MiB(mem_size_mib) = self.mem_size_mib;
+        if mem_size_mib == 0 {
+            push(violations, at(path, "mem_size_mib"), "must be positive");
+        } else if self.huge_pages == Some(HugePages::TwoM) && mem_size_mib % 2 != 0 {
+            let message = "must be a multiple of 2 MiB with 2M huge pages";
+            push(violations, at(path, "mem_size_mib"), message);
+        }
+    }
+}
+
+impl Validate for models::MemoryHotplugConfig {
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        if self.total_size_mib == MiB(0) {
+            push(violations, at(path, "total_size_mib"), "must be positive");
+        }
+        if let Some(MiB(block)) = self.block_size_mib {
+            if block < 2 || !block.is_power_of_two() {
+                let message = "must be a power of 2, of at least 2 MiB";
+                push(violations, at(path, "block_size_mib"), message);
+            }
+        }
+        if let Some(MiB(slot)) = self.slot_size_mib {
+            let MiB(block) = self.block_size_mib.unwrap_or(MiB(2));
+            if slot == 0 || slot % block.max(1) != 0 {
+                let message = "must be a positive multiple of the block size";
+                push(violations, at(path, "slot_size_mib"), message);
+            }
+        }
+    }
+}
+
+impl Validate for models::MmdsConfig {
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        if let Some(address) = &self.ipv4_address {
+            if !address.parse().is_ok_and(|ip: Ipv4Addr| ip.is_link_local()) {
+                let message = "must be an IPv4 link-local address, in 169.254.0.0/16";
+                push(violations, at(path, "ipv4_address"), message);
+            }
+        }
+    }
+}
+
+impl Validate for models::NetworkInterface {
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        host_dev_name(violations, path, &self.host_dev_name);
+        self.rx_rate_limiter
+            .check(&at(path, "rx_rate_limiter"), violations);
+        self.tx_rate_limiter
+            .check(&at(path, "tx_rate_limiter"), violations);
+    }
+}
+
+impl Validate for models::NetworkOverride {
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        host_dev_name(violations, path, &self.host_dev_name);
+    }
+}
+
+impl Validate for models::PartialDrive {
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        self.rate_limiter
+            .check(&at(path, "rate_limiter"), violations);
+    }
+}
+
+impl Validate for models::PartialNetworkInterface {
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        self.rx_rate_limiter
+            .check(&at(path, "rx_rate_limiter"), violations);
+        self.tx_rate_limiter
+            .check(&at(path, "tx_rate_limiter"), violations);
+    }
+}
+
+impl Validate for models::Pmem {
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        if self.path_on_host.as_str().is_empty() {
+            push(violations, at(path, "path_on_host"), "must not be empty");
+        }
+    }
+}
+
+impl Validate for models::SnapshotLoadParams {
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        match (&self.mem_file_path, &self.mem_backend) {
+            (Some(_), Some(_)) => push(
+                violations,
+                at(path, "mem_backend"),
+                "must not be set along with mem_file_path",
+            ),
+            (None, None) => push(
+                violations,
+                at(path, "mem_backend"),
+                "either mem_backend or mem_file_path must be set",
+            ),
+            _ => {}
+        }
+        self.network_overrides
+            .check(&at(path, "network_overrides"), violations);
+    }
+}
+
+impl Validate for models::Vsock {
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        if self.uds_path.as_str().is_empty() {
+            push(violations, at(path, "uds_path"), "must not be empty");
+        }
+    }
+}
+
+/// Models without any constraints that can be checked client-side.
+macro_rules! unconstrained {
+    ($($model:ty),* $(,)?) => {
+        $(
+            impl Validate for $model {
+                #[inline]
+                fn check(&self, _: &str, _: &mut Vec<Violation>) {}
+            }
+        )*
+    };
+}
+
+unconstrained!(
+    models::BalloonHintingStatus,
+    models::BalloonStartHinting,
+    models::BalloonStats,
+    models::BalloonUpdate,
+    models::Error,
+    models::FirecrackerVersion,
+    models::InstanceActionInfo,
+    models::InstanceInfo,
+    models::Logger,
+    models::MemoryBackend,
+    models::MemoryHotplugSizeUpdate,
+    models::MemoryHotplugStatus,
+    models::Metrics,
+    models::Serial,
+    models::SnapshotCreateParams,
+    models::Vm,
+);
+
+impl Validate for models::FullVmConfiguration {
+    fn check(&self, path: &str, violations: &mut Vec<Violation>) {
+        self.balloon.check(&at(path, "balloon"), violations);
+        self.drives.check(&at(path, "drives"), violations);
+        self.boot_source.check(&at(path, "boot-source"), violations);
+        self.cpu_config.check(&at(path, "cpu-config"), violations);
+        self.logger.check(&at(path, "logger"), violations);
+        self.machine_config
+            .check(&at(path, "machine-config"), violations);
+        self.metrics.check(&at(path, "metrics"), violations);
+        self.mmds_config.check(&at(path, "mmds-config"), violations);
+        self.network_interfaces
+            .check(&at(path, "network-interfaces"), violations);
+        self.vsock.check(&at(path, "vsock"), violations);
+        self.entropy.check(&at(path, "entropy"), violations);
+        self.serial.check(&at(path, "serial"), violations);
+        self.memory_hotplug
+            .check(&at(path, "memory-hotplug"), violations);
+        self.pmem.check(&at(path, "pmem"), violations);
+
+        let drives = self.drives.as_deref().unwrap_or_default();
+        let ifaces = self.network_interfaces.as_deref().unwrap_or_default();
+        let pmem = self.pmem.as_deref().unwrap_or_default();
+
+        unique(violations, &at(path, "drives"), "drive_id", drives, |d| {
+            &d.drive_id
+        });
+        unique(
+            violations,
+            &at(path, "network-interfaces"),
+            "iface_id",
+            ifaces,
+            |i| &i.iface_id,
+        );
+        unique(violations, &at(path, "pmem"), "id", pmem, |p| &p.id);
+
+        // At most one root device, among both block and pmem devices; every one after the first
+        // is a violation.
+        for root in root_devices(self, path).into_iter().skip(1) {
+            push(violations, root, "only one device can be the root device");
+        }
+
+        if let Some(mmds_config) = &self.mmds_config {
+            for (i, iface_id) in mmds_config.network_interfaces.iter().enumerate() {
+                if !ifaces.iter().any(|iface| iface.iface_id == *iface_id) {
+                    push(
+                        violations,
+                        at(path, &format!("mmds-config.network_interfaces[{i}]")),
+                        format_compact!(
+                            "no network interface {:?} is configured",
+                            iface_id.as_str()
+                        ),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Paths of the root device flags that are set in `config`, block devices first.
+pub(crate) fn root_devices(config: &models::FullVmConfiguration, path: &str) -> Vec<String> {
+    let drives = config.drives.iter().flatten().enumerate();
+    let drives = drives
+        .filter(|(_, drive)| drive.is_root_device)
+        .map(|(i, _)| at(path, &format!("drives[{i}].is_root_device")));
+    let pmem = config.pmem.iter().flatten().enumerate();
+    let pmem = pmem
+        .filter(|(_, pmem)| pmem.is_root_device())
+        .map(|(i, _)| at(path, &format!("pmem[{i}].root_device")));
+    drives.chain(pmem).collect()
+}
+
+/// Flag every model in `models` whose ID, as returned by `id`, was already used by an earlier one.
+fn unique<T>(
+    violations: &mut Vec<Violation>,
+    path: &str,
+    field: &str,
+    models: &[T],
+    id: impl Fn(&T) -> &str,
+) {
+    let mut first = HashMap::new();
+    for (i, model) in models.iter().enumerate() {
+        match first.entry(id(model)) {
+            Entry::Occupied(entry) => push(
+                violations,
+                format!("{path}[{i}].{field}"),
+                format_compact!("duplicates {path}[{}].{field}", entry.get()),
+            ),
+            Entry::Vacant(entry) => {
+                entry.insert(i);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Drive, FullVmConfiguration, MachineConfiguration, NetworkInterface};
+
+    fn paths(model: &impl Validate) -> Vec<String> {
+        match model.validate() {
+            Ok(()) => Vec::new(),
+            Err(Violations(violations)) => violations.into_iter().map(|v| v.path).collect(),
+        }
+    }
+
+    fn machine(mem_size_mib: u32, vcpus: u32) -> MachineConfiguration {
+        let vcpus = models::VcpuCount::new(vcpus).unwrap();
+        MachineConfiguration::new(MiB(mem_size_mib), vcpus)
+    }
+
+    fn drive(id: &str, is_root_device: bool) -> Drive {
+        Drive::builder(id.parse().unwrap(), is_root_device)
+            .path_on_host("/rootfs.ext4")
+            .is_read_only(false)
+            .build()
+    }
+
+    fn iface(id: &str) -> NetworkInterface {
+        NetworkInterface::new("tap0", id.parse().unwrap())
+    }
+
+    #[test]
+    fn smt_requires_one_or_an_even_vcpu_count() {
+        for (vcpus, valid) in [(1, true), (2, true), (3, false), (4, true)] {
+            let mut config = machine(128, vcpus);
+            config.smt = Some(true);
+            let expected = if valid { vec![] } else { vec!["vcpu_count"] };
+            assert_eq!(paths(&config), expected, "{vcpus} vCPUs");
+        }
+        assert_eq!(paths(&machine(128, 3)), Vec::<String>::new());
+    }
+
+    #[test]
+    fn two_megabyte_huge_pages_require_an_even_memory_size() {
+        let mut config = machine(129, 1);
+        assert_eq!(paths(&config), Vec::<String>::new());
+        config.huge_pages = Some(HugePages::TwoM);
+        assert_eq!(paths(&config), ["mem_size_mib"]);
+        config.mem_size_mib = MiB(128);
+        assert_eq!(paths(&config), Vec::<String>::new());
+    }
+
+    #[test]
+    fn partuuid_is_a_gpt_guid_or_an_mbr_partition() {
+        for (partuuid, valid) in [
+            ("6a2ce84f-7f12-4a6d-b0c6-2f1c9d3e4b5a", true),
+            ("1234abcd-01", true),
+            ("1234abcd-1", false),
+            ("6a2ce84f-7f12-4a6d-b0c6", false),
+            ("zzzzzzzz-01", false),
+            ("", false),
+        ] {
+            let mut drive = drive("rootfs", true);
+            drive.partuuid = Some(partuuid.to_owned());
+            let expected = if valid { vec![] } else { vec!["partuuid"] };
+            assert_eq!(paths(&drive), expected, "{partuuid:?}");
+        }
+    }
+
+    #[test]
+    fn mmds_address_is_link_local() {
+        for (address, valid) in [
+            ("169.254.169.254", true),
+            ("169.254.0.1", true),
+            ("10.0.0.1", false),
+            ("169.255.0.1", false),
+            ("fe80::1", false),
+            ("not an address", false),
+        ] {
+            let mut config = models::MmdsConfig::new(vec![]);
+            config.ipv4_address = Some(address.into());
+            let expected = if valid { vec![] } else { vec!["ipv4_address"] };
+            assert_eq!(paths(&config), expected, "{address:?}");
+        }
+    }
+
+    #[test]
+    fn device_ids_are_unique() {
+        let config = FullVmConfiguration::builder()
+            .drive(drive("rootfs", true))
+            .drive(drive("scratch", false))
+            .drive(drive("rootfs", false))
+            .network_interface(iface("eth0"))
+            .network_interface(iface("eth0"))
+            .pmem(models::Pmem::new("pmem0".parse().unwrap(), "/a"))
+            .pmem(models::Pmem::new("pmem0".parse().unwrap(), "/b"))
+            .build();
+        let Err(Violations(violations)) = config.validate() else {
+            panic!("duplicate IDs not reported");
+        };
+        let violations: Vec<_> = violations.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            violations,
+            [
+                "drives[2].drive_id: duplicates drives[0].drive_id",
+                "network-interfaces[1].iface_id: duplicates network-interfaces[0].iface_id",
+                "pmem[1].id: duplicates pmem[0].id",
+            ]
+        );
+    }
+
+    #[test]
+    fn one_root_device_among_drives_and_pmem() {
+        let mut root_pmem = models::Pmem::new("pmem0".parse().unwrap(), "/rootfs.img");
+        root_pmem.root_device = Some(true);
+        let config = FullVmConfiguration::builder()
+            .drive(drive("rootfs", true))
+            .drive(drive("scratch", false))
+            .pmem(root_pmem)
+            .build();
+        assert_eq!(paths(&config), ["pmem[0].root_device"]);
+        assert_eq!(
+            config.check_root_device().unwrap_err().0,
+            ["drives[0].is_root_device", "pmem[0].root_device"]
+        );
+
+        let config = FullVmConfiguration::builder()
+            .drive(drive("rootfs", true))
+            .build();
+        assert_eq!(paths(&config), Vec::<String>::new());
+        assert!(config.check_root_device().is_ok());
+    }
+
+    #[test]
+    fn mmds_interfaces_are_configured() {
+        let eth0: models::IfaceId = "eth0".parse().unwrap();
+        let eth1: models::IfaceId = "eth1".parse().unwrap();
+        let config = FullVmConfiguration::builder()
+            .network_interface(iface("eth0"))
+            .mmds_config(models::MmdsConfig::new(vec![eth0, eth1]))
+            .build();
+        let Err(Violations(violations)) = config.validate() else {
+            panic!("unknown MMDS interface not reported");
+        };
+        assert_eq!(
+            violations,
+            [Violation {
+                path: "mmds-config.network_interfaces[1]".to_owned(),
+                message: "no network interface \"eth1\" is configured".into(),
+            }]
+        );
+    }
+}
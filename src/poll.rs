@@ -0,0 +1,22 @@
+//! Polling of asynchronous operations that the guest carries out after the VMM accepted them.
+
+use std::time::Duration;
+
+/// How often, and for how long, to poll the status of an operation, e.g. by
+/// [`hotplug::resize`](crate::hotplug::resize) and
+/// [`run_hinting`](crate::balloon::run_hinting).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Polling {
+    pub interval: Duration,
+    pub timeout: Duration,
+}
+
+impl Default for Polling {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_millis(100),
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
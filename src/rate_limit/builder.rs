@@ -0,0 +1,344 @@
+use std::fmt::{self, Write};
+
+use crate::{
+    models::{RateLimiter, TokenBucket},
+    rate_limit::{
+        units::{fmt_bytes, trim_decimals},
+        ByteRate, ByteSize, Error, OpRate,
+    },
+};
+
+const BANDWIDTH: &str = "bandwidth";
+const OPS: &str = "ops";
+
+/// Rate, capacity and one-time burst of a single token bucket, in tokens (bytes or
+/// operations).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+struct BucketSpec {
+    rate_per_sec: Option<u64>,
+    capacity: Option<u64>,
+    one_time_burst: Option<u64>,
+}
+
+impl BucketSpec {
+    fn is_unset(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn build(self, bucket: &'static str) -> Result<Option<Box<TokenBucket>>, Error> {
+        if self.is_unset() {
+            return Ok(None);
+        }
+        let Some(rate) = self.rate_per_sec else {
+            return Err(Error::BurstWithoutRate { bucket });
+        };
+        if rate == 0 {
+            return Err(Error::ZeroRate { bucket });
+        }
+
+        // A second's worth of tokens, unless the capacity is set explicitly. The refill time is
+        // rounded to Firecracker's millisecond granularity, and the size is then adjusted to
+        // keep the rate as close as possible to the requested one.
+        let capacity = self.capacity.unwrap_or(rate) as u128;
+        let refill_time = (capacity * 1000 + u128::from(rate) / 2) / u128::from(rate);
+        if refill_time == 0 {
+            return Err(Error::CapacityTooSmall { bucket });
+        }
+        let size = u128::from(rate) * refill_time / 1000;
+
+        let overflow = |_| Error::Overflow { bucket };
+        Ok(Some(Box::new(TokenBucket {
+            one_time_burst: self
+                .one_time_burst
+                .map(i64::try_from)
+                .transpose()
+                .map_err(overflow)?,
+            refill_time: i64::try_from(refill_time).map_err(overflow)?,
+            size: i64::try_from(size).map_err(overflow)?,
+            extras: Default::default(),
+        })))
+    }
+}
+
+/// Builds [`RateLimiter`]s out of rates in human units.
+///
+/// Each bucket's capacity defaults to a second's worth of its rate.
+///
+/// # Example
+///
+/// ```
+/// use wick::rate_limit::RateLimiterBuilder;
+///
+/// # fn main() -> Result<(), wick::rate_limit::Error> {
+/// let limiter = RateLimiterBuilder::new()
+///     .bandwidth("200 MiB/s".parse()?)
+///     .bandwidth_one_time_burst("1 GiB".parse()?)
+///     .ops("5000 IOPS".parse()?)
+///     .build()?;
+///
+/// let bandwidth = limiter.bandwidth.as_deref().unwrap();
+/// assert_eq!((bandwidth.size, bandwidth.refill_time), (200 << 20, 1000));
+/// assert_eq!(
+///     wick::rate_limit::describe(&limiter),
+///     "bandwidth 200 MiB/s (bucket 200 MiB, one-time burst 1 GiB), ops 5000 IOPS (bucket 5000)",
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct RateLimiterBuilder {
+    bandwidth: BucketSpec,
+    ops: BucketSpec,
+}
+
+impl RateLimiterBuilder {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sustained throughput of the bandwidth bucket.
+    #[inline]
+    pub fn bandwidth(mut self, rate: ByteRate) -> Self {
+        self.bandwidth.rate_per_sec = Some(rate.0);
+        self
+    }
+
+    /// Capacity of the bandwidth bucket, i.e. the largest burst after a period of inactivity.
+    #[inline]
+    pub fn bandwidth_capacity(mut self, capacity: ByteSize) -> Self {
+        self.bandwidth.capacity = Some(capacity.0);
+        self
+    }
+
+    /// Initial burst of the bandwidth bucket, consumed before the rate limit kicks in.
+    #[inline]
+    pub fn bandwidth_one_time_burst(mut self, burst: ByteSize) -> Self {
+        self.bandwidth.one_time_burst = Some(burst.0);
+        self
+    }
+
+    /// Sustained rate of the ops bucket.
+    #[inline]
+    pub fn ops(mut self, rate: OpRate) -> Self {
+        self.ops.rate_per_sec = Some(rate.0);
+        self
+    }
+
+    /// Capacity of the ops bucket, i.e. the largest burst after a period of inactivity.
+    #[inline]
+    pub fn ops_capacity(mut self, capacity: u64) -> Self {
+        self.ops.capacity = Some(capacity);
+        self
+    }
+
+    /// Initial burst of the ops bucket, consumed before the rate limit kicks in.
+    #[inline]
+    pub fn ops_one_time_burst(mut self, burst: u64) -> Self {
+        self.ops.one_time_burst = Some(burst);
+        self
+    }
+
+    /// Derive the [`RateLimiter`].
+    pub fn build(self) -> Result<RateLimiter, Error> {
+        Ok(RateLimiter {
+            bandwidth: self.bandwidth.build(BANDWIDTH)?,
+            ops: self.ops.build(OPS)?,
+            extras: Default::default(),
+        })
+    }
+}
+
+/// Check a [`TokenBucket`] for nonsensical combinations of values, which Firecracker would
+/// either reject or silently treat as "unlimited".
+pub fn validate_bucket(bucket: &TokenBucket, name: &'static str) -> Result<(), Error> {
+    let TokenBucket {
+        one_time_burst,
+        refill_time,
+        size,
+        ..
+    } = *bucket;
+    let burst = one_time_burst.unwrap_or(0);
+
+    if size < 0 || refill_time < 0 || burst < 0 {
+        return Err(Error::Negative { bucket: name });
+    }
+    if size == 0 && burst > 0 {
+        return Err(Error::BurstWithoutSize { bucket: name });
+    }
+    if size == 0 {
+        return Err(Error::ZeroSize { bucket: name });
+    }
+    if refill_time == 0 {
+        return Err(Error::ZeroRefillTime { bucket: name });
+    }
+    Ok(())
+}
+
+/// Check both buckets of a [`RateLimiter`] through [`validate_bucket`].
+pub fn validate(limiter: &RateLimiter) -> Result<(), Error> {
+    if let Some(bandwidth) = &limiter.bandwidth {
+        validate_bucket(bandwidth, BANDWIDTH)?;
+    }
+    if let Some(ops) = &limiter.ops {
+        validate_bucket(ops, OPS)?;
+    }
+    Ok(())
+}
+
+/// Describe a [`RateLimiter`] in human units, e.g.
+/// `"bandwidth 200 MiB/s (bucket 200 MiB), ops 5000 IOPS (bucket 5000)"`.
+///
+/// Buckets that Firecracker ignores (zero size or refill time) are described as such.
+pub fn describe(limiter: &RateLimiter) -> String {
+    struct Bucket<'a>(&'a TokenBucket, bool);
+
+    impl fmt::Display for Bucket<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let Self(bucket, bytes) = *self;
+            if bucket.size <= 0 || bucket.refill_time <= 0 {
+                return f.write_str("disabled");
+            }
+            let rate = bucket.size as f64 * 1000.0 / bucket.refill_time as f64;
+            let size = bucket.size as f64;
+            if bytes {
+                fmt_bytes(f, rate, "/s")?;
+                f.write_str(" (bucket ")?;
+                fmt_bytes(f, size, "")?;
+            } else {
+                write!(f, "{} IOPS (bucket {}", trim_decimals(rate), bucket.size)?;
+            }
+            if let Some(burst) = bucket.one_time_burst.filter(|&burst| burst > 0) {
+                f.write_str(", one-time burst ")?;
+                if bytes {
+                    fmt_bytes(f, burst as f64, "")?;
+                } else {
+                    write!(f, "{burst}")?;
+                }
+            }
+            f.write_char(')')
+        }
+    }
+
+    let mut parts = Vec::with_capacity(2);
+    if let Some(bandwidth) = &limiter.bandwidth {
+        parts.push(format!("{BANDWIDTH} {}", Bucket(bandwidth, true)));
+    }
+    if let Some(ops) = &limiter.ops {
+        parts.push(format!("{OPS} {}", Bucket(ops, false)));
+    }
+    if parts.is_empty() {
+        return "unlimited".to_owned();
+    }
+    parts.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bucket(size: i64, refill_time: i64, one_time_burst: Option<i64>) -> TokenBucket {
+        TokenBucket {
+            one_time_burst,
+            refill_time,
+            size,
+            extras: Default::default(),
+        }
+    }
+
+    #[test]
+    fn builds_nothing_without_rates() {
+        let limiter = RateLimiterBuilder::new().build().unwrap();
+        assert_eq!((limiter.bandwidth, limiter.ops), (None, None));
+        assert_eq!(describe(&RateLimiter::default()), "unlimited");
+    }
+
+    #[test]
+    fn defaults_to_a_second_worth_of_tokens() {
+        let limiter = RateLimiterBuilder::new()
+            .bandwidth(ByteRate(125_000_000))
+            .ops(OpRate(5000))
+            .ops_one_time_burst(100)
+            .build()
+            .unwrap();
+        assert_eq!(
+            limiter.bandwidth.as_deref(),
+            Some(&bucket(125_000_000, 1000, None))
+        );
+        assert_eq!(limiter.ops.as_deref(), Some(&bucket(5000, 1000, Some(100))));
+    }
+
+    #[test]
+    fn rounds_the_refill_time_to_milliseconds() {
+        // 1000 tokens take 333.3 ms at 3000/s; the size is adjusted to keep the rate.
+        let limiter = RateLimiterBuilder::new()
+            .bandwidth(ByteRate(3000))
+            .bandwidth_capacity(ByteSize(1000))
+            .build()
+            .unwrap();
+        assert_eq!(limiter.bandwidth.as_deref(), Some(&bucket(999, 333, None)));
+
+        // Larger buckets than a second's worth refill over several seconds.
+        let limiter = RateLimiterBuilder::new()
+            .ops(OpRate(1000))
+            .ops_capacity(10_000)
+            .build()
+            .unwrap();
+        assert_eq!(limiter.ops.as_deref(), Some(&bucket(10_000, 10_000, None)));
+    }
+
+    #[test]
+    fn rejects_unbuildable_buckets() {
+        let bandwidth = Err(Error::BurstWithoutRate { bucket: BANDWIDTH });
+        let builder = RateLimiterBuilder::new().bandwidth_one_time_burst(ByteSize(1 << 30));
+        assert_eq!(builder.build(), bandwidth);
+
+        let ops = Err(Error::ZeroRate { bucket: OPS });
+        assert_eq!(RateLimiterBuilder::new().ops(OpRate(0)).build(), ops);
+
+        let too_small = Err(Error::CapacityTooSmall { bucket: BANDWIDTH });
+        let builder = RateLimiterBuilder::new()
+            .bandwidth(ByteRate(3000))
+            .bandwidth_capacity(ByteSize(1));
+        assert_eq!(builder.build(), too_small);
+
+        let overflow = Err(Error::Overflow { bucket: BANDWIDTH });
+        let builder = RateLimiterBuilder::new().bandwidth(ByteRate(u64::MAX));
+        assert_eq!(builder.build(), overflow);
+    }
+
+    #[test]
+    fn validates_buckets() {
+        let name = BANDWIDTH;
+        assert_eq!(validate_bucket(&bucket(1000, 1000, Some(10)), name), Ok(()));
+        assert_eq!(
+            validate_bucket(&bucket(1000, -1, None), name),
+            Err(Error::Negative { bucket: name })
+        );
+        assert_eq!(
+            validate_bucket(&bucket(0, 1000, Some(10)), name),
+            Err(Error::BurstWithoutSize { bucket: name })
+        );
+        assert_eq!(
+            validate_bucket(&bucket(0, 1000, None), name),
+            Err(Error::ZeroSize { bucket: name })
+        );
+        assert_eq!(
+            validate_bucket(&bucket(1000, 0, None), name),
+            Err(Error::ZeroRefillTime { bucket: name })
+        );
+    }
+
+    #[test]
+    fn describes_disabled_buckets() {
+        let limiter = RateLimiter {
+            bandwidth: Some(Box::new(bucket(0, 1000, None))),
+            ops: Some(Box::new(bucket(500, 100, None))),
+            extras: Default::default(),
+        };
+        assert_eq!(
+            describe(&limiter),
+            "bandwidth disabled, ops 5000 IOPS (bucket 500)"
+        );
+    }
+}
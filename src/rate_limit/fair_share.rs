@@ -0,0 +1,509 @@
+//! Division of a host-wide disk and network budget among microVMs, according to their weights
+//! and guarantees.
+//!
+//! Each microVM is first given its guarantee; what is left of the budget is then divided in
+//! proportion to the weights. If the guarantees alone exceed the budget, they are scaled down
+//! proportionally. A microVM's share of a device class is split evenly among its devices of that
+//! class; both directions of a network interface are given the same limits, as NICs are full
+//! duplex.
+
+use std::num::NonZeroU32;
+
+use compact_str::CompactString;
+use tracing::{info, instrument, warn, Level};
+
+use crate::{
+    models::{DriveId, IfaceId, PartialDrive, PartialNetworkInterface, RateLimiter},
+    rate_limit::{ByteRate, Error, OpRate, RateLimiterBuilder},
+    Api,
+};
+
+/// Rates of a device class; `None` leaves the corresponding bucket unlimited.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Rates {
+    pub bytes_per_sec: Option<ByteRate>,
+    pub ops_per_sec: Option<OpRate>,
+}
+
+impl Rates {
+    #[inline]
+    pub fn new(bytes_per_sec: ByteRate, ops_per_sec: OpRate) -> Self {
+        Self {
+            bytes_per_sec: Some(bytes_per_sec),
+            ops_per_sec: Some(ops_per_sec),
+        }
+    }
+
+    /// The [`RateLimiter`] enforcing these rates.
+    pub fn limiter(&self) -> Result<RateLimiter, Error> {
+        let mut builder = RateLimiterBuilder::new();
+        if let Some(rate) = self.bytes_per_sec {
+            builder = builder.bandwidth(rate);
+        }
+        if let Some(rate) = self.ops_per_sec {
+            builder = builder.ops(rate);
+        }
+        builder.build()
+    }
+}
+
+/// The disk and network capacity of the host that is shared among microVMs.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct HostBudget {
+    pub disk: Rates,
+    pub net: Rates,
+}
+
+/// How much of the [`HostBudget`] a microVM is entitled to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Share {
+    /// Relative weight in the division of the budget left after guarantees.
+    pub weight: NonZeroU32,
+    /// Disk rates the microVM is given before the rest of the budget is divided.
+    pub disk_guarantee: Rates,
+    /// Network rates the microVM is given before the rest of the budget is divided.
+    pub net_guarantee: Rates,
+}
+
+impl Default for Share {
+    fn default() -> Self {
+        Self {
+            weight: NonZeroU32::MIN,
+            disk_guarantee: Rates::default(),
+            net_guarantee: Rates::default(),
+        }
+    }
+}
+
+impl Share {
+    #[inline]
+    pub fn new(weight: NonZeroU32) -> Self {
+        Self {
+            weight,
+            ..Default::default()
+        }
+    }
+}
+
+/// The rates planned for the devices of a single microVM.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VmAllocation {
+    pub id: CompactString,
+    /// Rates of each of its drives.
+    pub per_drive: Rates,
+    /// Rates of each direction of each of its network interfaces.
+    pub per_iface: Rates,
+}
+
+/// A microVM as seen by [`FairSharePolicy::plan`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VmDevices {
+    pub id: CompactString,
+    pub share: Share,
+    pub drives: usize,
+    pub ifaces: usize,
+}
+
+/// How a [`FairShare`] divides the host's capacity among microVMs.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct FairSharePolicy {
+    /// The capacity shared among all managed microVMs.
+    pub budget: HostBudget,
+}
+
+impl FairSharePolicy {
+    #[inline]
+    pub fn new(budget: HostBudget) -> Self {
+        Self { budget }
+    }
+
+    /// Divide the budget among `vms`.
+    ///
+    /// MicroVMs without devices of a class take no part in its division.
+    pub fn plan(&self, vms: &[VmDevices]) -> Vec<VmAllocation> {
+        let disk = divide_class(
+            &self.budget.disk,
+            vms,
+            |vm| vm.drives,
+            |share| &share.disk_guarantee,
+        );
+        let net = divide_class(
+            &self.budget.net,
+            vms,
+            |vm| vm.ifaces,
+            |share| &share.net_guarantee,
+        );
+        vms.iter()
+            .zip(disk.into_iter().zip(net))
+            .map(|(vm, (per_drive, per_iface))| VmAllocation {
+                id: vm.id.clone(),
+                per_drive,
+                per_iface,
+            })
+            .collect()
+    }
+}
+
+/// Per-device rates of every microVM for a single device class.
+fn divide_class(
+    budget: &Rates,
+    vms: &[VmDevices],
+    devices: impl Fn(&VmDevices) -> usize,
+    guarantee: impl Fn(&Share) -> &Rates,
+) -> Vec<Rates> {
+    let weights: Vec<u64> = vms
+        .iter()
+        .map(|vm| {
+            if devices(vm) == 0 {
+                0
+            } else {
+                vm.share.weight.get().into()
+            }
+        })
+        .collect();
+    let guarantees = |rate: fn(&Rates) -> Option<u64>| -> Vec<u64> {
+        vms.iter()
+            .map(|vm| {
+                if devices(vm) == 0 {
+                    0
+                } else {
+                    rate(guarantee(&vm.share)).unwrap_or(0)
+                }
+            })
+            .collect()
+    };
+    let bytes = budget.bytes_per_sec.map(|total| {
+        divide(
+            total.0,
+            &guarantees(|r| r.bytes_per_sec.map(|b| b.0)),
+            &weights,
+        )
+    });
+    let ops = budget.ops_per_sec.map(|total| {
+        divide(
+            total.0,
+            &guarantees(|r| r.ops_per_sec.map(|o| o.0)),
+            &weights,
+        )
+    });
+
+    (0..vms.len())
+        .map(|i| {
+            let count = devices(&vms[i]).max(1) as u64;
+            let per_device = |totals: &Vec<u64>| (totals[i] / count).max(1);
+            Rates {
+                bytes_per_sec: bytes.as_ref().map(|b| ByteRate(per_device(b))),
+                ops_per_sec: ops.as_ref().map(|o| OpRate(per_device(o))),
+            }
+        })
+        .collect()
+}
+
+/// Divide `total` into guarantees plus a weighted share of the remainder.
+fn divide(total: u64, guarantees: &[u64], weights: &[u64]) -> Vec<u64> {
+    let guaranteed: u128 = guarantees.iter().map(|&g| u128::from(g)).sum();
+    if guaranteed >= u128::from(total) {
+        if guaranteed > u128::from(total) {
+            warn!(total, %guaranteed, "guarantees exceed the budget; scaling them down");
+        }
+        return guarantees
+            .iter()
+            .map(|&g| (u128::from(g) * u128::from(total) / guaranteed.max(1)) as u64)
+            .collect();
+    }
+
+    let remainder = u128::from(total) - guaranteed;
+    let weight_sum: u128 = weights.iter().map(|&w| u128::from(w)).sum();
+    guarantees
+        .iter()
+        .zip(weights)
+        .map(|(&g, &w)| {
+            let extra = (remainder * u128::from(w))
+                .checked_div(weight_sum)
+                .unwrap_or(0);
+            g + extra as u64
+        })
+        .collect()
+}
+
+/// A microVM managed by a [`FairShare`].
+#[derive(Debug)]
+struct ManagedVm<A> {
+    id: CompactString,
+    api: A,
+    share: Share,
+    drives: Vec<DriveId>,
+    ifaces: Vec<IfaceId>,
+}
+
+/// Keeps the drives and network interfaces of all managed microVMs within a [`HostBudget`],
+/// re-balancing whenever a microVM joins or leaves.
+#[derive(Debug)]
+pub struct FairShare<A> {
+    policy: FairSharePolicy,
+    vms: Vec<ManagedVm<A>>,
+}
+
+impl<A: Api> FairShare<A> {
+    #[inline]
+    pub fn new(policy: FairSharePolicy) -> Self {
+        Self {
+            policy,
+            vms: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn policy(&self) -> &FairSharePolicy {
+        &self.policy
+    }
+
+    /// Start managing the microVM behind `api`, and re-balance; a microVM already managed under
+    /// the same `id` is replaced.
+    ///
+    /// Its drives and network interfaces are discovered from its configuration, so they must
+    /// all be attached beforehand. Drives backed by vhost-user are left alone, as Firecracker
+    /// does not rate limit them.
+    pub async fn add_vm(
+        &mut self,
+        id: impl Into<CompactString>,
+        api: A,
+        share: Share,
+    ) -> Result<Vec<VmAllocation>, crate::Error> {
+        let id = id.into();
+        let config = api.get_export_vm_config().await?;
+        let drives = config
+            .drives
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|drive| drive.socket.is_none())
+            .map(|drive| drive.drive_id)
+            .collect();
+        let ifaces = config
+            .network_interfaces
+            .unwrap_or_default()
+            .into_iter()
+            .map(|iface| iface.iface_id)
+            .collect();
+        self.vms.retain(|vm| vm.id != id);
+        self.vms.push(ManagedVm {
+            id,
+            api,
+            share,
+            drives,
+            ifaces,
+        });
+        Ok(self.rebalance().await)
+    }
+
+    /// Stop managing the microVM identified by `id`, re-balance, and return its [`Api`].
+    pub async fn remove_vm(&mut self, id: &str) -> Option<A> {
+        let idx = self.vms.iter().position(|vm| vm.id == id)?;
+        let vm = self.vms.swap_remove(idx);
+        self.rebalance().await;
+        Some(vm.api)
+    }
+
+    /// Replace the policy, and re-balance.
+    pub async fn set_policy(&mut self, policy: FairSharePolicy) -> Vec<VmAllocation> {
+        self.policy = policy;
+        self.rebalance().await
+    }
+
+    /// Plan the rates of every managed microVM, and apply them through the PATCH endpoints.
+    ///
+    /// MicroVMs whose devices cannot be updated are skipped (and logged), so that a single
+    /// exited VMM does not stall the whole fleet. Returns the allocations that were applied
+    /// successfully.
+    #[instrument(level = Level::DEBUG, skip(self), fields(vms = self.vms.len()))]
+    pub async fn rebalance(&mut self) -> Vec<VmAllocation> {
+        let views: Vec<VmDevices> = self
+            .vms
+            .iter()
+            .map(|vm| VmDevices {
+                id: vm.id.clone(),
+                share: vm.share,
+                drives: vm.drives.len(),
+                ifaces: vm.ifaces.len(),
+            })
+            .collect();
+
+        let mut applied = Vec::new();
+        for (vm, allocation) in self.vms.iter().zip(self.policy.plan(&views)) {
+            match apply(vm, &allocation).await {
+                Ok(()) => {
+                    info!(?allocation, "updated rate limiters");
+                    applied.push(allocation);
+                }
+                Err(err) => warn!(id = %vm.id, error = %err, "failed to update rate limiters"),
+            }
+        }
+        applied
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+enum ApplyError {
+    #[error(transparent)]
+    Limiter(#[from] Error),
+
+    #[error(transparent)]
+    Api(#[from] crate::Error),
+}
+
+async fn apply<A: Api>(vm: &ManagedVm<A>, allocation: &VmAllocation) -> Result<(), ApplyError> {
+    if !vm.drives.is_empty() {
+        let limiter = allocation.per_drive.limiter()?;
+        for drive_id in &vm.drives {
+            let drive = PartialDrive {
+                rate_limiter: Some(Box::new(limiter.clone())),
+                ..PartialDrive::new(drive_id.clone())
+            };
+            vm.api.patch_guest_drive_by_id(drive_id, drive).await?;
+        }
+    }
+    if !vm.ifaces.is_empty() {
+        let limiter = allocation.per_iface.limiter()?;
+        for iface_id in &vm.ifaces {
+            let iface = PartialNetworkInterface {
+                rx_rate_limiter: Some(Box::new(limiter.clone())),
+                tx_rate_limiter: Some(Box::new(limiter.clone())),
+                ..PartialNetworkInterface::new(iface_id.clone())
+            };
+            vm.api
+                .patch_guest_network_interface_by_id(iface_id, iface)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::api::fake::FakeApi;
+
+    fn weight(weight: u32) -> NonZeroU32 {
+        NonZeroU32::new(weight).unwrap()
+    }
+
+    fn vm(id: &str, share: Share, drives: usize, ifaces: usize) -> VmDevices {
+        VmDevices {
+            id: id.into(),
+            share,
+            drives,
+            ifaces,
+        }
+    }
+
+    #[test]
+    fn divides_the_remainder_by_weight() {
+        let shares = divide(1000, &[100, 0, 0], &[1, 3, 0]);
+        assert_eq!(shares, [325, 675, 0]);
+        assert_eq!(shares.iter().sum::<u64>(), 1000);
+        // Rounding never exceeds the budget.
+        assert_eq!(divide(1000, &[0, 0, 0], &[1, 1, 1]), [333, 333, 333]);
+        // Nothing to divide the remainder by.
+        assert_eq!(divide(1000, &[100], &[0]), [100]);
+    }
+
+    #[test]
+    fn scales_guarantees_down_to_the_budget() {
+        let shares = divide(1000, &[600, 900], &[5, 1]);
+        assert_eq!(shares, [400, 600]);
+        assert_eq!(shares.iter().sum::<u64>(), 1000);
+    }
+
+    #[test]
+    fn rejects_zero_weights() {
+        assert_eq!(NonZeroU32::new(0).map(Share::new), None);
+        assert_eq!(Share::default().weight, weight(1));
+    }
+
+    #[test]
+    fn plans_per_device_rates() {
+        let policy = FairSharePolicy::new(HostBudget {
+            disk: Rates::new(ByteRate(1000), OpRate(100)),
+            net: Rates {
+                bytes_per_sec: Some(ByteRate(900)),
+                ops_per_sec: None,
+            },
+        });
+        let guaranteed = Share {
+            disk_guarantee: Rates {
+                bytes_per_sec: Some(ByteRate(100)),
+                ops_per_sec: None,
+            },
+            ..Share::new(weight(1))
+        };
+        let vms = [
+            vm("a", guaranteed, 2, 1),
+            vm("b", Share::new(weight(3)), 1, 0),
+            vm("c", Share::new(weight(1)), 0, 2),
+        ];
+        let rates = |bytes, ops: Option<u64>| Rates {
+            bytes_per_sec: Some(ByteRate(bytes)),
+            ops_per_sec: ops.map(OpRate),
+        };
+        // Disk: `a` gets 100 + 900 / 4 bytes and 100 / 4 ops, split over its two drives; `c`
+        // has no drives and takes no part. Network: `b` has no interfaces and takes no part.
+        assert_eq!(
+            policy.plan(&vms),
+            [
+                VmAllocation {
+                    id: "a".into(),
+                    per_drive: rates(162, Some(12)),
+                    per_iface: rates(450, None),
+                },
+                VmAllocation {
+                    id: "b".into(),
+                    per_drive: rates(675, Some(75)),
+                    per_iface: rates(1, None),
+                },
+                VmAllocation {
+                    id: "c".into(),
+                    per_drive: rates(1, Some(1)),
+                    per_iface: rates(225, None),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn leaves_vhost_user_drives_alone() {
+        let api = FakeApi::new();
+        api.respond(
+            "get_export_vm_config",
+            json!({
+                "drives": [
+                    {"drive_id": "rootfs", "is_root_device": true, "path_on_host": "/rootfs.ext4"},
+                    {"drive_id": "data", "is_root_device": false, "socket": "/vhost.sock"},
+                ],
+                "network-interfaces": [{"iface_id": "eth0", "host_dev_name": "tap0"}],
+            }),
+        );
+        let budget = HostBudget {
+            disk: Rates::new(ByteRate(1000), OpRate(100)),
+            net: Rates::default(),
+        };
+        let mut fair_share = FairShare::new(FairSharePolicy::new(budget));
+        let applied = fair_share
+            .add_vm("vm", api.clone(), Share::default())
+            .await
+            .unwrap();
+        assert_eq!(applied.len(), 1);
+        assert_eq!(applied[0].per_drive, budget.disk);
+
+        let drives = api.calls_to("patch_guest_drive_by_id");
+        assert_eq!(drives.len(), 1);
+        assert_eq!(drives[0][0], "rootfs");
+        let limiter = budget.disk.limiter().unwrap();
+        assert_eq!(drives[0][1]["rate_limiter"], json!(limiter));
+        assert_eq!(
+            api.calls_to("patch_guest_network_interface_by_id")[0][0],
+            "eth0"
+        );
+    }
+}
@@ -0,0 +1,54 @@
+//! Construction, inspection and validation of [`RateLimiter`](crate::models::RateLimiter)s in
+//! human units.
+//!
+//! A Firecracker [`TokenBucket`](crate::models::TokenBucket) is configured through its `size`
+//! (in bytes or operations), its `refill_time` (in milliseconds) and an optional
+//! `one_time_burst`; the sustained rate is `size / refill_time`. Getting these right by hand is
+//! error-prone, so [`RateLimiterBuilder`] derives them from rates like `"200 MiB/s"` or
+//! `"5000 IOPS"`, and [`describe`] does the inverse.
+
+pub mod builder;
+pub mod fair_share;
+pub mod qos;
+pub mod simulator;
+pub mod units;
+
+use compact_str::CompactString;
+
+pub use builder::{describe, validate, validate_bucket, RateLimiterBuilder};
+pub use fair_share::{FairShare, FairSharePolicy, HostBudget, Share};
+pub use qos::{QosAction, QosEngine, QosPolicy};
+pub use units::{ByteRate, ByteSize, OpRate};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum Error {
+    #[error("failed to parse '{input}': {reason}")]
+    Parse {
+        input: CompactString,
+        reason: &'static str,
+    },
+
+    #[error("{bucket} bucket: a capacity or one-time burst was set without a rate")]
+    BurstWithoutRate { bucket: &'static str },
+
+    #[error("{bucket} bucket: rate must be non-zero")]
+    ZeroRate { bucket: &'static str },
+
+    #[error("{bucket} bucket: capacity is too small to be refilled in at least 1 ms at this rate")]
+    CapacityTooSmall { bucket: &'static str },
+
+    #[error("{bucket} bucket: value does not fit in the API's integers")]
+    Overflow { bucket: &'static str },
+
+    #[error("{bucket} bucket: negative values are not allowed")]
+    Negative { bucket: &'static str },
+
+    #[error("{bucket} bucket: zero refill time disables the bucket")]
+    ZeroRefillTime { bucket: &'static str },
+
+    #[error("{bucket} bucket: zero size disables the bucket")]
+    ZeroSize { bucket: &'static str },
+
+    #[error("{bucket} bucket: one-time burst without a bucket size is ignored")]
+    BurstWithoutSize { bucket: &'static str },
+}
@@ -0,0 +1,488 @@
+//! Dynamic quality of service for the drives and network interfaces of a microVM, driven by
+//! the per-device [`MetricsSnapshot`] sections Firecracker flushes.
+//!
+//! Every device starts at its base [`RateLimiter`] (see [`QosPolicy`]). A device that stays
+//! mostly idle for a while is granted a one-time burst, sized after the bandwidth it left
+//! unused, so that a tenant waking up is not throttled right away. A device that keeps getting
+//! throttled is clamped to a fraction of its base rate, so that it stops eating into the shared
+//! host resources, and is restored once it calms down.
+
+use std::collections::BTreeMap;
+
+use compact_str::CompactString;
+use tracing::{info, instrument, warn, Level};
+
+use crate::{
+    metrics::MetricsSnapshot,
+    models::{DriveId, IfaceId, PartialDrive, PartialNetworkInterface, RateLimiter, TokenBucket},
+    Api, Error,
+};
+
+/// Thresholds and limits of a [`QosEngine`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct QosPolicy {
+    /// Base limits of every drive.
+    pub drive: RateLimiter,
+    /// Base limits of every network interface's receive path.
+    pub net_rx: RateLimiter,
+    /// Base limits of every network interface's transmit path.
+    pub net_tx: RateLimiter,
+    /// A device using less than this fraction of its base bandwidth during a flush is idle.
+    pub idle_utilization: f64,
+    /// Consecutive idle flushes before a one-time burst is granted.
+    pub idle_flushes: u32,
+    /// Upper bound of a granted one-time burst, in bytes.
+    pub max_burst_bytes: u64,
+    /// A device throttled at least this many times during a flush is noisy.
+    pub noisy_throttled_events: u64,
+    /// Consecutive noisy flushes before a device is clamped.
+    pub noisy_flushes: u32,
+    /// Fraction of the base rates a clamped device is limited to.
+    pub clamp_factor: f64,
+    /// Consecutive flushes without throttling before a clamped device is restored.
+    pub calm_flushes: u32,
+}
+
+impl QosPolicy {
+    /// A policy with the given base limits and moderate thresholds.
+    pub fn new(drive: RateLimiter, net_rx: RateLimiter, net_tx: RateLimiter) -> Self {
+        Self {
+            drive,
+            net_rx,
+            net_tx,
+            idle_utilization: 0.1,
+            idle_flushes: 6,
+            max_burst_bytes: 1 << 30,
+            noisy_throttled_events: 10,
+            noisy_flushes: 3,
+            clamp_factor: 0.5,
+            calm_flushes: 6,
+        }
+    }
+}
+
+/// The path of a device that a [`QosEngine`] controls.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Path {
+    Drive,
+    NetRx,
+    NetTx,
+}
+
+/// Why a [`QosAction`] was taken.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Reason {
+    /// The device was idle; a one-time burst of the given size was granted.
+    BurstCredit(u64),
+    /// The device kept getting throttled.
+    Clamped,
+    /// The clamped device calmed down; its base limits were restored.
+    Restored,
+}
+
+/// A rate limiter update decided by a [`QosEngine`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct QosAction {
+    /// Drive or interface ID.
+    pub id: CompactString,
+    pub path: Path,
+    pub limiter: RateLimiter,
+    pub reason: Reason,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+enum Mode {
+    #[default]
+    Base,
+    Credited,
+    Clamped,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct DeviceState {
+    mode: Mode,
+    idle_streak: u32,
+    noisy_streak: u32,
+    calm_streak: u32,
+    unused_bytes: u64,
+}
+
+/// Decides rate limiter updates out of successive [`MetricsSnapshot`]s of a single microVM.
+#[derive(Clone, Debug)]
+pub struct QosEngine {
+    policy: QosPolicy,
+    devices: BTreeMap<(Path, CompactString), DeviceState>,
+    last_flush_ms: Option<u64>,
+}
+
+impl QosEngine {
+    #[inline]
+    pub fn new(policy: QosPolicy) -> Self {
+        Self {
+            policy,
+            devices: BTreeMap::new(),
+            last_flush_ms: None,
+        }
+    }
+
+    #[inline]
+    pub fn policy(&self) -> &QosPolicy {
+        &self.policy
+    }
+
+    /// Account a snapshot, returning the rate limiter updates to apply.
+    ///
+    /// The first snapshot only establishes the flush timestamp; utilization is computed over
+    /// the time elapsed between consecutive snapshots.
+    pub fn evaluate(&mut self, snapshot: &MetricsSnapshot) -> Vec<QosAction> {
+        let now_ms = snapshot.utc_timestamp_ms;
+        let elapsed_ms = self
+            .last_flush_ms
+            .replace(now_ms)
+            .map(|last| now_ms.saturating_sub(last));
+        let Some(elapsed_ms) = elapsed_ms.filter(|&ms| ms > 0) else {
+            return Vec::new();
+        };
+
+        let devices = &snapshot.devices;
+        let observations = devices
+            .block
+            .iter()
+            .map(|(id, m)| {
+                let bytes = m.read_bytes + m.write_bytes;
+                (Path::Drive, id, bytes, m.rate_limiter_throttled_events)
+            })
+            .chain(devices.net.iter().flat_map(|(id, m)| {
+                [
+                    (
+                        Path::NetRx,
+                        id,
+                        m.rx_bytes_count,
+                        m.rx_rate_limiter_throttled,
+                    ),
+                    (
+                        Path::NetTx,
+                        id,
+                        m.tx_bytes_count,
+                        m.tx_rate_limiter_throttled,
+                    ),
+                ]
+            }));
+
+        let mut actions = Vec::new();
+        for (path, id, bytes, throttled) in observations {
+            if let Some(action) = self.observe(path, id, bytes, throttled, elapsed_ms) {
+                actions.push(action);
+            }
+        }
+        actions
+    }
+
+    fn observe(
+        &mut self,
+        path: Path,
+        id: &CompactString,
+        bytes: u64,
+        throttled: u64,
+        elapsed_ms: u64,
+    ) -> Option<QosAction> {
+        let policy = &self.policy;
+        let base = match path {
+            Path::Drive => &policy.drive,
+            Path::NetRx => &policy.net_rx,
+            Path::NetTx => &policy.net_tx,
+        };
+        let state = self.devices.entry((path, id.clone())).or_default();
+        let action = |limiter, reason| QosAction {
+            id: id.clone(),
+            path,
+            limiter,
+            reason,
+        };
+
+        if throttled >= policy.noisy_throttled_events {
+            state.noisy_streak += 1;
+            state.calm_streak = 0;
+        } else {
+            state.noisy_streak = 0;
+            state.calm_streak += u32::from(throttled == 0);
+        }
+
+        match state.mode {
+            Mode::Clamped if state.calm_streak >= policy.calm_flushes => {
+                *state = DeviceState::default();
+                return Some(action(base.clone(), Reason::Restored));
+            }
+            Mode::Clamped => return None,
+            _ if state.noisy_streak >= policy.noisy_flushes => {
+                *state = DeviceState {
+                    mode: Mode::Clamped,
+                    ..Default::default()
+                };
+                return Some(action(scaled(base, policy.clamp_factor), Reason::Clamped));
+            }
+            _ => (),
+        }
+
+        let capacity = bandwidth_rate(base)? * elapsed_ms as f64 / 1000.0;
+        if (bytes as f64) < capacity * policy.idle_utilization {
+            state.idle_streak += 1;
+            state.unused_bytes = state
+                .unused_bytes
+                .saturating_add((capacity - bytes as f64) as u64);
+        } else {
+            // Activity resumed; a granted burst is left to be consumed.
+            state.idle_streak = 0;
+            state.unused_bytes = 0;
+            state.mode = Mode::Base;
+            return None;
+        }
+
+        if state.mode == Mode::Base && state.idle_streak >= policy.idle_flushes {
+            let burst = state.unused_bytes.min(policy.max_burst_bytes);
+            state.mode = Mode::Credited;
+            state.unused_bytes = 0;
+            let mut limiter = base.clone();
+            if let Some(bandwidth) = limiter.bandwidth.as_deref_mut() {
+                bandwidth.one_time_burst = Some(i64::try_from(burst).unwrap_or(i64::MAX));
+            }
+            return Some(action(limiter, Reason::BurstCredit(burst)));
+        }
+        None
+    }
+
+    /// Apply `actions` through the PATCH endpoints of the microVM behind `api`.
+    ///
+    /// Updates of the receive and transmit paths of the same interface are sent together.
+    #[instrument(level = Level::DEBUG, skip_all, fields(actions = actions.len()))]
+    pub async fn apply<A: Api>(api: &A, actions: &[QosAction]) -> Result<(), Error> {
+        let mut ifaces: BTreeMap<&str, PartialNetworkInterface> = BTreeMap::new();
+        for action in actions {
+            info!(
+                id = %action.id,
+                path = ?action.path,
+                reason = ?action.reason,
+                "updating rate limiter"
+            );
+            let limiter = Some(Box::new(action.limiter.clone()));
+            match action.path {
+                Path::Drive => {
+                    let Ok(drive_id) = DriveId::new(action.id.clone()) else {
+                        warn!(id = %action.id, "not a valid drive ID");
+                        continue;
+                    };
+                    let drive = PartialDrive {
+                        rate_limiter: limiter,
+                        ..PartialDrive::new(drive_id)
+                    };
+                    api.patch_guest_drive_by_id(&action.id, drive).await?;
+                }
+                Path::NetRx | Path::NetTx => {
+                    let Ok(iface_id) = IfaceId::new(action.id.clone()) else {
+                        warn!(id = %action.id, "not a valid network interface ID");
+                        continue;
+                    };
+                    let iface = ifaces
+                        .entry(&action.id)
+                        .or_insert_with(|| PartialNetworkInterface::new(iface_id));
+                    if action.path == Path::NetRx {
+                        iface.rx_rate_limiter = limiter;
+                    } else {
+                        iface.tx_rate_limiter = limiter;
+                    }
+                }
+            }
+        }
+        for (id, iface) in ifaces {
+            api.patch_guest_network_interface_by_id(id, iface).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Sustained rate of the bandwidth bucket, in bytes per second.
+fn bandwidth_rate(limiter: &RateLimiter) -> Option<f64> {
+    let bucket = limiter.bandwidth.as_deref()?;
+    (bucket.size > 0 && bucket.refill_time > 0)
+        .then(|| bucket.size as f64 * 1000.0 / bucket.refill_time as f64)
+}
+
+/// Scale the rates of both buckets of `limiter` by `factor`, keeping their refill times.
+pub fn scaled(limiter: &RateLimiter, factor: f64) -> RateLimiter {
+    let scale = |bucket: &Option<Box<TokenBucket>>| {
+        bucket.as_deref().map(|bucket| {
+            Box::new(TokenBucket {
+                one_time_burst: None,
+                size: ((bucket.size as f64 * factor) as i64).max(1),
+                ..bucket.clone()
+            })
+        })
+    };
+    RateLimiter {
+        bandwidth: scale(&limiter.bandwidth),
+        ops: scale(&limiter.ops),
+        extras: limiter.extras.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{api::fake::FakeApi, metrics::parse_line};
+
+    /// 1000 bytes per second.
+    fn base() -> RateLimiter {
+        RateLimiter {
+            bandwidth: Some(Box::new(TokenBucket {
+                one_time_burst: None,
+                refill_time: 1000,
+                size: 1000,
+                extras: Default::default(),
+            })),
+            ops: None,
+            extras: Default::default(),
+        }
+    }
+
+    fn engine() -> QosEngine {
+        QosEngine::new(QosPolicy {
+            idle_flushes: 2,
+            max_burst_bytes: 1500,
+            noisy_flushes: 2,
+            calm_flushes: 2,
+            ..QosPolicy::new(base(), base(), base())
+        })
+    }
+
+    /// A flush at `second`, in which the `rootfs` drive moved `bytes` and was throttled
+    /// `throttled` times.
+    fn flush(second: u64, bytes: u64, throttled: u64) -> MetricsSnapshot {
+        let snapshot = json!({
+            "utc_timestamp_ms": second * 1000,
+            "block_rootfs": {"read_bytes": bytes, "rate_limiter_throttled_events": throttled},
+        });
+        parse_line(&snapshot.to_string()).unwrap()
+    }
+
+    fn reasons(actions: Vec<QosAction>) -> Vec<(Path, Reason)> {
+        actions.iter().map(|a| (a.path, a.reason)).collect()
+    }
+
+    #[test]
+    fn takes_no_action_on_the_first_snapshot() {
+        let mut engine = engine();
+        assert_eq!(engine.evaluate(&flush(0, 0, 100)), []);
+        // Nor on a snapshot of the same flush.
+        assert_eq!(engine.evaluate(&flush(0, 0, 100)), []);
+    }
+
+    #[test]
+    fn clamps_noisy_devices_and_restores_them_once_calm() {
+        let mut engine = engine();
+        engine.evaluate(&flush(0, 1000, 0));
+        assert_eq!(engine.evaluate(&flush(1, 1000, 20)), []);
+        let clamped = engine.evaluate(&flush(2, 1000, 20));
+        assert_eq!(reasons(clamped.clone()), [(Path::Drive, Reason::Clamped)]);
+        assert_eq!(clamped[0].limiter, scaled(&base(), 0.5));
+        assert_eq!(clamped[0].limiter.bandwidth.as_ref().unwrap().size, 500);
+
+        // A single throttled event neither counts as noisy nor as calm.
+        assert_eq!(engine.evaluate(&flush(3, 500, 0)), []);
+        assert_eq!(engine.evaluate(&flush(4, 500, 1)), []);
+        let restored = engine.evaluate(&flush(5, 500, 0));
+        assert_eq!(reasons(restored.clone()), [(Path::Drive, Reason::Restored)]);
+        assert_eq!(restored[0].limiter, base());
+    }
+
+    #[test]
+    fn interrupted_noise_does_not_clamp() {
+        let mut engine = engine();
+        engine.evaluate(&flush(0, 1000, 0));
+        assert_eq!(engine.evaluate(&flush(1, 1000, 20)), []);
+        assert_eq!(engine.evaluate(&flush(2, 1000, 5)), []);
+        assert_eq!(engine.evaluate(&flush(3, 1000, 20)), []);
+    }
+
+    #[test]
+    fn credits_idle_devices_with_a_capped_burst() {
+        let mut engine = engine();
+        engine.evaluate(&flush(0, 0, 0));
+        // 1000 bytes go unused in every idle flush.
+        assert_eq!(engine.evaluate(&flush(1, 50, 0)), []);
+        let credited = engine.evaluate(&flush(2, 0, 0));
+        assert_eq!(
+            reasons(credited.clone()),
+            [(Path::Drive, Reason::BurstCredit(1500))]
+        );
+        let bandwidth = credited[0].limiter.bandwidth.as_deref().unwrap();
+        assert_eq!(bandwidth.one_time_burst, Some(1500));
+
+        // The burst is granted once, until the device becomes active again.
+        assert_eq!(engine.evaluate(&flush(3, 0, 0)), []);
+        assert_eq!(engine.evaluate(&flush(4, 0, 0)), []);
+        assert_eq!(engine.evaluate(&flush(5, 1000, 0)), []);
+        assert_eq!(engine.evaluate(&flush(6, 0, 0)), []);
+        let credited = engine.evaluate(&flush(8, 0, 0));
+        assert_eq!(
+            reasons(credited),
+            [(Path::Drive, Reason::BurstCredit(1500))]
+        );
+    }
+
+    #[test]
+    fn observes_both_paths_of_network_interfaces() {
+        let mut engine = engine();
+        let flush = |second: u64| {
+            let snapshot = json!({
+                "utc_timestamp_ms": second * 1000,
+                "net_eth0": {"rx_rate_limiter_throttled": 20, "tx_bytes_count": 1000},
+            });
+            parse_line(&snapshot.to_string()).unwrap()
+        };
+        engine.evaluate(&flush(0));
+        assert_eq!(engine.evaluate(&flush(1)), []);
+        assert_eq!(
+            reasons(engine.evaluate(&flush(2))),
+            [(Path::NetRx, Reason::Clamped)]
+        );
+    }
+
+    #[tokio::test]
+    async fn applies_both_paths_of_an_interface_at_once() {
+        let api = FakeApi::new();
+        let action = |id: &str, path, limiter| QosAction {
+            id: id.into(),
+            path,
+            limiter,
+            reason: Reason::Clamped,
+        };
+        let clamped = scaled(&base(), 0.5);
+        let actions = [
+            action("eth0", Path::NetRx, clamped.clone()),
+            action("rootfs", Path::Drive, clamped.clone()),
+            action("eth0", Path::NetTx, base()),
+            action("bad id!", Path::Drive, base()),
+        ];
+        QosEngine::apply(&api, &actions).await.unwrap();
+
+        let drive = PartialDrive {
+            rate_limiter: Some(Box::new(clamped.clone())),
+            ..PartialDrive::new(DriveId::new("rootfs").unwrap())
+        };
+        assert_eq!(
+            api.calls_to("patch_guest_drive_by_id"),
+            [vec![json!("rootfs"), serde_json::to_value(drive).unwrap()]]
+        );
+        let iface = PartialNetworkInterface {
+            rx_rate_limiter: Some(Box::new(clamped)),
+            tx_rate_limiter: Some(Box::new(base())),
+            ..PartialNetworkInterface::new(IfaceId::new("eth0").unwrap())
+        };
+        assert_eq!(
+            api.calls_to("patch_guest_network_interface_by_id"),
+            [vec![json!("eth0"), serde_json::to_value(iface).unwrap()]]
+        );
+    }
+}
@@ -0,0 +1,368 @@
+//! A deterministic simulator of Firecracker's rate limiter, to predict what guests will
+//! experience under a [`RateLimiter`] before applying it.
+//!
+//! The simulation mirrors Firecracker's token bucket: buckets start full, the one-time burst is
+//! consumed before the bucket's budget and no refill accrues while it lasts, and tokens are
+//! replenished one at a time at the `size / refill_time` rate, carrying the time spent towards a
+//! partial token over to the next replenishment. Like Firecracker's block device, each request
+//! takes its token of the ops bucket before its bytes, and gets the token back if the bandwidth
+//! bucket refuses it. When a request cannot be served, the device stops processing its queue
+//! until the rate limiter's 100 ms timer fires. Requests larger than a bucket's size are let
+//! through, but block the device for the time it takes to refill the excess.
+//!
+//! Device service times are not modeled; only the delays imposed by the rate limiter are.
+
+use std::time::Duration;
+
+use crate::models::{RateLimiter, TokenBucket};
+
+const NANOS_PER_MILLI: u64 = 1_000_000;
+
+/// Interval of Firecracker's rate limiter timer, re-checking the buckets of a blocked device.
+pub const REFILL_TIMER_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A single I/O request of a synthetic trace.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct IoRequest {
+    /// When the guest submits the request, relative to the start of the trace.
+    pub at: Duration,
+    /// Size of the request; each request also consumes a single token of the ops bucket.
+    pub bytes: u64,
+}
+
+impl IoRequest {
+    #[inline]
+    pub fn new(at: Duration, bytes: u64) -> Self {
+        Self { at, bytes }
+    }
+}
+
+/// A trace of `count` requests of `bytes` each, submitted every `interval`.
+pub fn uniform_trace(count: usize, interval: Duration, bytes: u64) -> Vec<IoRequest> {
+    (0..count)
+        .map(|i| IoRequest::new(interval * i as u32, bytes))
+        .collect()
+}
+
+/// What the guest experienced during a simulation.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SimulationReport {
+    pub requests: usize,
+    pub bytes: u64,
+    /// Time from the first submission to the last completion.
+    pub elapsed: Duration,
+    /// Achieved throughput over `elapsed`, in bytes per second.
+    pub bytes_per_sec: f64,
+    /// Achieved rate over `elapsed`, in operations per second.
+    pub ops_per_sec: f64,
+    /// Total time during which the device was blocked by the rate limiter.
+    pub throttled_time: Duration,
+    /// Number of requests delayed by the rate limiter.
+    pub throttled_requests: usize,
+    /// Longest delay of a single request, from its submission to its completion.
+    pub max_delay: Duration,
+    /// Average delay of a request, from its submission to its completion.
+    pub mean_delay: Duration,
+    /// When the bandwidth bucket's one-time burst ran out, if it did.
+    pub bandwidth_burst_exhausted_at: Option<Duration>,
+    /// When the ops bucket's one-time burst ran out, if it did.
+    pub ops_burst_exhausted_at: Option<Duration>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Reduction {
+    Success,
+    Failure,
+    /// The request exceeded the bucket's size; it is let through, but the device is blocked for
+    /// the given time.
+    OverConsumption(u64),
+}
+
+/// Simulated state of a Firecracker token bucket; times are in nanoseconds.
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+    size: u64,
+    one_time_burst: u64,
+    refill_time: u64,
+    budget: u64,
+    last_update: u64,
+    /// `size` and `refill_time`, divided by their greatest common divisor.
+    processed_capacity: u64,
+    processed_refill_time: u64,
+    burst_exhausted_at: Option<u64>,
+}
+
+impl Bucket {
+    /// `None` for buckets that Firecracker ignores.
+    fn new(bucket: &TokenBucket) -> Option<Self> {
+        let size = u64::try_from(bucket.size).ok().filter(|&size| size > 0)?;
+        let refill_time = u64::try_from(bucket.refill_time)
+            .ok()
+            .filter(|&refill_time| refill_time > 0)?
+            .checked_mul(NANOS_PER_MILLI)?;
+        let one_time_burst = bucket
+            .one_time_burst
+            .and_then(|burst| u64::try_from(burst).ok())
+            .unwrap_or(0);
+        let common = gcd(size, refill_time);
+        Some(Self {
+            size,
+            one_time_burst,
+            refill_time,
+            budget: size,
+            last_update: 0,
+            processed_capacity: size / common,
+            processed_refill_time: refill_time / common,
+            burst_exhausted_at: None,
+        })
+    }
+
+    fn auto_replenish(&mut self, now: u64) {
+        let elapsed = now.saturating_sub(self.last_update);
+        if elapsed >= self.refill_time {
+            self.budget = self.size;
+            self.last_update = now;
+            return;
+        }
+        let capacity = u128::from(self.processed_capacity);
+        let refill_time = u128::from(self.processed_refill_time);
+        let tokens = u128::from(elapsed) * capacity / refill_time;
+        // Only move forward by the time it took to generate whole tokens, rounding up so that
+        // no fraction of a nanosecond counts twice.
+        let adjustment = (tokens * refill_time).div_ceil(capacity);
+        self.last_update += adjustment as u64;
+        let tokens = u64::try_from(tokens).unwrap_or(u64::MAX);
+        self.budget = self.budget.saturating_add(tokens).min(self.size);
+    }
+
+    fn reduce(&mut self, now: u64, mut tokens: u64) -> Reduction {
+        if self.one_time_burst > 0 {
+            if self.one_time_burst >= tokens {
+                self.one_time_burst -= tokens;
+                self.last_update = now;
+                if self.one_time_burst == 0 {
+                    self.burst_exhausted_at = Some(now);
+                }
+                return Reduction::Success;
+            }
+            tokens -= self.one_time_burst;
+            self.one_time_burst = 0;
+            self.burst_exhausted_at = Some(now);
+        }
+
+        if tokens > self.budget {
+            self.auto_replenish(now);
+            if tokens > self.size {
+                let excess = tokens - self.budget;
+                self.budget = 0;
+                let block = (u128::from(excess) * u128::from(self.refill_time)
+                    / u128::from(self.size)) as u64;
+                return Reduction::OverConsumption(block);
+            }
+            if tokens > self.budget {
+                return Reduction::Failure;
+            }
+        }
+        self.budget -= tokens;
+        Reduction::Success
+    }
+
+    /// Give back tokens taken by a request that another bucket then refused.
+    fn replenish(&mut self, tokens: u64) {
+        self.budget = self.budget.saturating_add(tokens).min(self.size);
+    }
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Simulate `trace` against `limiter`.
+///
+/// Requests are served in the order of submission (ties in the order given).
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use wick::rate_limit::{simulator, RateLimiterBuilder};
+///
+/// # fn main() -> Result<(), wick::rate_limit::Error> {
+/// let limiter = RateLimiterBuilder::new().bandwidth("1 MiB/s".parse()?).build()?;
+/// // 4 MiB submitted at once, in 64 KiB requests.
+/// let trace = simulator::uniform_trace(64, Duration::ZERO, 64 << 10);
+///
+/// let report = simulator::simulate(&limiter, &trace);
+/// assert!(report.elapsed >= Duration::from_secs(3));
+/// assert!(report.bytes_per_sec <= 1.4 * (1 << 20) as f64);
+/// # Ok(())
+/// # }
+/// ```
+pub fn simulate(limiter: &RateLimiter, trace: &[IoRequest]) -> SimulationReport {
+    let mut bandwidth = limiter.bandwidth.as_deref().and_then(Bucket::new);
+    let mut ops = limiter.ops.as_deref().and_then(Bucket::new);
+
+    let mut order: Vec<&IoRequest> = trace.iter().collect();
+    order.sort_by_key(|req| req.at);
+
+    let timer = REFILL_TIMER_INTERVAL.as_nanos() as u64;
+    let mut report = SimulationReport {
+        requests: trace.len(),
+        ..Default::default()
+    };
+    let mut now = 0;
+    let mut last_completion = 0;
+    let mut total_delay = 0u128;
+    let mut first_submission = None;
+
+    for req in order {
+        let submitted = req.at.as_nanos() as u64;
+        first_submission.get_or_insert(submitted);
+        now = now.max(submitted);
+
+        loop {
+            let op = ops
+                .as_mut()
+                .map_or(Reduction::Success, |b| b.reduce(now, 1));
+            if op == Reduction::Failure {
+                report.throttled_time += Duration::from_nanos(timer);
+                now += timer;
+                continue;
+            }
+            let bw = bandwidth
+                .as_mut()
+                .map_or(Reduction::Success, |b| b.reduce(now, req.bytes));
+            if bw == Reduction::Failure {
+                if let Some(b) = ops.as_mut() {
+                    b.replenish(1);
+                }
+                report.throttled_time += Duration::from_nanos(timer);
+                now += timer;
+                continue;
+            }
+
+            // The request goes through; over-consumption blocks subsequent ones.
+            let block = [op, bw]
+                .into_iter()
+                .filter_map(|r| match r {
+                    Reduction::OverConsumption(block) => Some(block),
+                    _ => None,
+                })
+                .max();
+            let delay = now - submitted;
+            if delay > 0 {
+                report.throttled_requests += 1;
+            }
+            report.max_delay = report.max_delay.max(Duration::from_nanos(delay));
+            total_delay += u128::from(delay);
+            report.bytes += req.bytes;
+            last_completion = now;
+            if let Some(block) = block {
+                report.throttled_time += Duration::from_nanos(block);
+                now += block;
+            }
+            break;
+        }
+    }
+
+    let elapsed = last_completion - first_submission.unwrap_or(0);
+    report.elapsed = Duration::from_nanos(elapsed);
+    if elapsed > 0 {
+        let secs = report.elapsed.as_secs_f64();
+        report.bytes_per_sec = report.bytes as f64 / secs;
+        report.ops_per_sec = report.requests as f64 / secs;
+    }
+    if report.requests > 0 {
+        report.mean_delay = Duration::from_nanos((total_delay / report.requests as u128) as u64);
+    }
+    report.bandwidth_burst_exhausted_at = bandwidth
+        .and_then(|b| b.burst_exhausted_at)
+        .map(Duration::from_nanos);
+    report.ops_burst_exhausted_at = ops
+        .and_then(|b| b.burst_exhausted_at)
+        .map(Duration::from_nanos);
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MILLI: u64 = NANOS_PER_MILLI;
+
+    fn bucket(size: i64, refill_time: i64, one_time_burst: Option<i64>) -> Bucket {
+        let mut bucket = TokenBucket::new(refill_time, size);
+        bucket.one_time_burst = one_time_burst;
+        Bucket::new(&bucket).unwrap()
+    }
+
+    #[test]
+    fn refills_partially() {
+        // 7 tokens per second: one every ~142.86 ms.
+        let mut b = bucket(7, 1000, None);
+        assert_eq!(b.reduce(0, 7), Reduction::Success);
+        assert_eq!(b.reduce(142 * MILLI, 1), Reduction::Failure);
+        assert_eq!(b.reduce(143 * MILLI, 1), Reduction::Success);
+        // The time of the first token is rounded up, and the rest carried over.
+        assert_eq!(b.last_update, 142_857_143);
+        assert_eq!(b.reduce(285 * MILLI, 1), Reduction::Failure);
+        assert_eq!(b.reduce(286 * MILLI, 1), Reduction::Success);
+    }
+
+    #[test]
+    fn refills_fully_after_refill_time() {
+        let mut b = bucket(7, 1000, None);
+        assert_eq!(b.reduce(0, 7), Reduction::Success);
+        b.auto_replenish(5000 * MILLI);
+        assert_eq!((b.budget, b.last_update), (7, 5000 * MILLI));
+    }
+
+    #[test]
+    fn consumes_one_time_burst_first() {
+        let mut b = bucket(10, 1000, Some(5));
+        assert_eq!(b.reduce(0, 3), Reduction::Success);
+        assert_eq!((b.one_time_burst, b.budget), (2, 10));
+        assert_eq!(b.burst_exhausted_at, None);
+
+        assert_eq!(b.reduce(10 * MILLI, 4), Reduction::Success);
+        assert_eq!((b.one_time_burst, b.budget), (0, 8));
+        assert_eq!(b.burst_exhausted_at, Some(10 * MILLI));
+    }
+
+    #[test]
+    fn over_consumption_blocks_for_the_excess() {
+        let mut b = bucket(10, 1000, None);
+        assert_eq!(b.reduce(0, 25), Reduction::OverConsumption(1500 * MILLI));
+        assert_eq!(b.budget, 0);
+
+        let limiter = RateLimiter {
+            bandwidth: Some(Box::new(TokenBucket::new(1000, 10))),
+            ..Default::default()
+        };
+        let trace = [
+            IoRequest::new(Duration::ZERO, 25),
+            IoRequest::new(Duration::ZERO, 1),
+        ];
+        let report = simulate(&limiter, &trace);
+        assert_eq!(report.throttled_time, Duration::from_millis(1500));
+        assert_eq!(report.elapsed, Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn gives_back_the_op_of_a_request_refused_by_bandwidth() {
+        // Without getting its op back, the second request would exhaust the ops bucket while
+        // waiting for bandwidth, and then wait seconds for an op.
+        let limiter = RateLimiter {
+            bandwidth: Some(Box::new(TokenBucket::new(1000, 100))),
+            ops: Some(Box::new(TokenBucket::new(10_000, 2))),
+            ..Default::default()
+        };
+        let trace = uniform_trace(2, Duration::ZERO, 100);
+        let report = simulate(&limiter, &trace);
+        assert_eq!(report.elapsed, Duration::from_secs(1));
+        assert_eq!(report.throttled_requests, 1);
+    }
+}
@@ -0,0 +1,235 @@
+//! Human-readable units of data sizes and rates.
+
+use std::{fmt, str::FromStr};
+
+use compact_str::CompactString;
+
+use crate::rate_limit::Error;
+
+const UNITS: &[(&str, f64)] = &[
+    ("B", 1.0),
+    ("KB", 1e3),
+    ("MB", 1e6),
+    ("GB", 1e9),
+    ("TB", 1e12),
+    ("KiB", 1024.0),
+    ("MiB", 1024.0 * 1024.0),
+    ("GiB", 1024.0 * 1024.0 * 1024.0),
+    ("TiB", 1024.0 * 1024.0 * 1024.0 * 1024.0),
+    ("bit", 1.0 / 8.0),
+    ("Kbit", 1e3 / 8.0),
+    ("Mbit", 1e6 / 8.0),
+    ("Gbit", 1e9 / 8.0),
+];
+
+/// An amount of bytes, parsed from strings like `"64 MiB"`, `"1.5GB"` or `"800 Mbit"`.
+///
+/// Decimal (`KB`, `MB`, ...), binary (`KiB`, `MiB`, ...) and bit (`Kbit`, `Mbit`, ...) units are
+/// supported; a plain number is taken as bytes.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ByteSize(pub u64);
+
+/// A throughput in bytes per second, parsed from strings like `"200 MiB/s"` or `"1 Gbit/s"`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ByteRate(pub u64);
+
+/// A rate of operations per second, parsed from strings like `"5000 IOPS"`, `"5k ops/s"` or
+/// `"250/s"`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct OpRate(pub u64);
+
+fn parse_error(input: &str, reason: &'static str) -> Error {
+    Error::Parse {
+        input: CompactString::from(input),
+        reason,
+    }
+}
+
+/// Split `"1.5 MiB"` into `1.5` and `"MiB"`.
+fn split_number(input: &str) -> Result<(f64, &str), Error> {
+    let trimmed = input.trim();
+    let end = trimmed
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '_'))
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(end);
+    let number: f64 = number
+        .replace('_', "")
+        .parse()
+        .map_err(|_| parse_error(input, "expected a non-negative number"))?;
+    Ok((number, unit.trim()))
+}
+
+fn to_u64(input: &str, value: f64) -> Result<u64, Error> {
+    if !value.is_finite() || value > u64::MAX as f64 {
+        return Err(parse_error(input, "value out of range"));
+    }
+    Ok(value.round() as u64)
+}
+
+impl FromStr for ByteSize {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (number, unit) = split_number(input)?;
+        let multiplier = if unit.is_empty() {
+            1.0
+        } else {
+            // Only the case of the prefix is insignificant (e.g. "kB" and "KB"), since "MB" and
+            // "Mb" are rather different.
+            UNITS
+                .iter()
+                .find(|(name, _)| {
+                    *name == unit
+                        || (name.len() > 1
+                            && name.get(1..) == unit.get(1..)
+                            && name[..1].eq_ignore_ascii_case(unit.get(..1).unwrap_or_default()))
+                })
+                .map(|(_, multiplier)| *multiplier)
+                .ok_or_else(|| parse_error(input, "unknown unit of size"))?
+        };
+        to_u64(input, number * multiplier).map(Self)
+    }
+}
+
+impl FromStr for ByteRate {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let size = input
+            .trim()
+            .strip_suffix("/s")
+            .ok_or_else(|| parse_error(input, "expected a rate per second (e.g. '200 MiB/s')"))?;
+        size.parse::<ByteSize>()
+            .map(|ByteSize(bytes)| Self(bytes))
+            .map_err(|_| parse_error(input, "expected a rate per second (e.g. '200 MiB/s')"))
+    }
+}
+
+impl FromStr for OpRate {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (number, rest) = split_number(input)?;
+        let (multiplier, unit) = match rest.chars().next() {
+            Some('k' | 'K') => (1e3, &rest[1..]),
+            Some('M') => (1e6, &rest[1..]),
+            _ => (1.0, rest),
+        };
+        match unit.trim() {
+            "IOPS" | "iops" | "ops/s" | "/s" => to_u64(input, number * multiplier).map(Self),
+            _ => Err(parse_error(input, "expected 'IOPS', 'ops/s' or '/s'")),
+        }
+    }
+}
+
+/// Format `value` in the largest binary unit in which it is at least 1, with up to two
+/// decimals.
+pub(crate) fn fmt_bytes(f: &mut fmt::Formatter<'_>, value: f64, suffix: &str) -> fmt::Result {
+    const BINARY: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut scaled = value;
+    let mut unit = 0;
+    while scaled >= 1024.0 && unit < BINARY.len() - 1 {
+        scaled /= 1024.0;
+        unit += 1;
+    }
+    write!(f, "{} {}{suffix}", trim_decimals(scaled), BINARY[unit])
+}
+
+pub(crate) fn trim_decimals(value: f64) -> CompactString {
+    let formatted = compact_str::format_compact!("{value:.2}");
+    CompactString::from(formatted.trim_end_matches('0').trim_end_matches('.'))
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_bytes(f, self.0 as f64, "")
+    }
+}
+
+impl fmt::Display for ByteRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_bytes(f, self.0 as f64, "/s")
+    }
+}
+
+impl fmt::Display for OpRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} IOPS", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn size(input: &str) -> Result<u64, Error> {
+        input.parse::<ByteSize>().map(|ByteSize(bytes)| bytes)
+    }
+
+    fn reason(result: Result<impl fmt::Debug, Error>) -> &'static str {
+        match result {
+            Err(Error::Parse { reason, .. }) => reason,
+            other => panic!("expected a parse error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_decimal_and_binary_sizes() {
+        assert_eq!(size("4096"), Ok(4096));
+        assert_eq!(size("1_000 B"), Ok(1000));
+        assert_eq!(size("1.5GB"), Ok(1_500_000_000));
+        assert_eq!(size("2 kB"), Ok(2000));
+        assert_eq!(size("2 KB"), Ok(2000));
+        assert_eq!(size("2 KiB"), Ok(2048));
+        assert_eq!(size("64 MiB"), Ok(64 << 20));
+        assert_eq!(size("1 TiB"), Ok(1 << 40));
+    }
+
+    #[test]
+    fn parses_bits_as_eighths_of_bytes() {
+        assert_eq!(size("800 Mbit"), Ok(100_000_000));
+        assert_eq!(size("1 kbit"), Ok(125));
+        assert_eq!(size("12 bit"), Ok(2));
+        // "Mb" is neither megabytes nor megabits.
+        assert_eq!(reason(size("1 Mb")), "unknown unit of size");
+    }
+
+    #[test]
+    fn parses_rates() {
+        assert_eq!("200 MiB/s".parse(), Ok(ByteRate(200 << 20)));
+        assert_eq!("1 Gbit/s".parse(), Ok(ByteRate(125_000_000)));
+        assert_eq!("5000 IOPS".parse(), Ok(OpRate(5000)));
+        assert_eq!("5k ops/s".parse(), Ok(OpRate(5000)));
+        assert_eq!("1.5M iops".parse(), Ok(OpRate(1_500_000)));
+        assert_eq!("250/s".parse(), Ok(OpRate(250)));
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert_eq!(reason(size("")), "expected a non-negative number");
+        assert_eq!(reason(size("-5 MiB")), "expected a non-negative number");
+        assert_eq!(reason(size("5 XB")), "unknown unit of size");
+        assert_eq!(reason(size("99999999999 TiB")), "value out of range");
+        assert_eq!(
+            reason("200 MiB".parse::<ByteRate>()),
+            "expected a rate per second (e.g. '200 MiB/s')"
+        );
+        assert_eq!(
+            reason("200 MiB/ms".parse::<ByteRate>()),
+            "expected a rate per second (e.g. '200 MiB/s')"
+        );
+        assert_eq!(
+            reason("5 widgets".parse::<OpRate>()),
+            "expected 'IOPS', 'ops/s' or '/s'"
+        );
+    }
+
+    #[test]
+    fn displays_in_binary_units() {
+        assert_eq!(ByteSize(512).to_string(), "512 B");
+        assert_eq!(ByteSize(1536).to_string(), "1.5 KiB");
+        assert_eq!(ByteRate(200 << 20).to_string(), "200 MiB/s");
+        assert_eq!(OpRate(5000).to_string(), "5000 IOPS");
+    }
+}
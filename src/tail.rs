@@ -0,0 +1,93 @@
+//! Line-oriented reading of files and named pipes that Firecracker keeps writing to.
+
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+
+use tokio::{
+    fs::File,
+    io::{AsyncBufRead, BufReader},
+    time::{sleep, Sleep},
+};
+
+#[derive(Debug)]
+pub(crate) struct LineTailer {
+    reader: BufReader<File>,
+    buf: Vec<u8>,
+    follow: Option<Duration>,
+    /// The wait for more data at end-of-file, when following.
+    idle: Option<Pin<Box<Sleep>>>,
+}
+
+impl LineTailer {
+    #[inline]
+    pub(crate) fn new(file: File) -> Self {
+        Self {
+            reader: BufReader::new(file),
+            buf: Vec::new(),
+            follow: None,
+            idle: None,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn follow(&mut self, poll_interval: Duration) {
+        self.follow = Some(poll_interval);
+    }
+
+    /// Poll for the next non-empty line, without its trailing newline.
+    ///
+    /// Returns `None` at end-of-file, unless following, in which case end-of-file is polled
+    /// until a complete line has been written.
+    pub(crate) fn poll_next_line(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<io::Result<String>>> {
+        loop {
+            if let Some(idle) = &mut self.idle {
+                ready!(idle.as_mut().poll(cx));
+                self.idle = None;
+            }
+
+            let available = match ready!(Pin::new(&mut self.reader).poll_fill_buf(cx)) {
+                Ok(available) => available,
+                Err(err) => return Poll::Ready(Some(Err(err))),
+            };
+            if available.is_empty() {
+                // End-of-file; `self.buf` holds a (possibly empty) partially written line.
+                match self.follow {
+                    Some(poll_interval) => self.idle = Some(Box::pin(sleep(poll_interval))),
+                    None if self.buf.iter().all(u8::is_ascii_whitespace) => {
+                        self.buf.clear();
+                        return Poll::Ready(None);
+                    }
+                    None => return Poll::Ready(Some(self.take_line())),
+                }
+                continue;
+            }
+
+            let newline = available.iter().position(|&b| b == b'\n');
+            let used = newline.map_or(available.len(), |i| i + 1);
+            self.buf.extend_from_slice(&available[..used]);
+            Pin::new(&mut self.reader).consume(used);
+            if newline.is_some() {
+                if self.buf.iter().all(u8::is_ascii_whitespace) {
+                    self.buf.clear();
+                    continue;
+                }
+                return Poll::Ready(Some(self.take_line()));
+            }
+        }
+    }
+
+    fn take_line(&mut self) -> io::Result<String> {
+        let mut line = String::from_utf8(std::mem::take(&mut self.buf))
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        line.truncate(line.trim_end_matches(['\r', '\n']).len());
+        Ok(line)
+    }
+}
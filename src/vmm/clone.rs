@@ -0,0 +1,353 @@
+//! Fan-out cloning of microVMs from a single snapshot.
+//!
+//! Each clone gets its own Firecracker process, API socket and (optionally) log file. The
+//! snapshot is always loaded paused, so that per-clone MMDS identity is in place before the
+//! guest gets a chance to run; clones are resumed afterwards if so requested.
+
+use std::{fmt, sync::Arc, time::Duration};
+
+use camino::Utf8PathBuf;
+use compact_str::CompactString;
+use tokio::{sync::Semaphore, task::JoinSet};
+use tracing::{debug, instrument, warn, Level};
+
+use crate::{
+    models,
+    vmm::{self, Vmm, VmmConfig, FIRECRACKER_BIN, STARTUP_TIMEOUT},
+    Api,
+};
+
+/// The snapshot every clone is restored from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CloneSource {
+    /// Path to the file that contains the microVM state.
+    pub snapshot_path: Utf8PathBuf,
+    /// Backend of the guest memory.
+    pub mem_backend: models::MemoryBackend,
+    /// Enable dirty page tracking on the clones.
+    pub track_dirty_pages: Option<bool>,
+}
+
+impl CloneSource {
+    #[inline]
+    pub fn new(snapshot_path: impl Into<Utf8PathBuf>, mem_backend: models::MemoryBackend) -> Self {
+        Self {
+            snapshot_path: snapshot_path.into(),
+            mem_backend,
+            track_dirty_pages: None,
+        }
+    }
+}
+
+/// The identity of a single clone.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CloneTarget {
+    /// MicroVM / instance ID of the clone.
+    pub id: CompactString,
+    /// Path of the clone's API socket.
+    pub api_sock: Utf8PathBuf,
+    /// Path of the clone's log file; the logger is left unconfigured if absent.
+    pub log_path: Option<Utf8PathBuf>,
+    /// TAP devices backing the clone's network interfaces.
+    pub network_overrides: Vec<models::NetworkOverride>,
+    /// MMDS contents to `PUT` right after the snapshot is loaded.
+    pub mmds: Option<serde_json::Value>,
+}
+
+impl CloneTarget {
+    #[inline]
+    pub fn new(id: impl Into<CompactString>, api_sock: impl Into<Utf8PathBuf>) -> Self {
+        Self {
+            id: id.into(),
+            api_sock: api_sock.into(),
+            log_path: None,
+            network_overrides: Vec::new(),
+            mmds: None,
+        }
+    }
+}
+
+/// Launches many clones of a [`CloneSource`] with bounded concurrency.
+#[derive(Clone, Debug)]
+pub struct Cloner {
+    source: CloneSource,
+    firecracker_bin: Utf8PathBuf,
+    extra_args: Vec<CompactString>,
+    startup_timeout: Duration,
+    logger: models::Logger,
+    concurrency: usize,
+    resume: bool,
+}
+
+impl Cloner {
+    /// Construct a `Cloner` for `source`, which launches up to 8 clones at a time and resumes
+    /// them once restored.
+    pub fn new(source: CloneSource) -> Self {
+        Self {
+            source,
+            firecracker_bin: FIRECRACKER_BIN.into(),
+            extra_args: Vec::new(),
+            startup_timeout: STARTUP_TIMEOUT,
+            logger: models::Logger::default(),
+            concurrency: 8,
+            resume: true,
+        }
+    }
+
+    /// Path to the Firecracker binary used for all clones.
+    pub fn firecracker_bin(mut self, firecracker_bin: impl Into<Utf8PathBuf>) -> Self {
+        self.firecracker_bin = firecracker_bin.into();
+        self
+    }
+
+    /// Additional command line arguments for every Firecracker process.
+    pub fn extra_args(mut self, extra_args: Vec<CompactString>) -> Self {
+        self.extra_args = extra_args;
+        self
+    }
+
+    /// How long to wait for each clone's API socket to appear.
+    pub fn startup_timeout(mut self, startup_timeout: Duration) -> Self {
+        self.startup_timeout = startup_timeout;
+        self
+    }
+
+    /// Logger configuration shared by all clones; each clone's `log_path` overrides the one in
+    /// `logger`.
+    pub fn logger(mut self, logger: models::Logger) -> Self {
+        self.logger = logger;
+        self
+    }
+
+    /// Maximum number of clones being launched and restored at the same time.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Whether clones are resumed once restored, or left paused.
+    pub fn resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    /// Launch and restore one clone per target.
+    ///
+    /// The returned results are in the same order as `targets`. A clone that fails at any stage
+    /// is shut down, and does not affect the others.
+    #[instrument(level = Level::DEBUG, skip_all, fields(clones = targets.len()))]
+    pub async fn clone_all(&self, targets: Vec<CloneTarget>) -> Vec<Result<Vmm, CloneError>> {
+        let permits = Arc::new(Semaphore::new(self.concurrency));
+        let mut tasks = JoinSet::new();
+        let n = targets.len();
+
+        for (idx, target) in targets.into_iter().enumerate() {
+            let this = self.clone();
+            let permits = Arc::clone(&permits);
+            tasks.spawn(async move {
+                let _permit = permits
+                    .acquire_owned()
+                    .await
+                    .expect("semaphore never closed");
+                (idx, this.clone_one(target).await)
+            });
+        }
+
+        let mut results: Vec<Option<Result<Vmm, CloneError>>> = (0..n).map(|_| None).collect();
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((idx, result)) => results[idx] = Some(result),
+                Err(err) => std::panic::resume_unwind(err.into_panic()),
+            }
+        }
+        results
+            .into_iter()
+            .map(|result| result.expect("every clone task reports back"))
+            .collect()
+    }
+
+    /// Launch and restore a single clone.
+    #[instrument(level = Level::DEBUG, skip_all, fields(id = %target.id))]
+    pub async fn clone_one(&self, target: CloneTarget) -> Result<Vmm, CloneError> {
+        let id = target.id.clone();
+        let fail = |stage| {
+            let id = id.clone();
+            move |source| CloneError { id, stage, source }
+        };
+
+        let config = VmmConfig {
+            firecracker_bin: self.firecracker_bin.clone(),
+            id: target.id.clone(),
+            api_sock: target.api_sock.clone(),
+            extra_args: self.extra_args.clone(),
+            startup_timeout: self.startup_timeout,
+        };
+        let vmm = Vmm::spawn(&config).await.map_err(fail(CloneStage::Spawn))?;
+
+        match self.restore(&vmm, target).await {
+            Ok(()) => {
+                debug!("clone is ready");
+                Ok(vmm)
+            }
+            Err((stage, err)) => {
+                if let Err(err) = vmm.shutdown().await {
+                    warn!(error = %err, "failed to shut down clone after a failed restore");
+                }
+                Err(fail(stage)(err))
+            }
+        }
+    }
+
+    async fn restore(
+        &self,
+        vmm: &Vmm,
+        target: CloneTarget,
+    ) -> Result<(), (CloneStage, vmm::Error)> {
+        let client = vmm.client();
+
+        if let Some(log_path) = target.log_path {
+            vmm::touch(&log_path)
+                .await
+                .map_err(|err| (CloneStage::Logger, err))?;
+            let logger = models::Logger {
+                log_path: Some(log_path),
+                ..self.logger.clone()
+            };
+            client
+                .put_logger(logger)
+                .await
+                .map_err(|err| (CloneStage::Logger, err.into()))?;
+        }
+
+        let network_overrides =
+            (!target.network_overrides.is_empty()).then_some(target.network_overrides);
+        client
+            .load_snapshot(models::SnapshotLoadParams {
+                track_dirty_pages: self.source.track_dirty_pages,
+                mem_file_path: None,
+                mem_backend: Some(self.source.mem_backend.clone()),
+                snapshot_path: self.source.snapshot_path.clone(),
+                resume_vm: Some(false),
+                network_overrides,
+                extras: Default::default(),
+            })
+            .await
+            .map_err(|err| (CloneStage::LoadSnapshot, err.into()))?;
+
+        if let Some(mmds) = target.mmds {
+            client
+                .put_mmds(Some(mmds))
+                .await
+                .map_err(|err| (CloneStage::Mmds, err.into()))?;
+        }
+
+        if self.resume {
+            client
+                .patch_vm(models::Vm::new(models::vm::State::Resumed))
+                .await
+                .map_err(|err| (CloneStage::Resume, err.into()))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The stage at which cloning failed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum CloneStage {
+    Spawn,
+    Logger,
+    LoadSnapshot,
+    Mmds,
+    Resume,
+}
+
+impl fmt::Display for CloneStage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Spawn => write!(f, "spawning the VMM"),
+            Self::Logger => write!(f, "configuring the logger"),
+            Self::LoadSnapshot => write!(f, "loading the snapshot"),
+            Self::Mmds => write!(f, "putting MMDS contents"),
+            Self::Resume => write!(f, "resuming the microVM"),
+        }
+    }
+}
+
+/// Error returned for a single clone that failed.
+#[derive(Debug, thiserror::Error)]
+#[error("clone '{id}' failed while {stage}")]
+pub struct CloneError {
+    pub id: CompactString,
+    pub stage: CloneStage,
+    #[source]
+    pub source: vmm::Error,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::*;
+
+    /// A stand-in for Firecracker that creates its "API socket" as a plain file, which the
+    /// [`Client`](crate::Client) then fails to connect to, unless its ID starts with `bad`, in
+    /// which case it exits right away.
+    const STUB: &str = r#"#!/bin/sh
+case "$4" in bad*) exit 1 ;; esac
+: > "$2"
+exec sleep 10
+"#;
+
+    #[tokio::test]
+    async fn clone_all_reports_every_clone_in_order() {
+        let dir = Utf8PathBuf::from_path_buf(std::env::temp_dir())
+            .unwrap()
+            .join(format!("wick-clone-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let stub = dir.join("firecracker");
+        std::fs::write(&stub, STUB).unwrap();
+        std::fs::set_permissions(&stub, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let source = CloneSource::new(
+            dir.join("snapshot"),
+            models::MemoryBackend::new(models::memory_backend::BackendType::File, dir.join("mem")),
+        );
+        let cloner = Cloner::new(source).firecracker_bin(&stub).concurrency(2);
+        let ids = ["ok0", "bad1", "ok2", "bad3", "ok4"];
+        let targets = ids
+            .iter()
+            .map(|id| CloneTarget::new(*id, dir.join(format!("{id}.sock"))))
+            .collect();
+
+        let results = cloner.clone_all(targets).await;
+
+        let outcomes: Vec<_> = results
+            .iter()
+            .map(|result| match result {
+                Ok(vmm) => panic!("clone {} unexpectedly succeeded", vmm.id()),
+                Err(err) => (err.id.as_str(), err.stage),
+            })
+            .collect();
+        assert_eq!(
+            outcomes,
+            [
+                ("ok0", CloneStage::LoadSnapshot),
+                ("bad1", CloneStage::Spawn),
+                ("ok2", CloneStage::LoadSnapshot),
+                ("bad3", CloneStage::Spawn),
+                ("ok4", CloneStage::LoadSnapshot),
+            ]
+        );
+        assert!(matches!(
+            results[1].as_ref().unwrap_err().source,
+            vmm::Error::Exited(_)
+        ));
+        // Clones that failed after spawning were shut down, which removes their API sockets.
+        for id in ids {
+            assert!(!dir.join(format!("{id}.sock")).exists(), "{id}");
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
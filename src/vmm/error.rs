@@ -0,0 +1,26 @@
+use std::{io, process::ExitStatus};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("failed to spawn the process")]
+    Spawn(#[source] io::Error),
+
+    #[error("I/O error")]
+    Io(#[source] io::Error),
+
+    #[error("process exited prematurely ({0})")]
+    Exited(ExitStatus),
+
+    #[error("timed out waiting for the socket to appear")]
+    Timeout,
+
+    #[error("API request failed")]
+    Api(#[source] crate::Error),
+}
+
+impl From<crate::Error> for Error {
+    #[inline]
+    fn from(err: crate::Error) -> Self {
+        Error::Api(err)
+    }
+}
@@ -0,0 +1,186 @@
+//! Launching and supervising Firecracker VMM processes.
+
+pub mod clone;
+pub mod error;
+pub mod pool;
+pub mod vhost_user;
+
+use std::{process::ExitStatus, time::Duration};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use compact_str::CompactString;
+use tokio::{
+    process::{Child, Command},
+    time::{sleep, Instant},
+};
+use tracing::{debug, instrument, Level};
+
+pub use error::Error;
+
+use crate::Client;
+
+/// Default path of the Firecracker binary, resolved through `$PATH`.
+pub const FIRECRACKER_BIN: &str = "firecracker";
+
+/// Default time to wait for the API socket of a newly spawned VMM to appear.
+pub const STARTUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Interval at which a socket's presence is checked while a VMM or a backend starts up.
+const SOCKET_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Describes how a Firecracker VMM process should be launched.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VmmConfig {
+    /// Path to the Firecracker binary.
+    pub firecracker_bin: Utf8PathBuf,
+    /// MicroVM / instance ID, passed to Firecracker through `--id`.
+    pub id: CompactString,
+    /// Path of the UNIX domain socket the API server will listen on.
+    pub api_sock: Utf8PathBuf,
+    /// Additional command line arguments for the Firecracker process.
+    pub extra_args: Vec<CompactString>,
+    /// How long to wait for the API socket to appear before giving up.
+    pub startup_timeout: Duration,
+}
+
+impl VmmConfig {
+    #[inline]
+    pub fn new(id: impl Into<CompactString>, api_sock: impl Into<Utf8PathBuf>) -> Self {
+        Self {
+            firecracker_bin: FIRECRACKER_BIN.into(),
+            id: id.into(),
+            api_sock: api_sock.into(),
+            extra_args: Vec::new(),
+            startup_timeout: STARTUP_TIMEOUT,
+        }
+    }
+}
+
+/// A running Firecracker VMM process, along with a [`Client`] for its API socket.
+///
+/// The process is killed if the `Vmm` is dropped; use [`shutdown`](Self::shutdown) to also
+/// reap it and remove its API socket.
+#[derive(Debug)]
+pub struct Vmm {
+    id: CompactString,
+    api_sock: Utf8PathBuf,
+    child: Child,
+    client: Client,
+}
+
+impl Vmm {
+    /// Spawn a Firecracker process as described by `config` and wait until its API socket
+    /// appears.
+    ///
+    /// A stale socket left behind at `config.api_sock` is removed beforehand, since Firecracker
+    /// refuses to bind over it.
+    #[instrument(level = Level::DEBUG)]
+    pub async fn spawn(config: &VmmConfig) -> Result<Self, Error> {
+        remove_if_exists(&config.api_sock).await?;
+
+        let mut child = Command::new(&config.firecracker_bin)
+            .arg("--api-sock")
+            .arg(&config.api_sock)
+            .arg("--id")
+            .arg(config.id.as_str())
+            .args(config.extra_args.iter().map(CompactString::as_str))
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(Error::Spawn)?;
+
+        wait_for_socket(&config.api_sock, &mut child, config.startup_timeout).await?;
+        debug!(pid = child.id(), "Firecracker API socket is up");
+
+        Ok(Self {
+            id: config.id.clone(),
+            api_sock: config.api_sock.clone(),
+            client: Client::new(&config.api_sock),
+            child,
+        })
+    }
+
+    /// The MicroVM / instance ID this VMM was launched with.
+    #[inline]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Path of the VMM's API socket.
+    #[inline]
+    pub fn api_sock(&self) -> &Utf8Path {
+        &self.api_sock
+    }
+
+    /// The OS-assigned process ID, if the process has not been reaped yet.
+    #[inline]
+    pub fn pid(&self) -> Option<u32> {
+        self.child.id()
+    }
+
+    /// A [`Client`] connected to the VMM's API socket.
+    #[inline]
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Check whether the process has exited, without blocking.
+    pub fn try_wait(&mut self) -> Result<Option<ExitStatus>, Error> {
+        self.child.try_wait().map_err(Error::Io)
+    }
+
+    /// Wait for the process to exit.
+    pub async fn wait(&mut self) -> Result<ExitStatus, Error> {
+        self.child.wait().await.map_err(Error::Io)
+    }
+
+    /// Kill the process, reap it and remove its API socket.
+    #[instrument(level = Level::DEBUG, skip(self), fields(id = %self.id))]
+    pub async fn shutdown(mut self) -> Result<(), Error> {
+        if self.child.try_wait().map_err(Error::Io)?.is_none() {
+            self.child.kill().await.map_err(Error::Io)?;
+        }
+        remove_if_exists(&self.api_sock).await
+    }
+}
+
+/// Wait until `child` creates the socket at `path`, failing if it exits or `timeout` elapses
+/// first.
+pub(crate) async fn wait_for_socket(
+    path: &Utf8Path,
+    child: &mut Child,
+    timeout: Duration,
+) -> Result<(), Error> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if ::tokio::fs::try_exists(path).await.map_err(Error::Io)? {
+            return Ok(());
+        }
+        if let Some(status) = child.try_wait().map_err(Error::Io)? {
+            return Err(Error::Exited(status));
+        }
+        if Instant::now() >= deadline {
+            return Err(Error::Timeout);
+        }
+        sleep(SOCKET_POLL_INTERVAL).await;
+    }
+}
+
+pub(crate) async fn remove_if_exists(path: &Utf8Path) -> Result<(), Error> {
+    match ::tokio::fs::remove_file(path).await {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(Error::Io(err)),
+        _ => Ok(()),
+    }
+}
+
+/// Create `path` if it does not exist, without truncating it.
+///
+/// Firecracker expects log and metrics files to exist before they are configured.
+pub(crate) async fn touch(path: &Utf8Path) -> Result<(), Error> {
+    ::tokio::fs::File::options()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .map(drop)
+        .map_err(Error::Io)
+}
@@ -0,0 +1,303 @@
+//! A pool of microVMs restored from a snapshot and kept paused, ready to be handed out.
+//!
+//! The pool keeps up to [`PoolConfig::size`] VMMs restored and paused. Whenever one is handed
+//! out (or found unhealthy and discarded), a background task restores a replacement; at most
+//! [`PoolConfig::concurrency`] restores are in flight at any time, and none are started while
+//! the pool is full.
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use tokio::{
+    sync::{watch, Notify, Semaphore},
+    task::{JoinHandle, JoinSet},
+    time::{interval, sleep, MissedTickBehavior},
+};
+use tracing::{debug, instrument, warn, Level};
+
+use crate::{
+    models,
+    vmm::{
+        clone::{CloneTarget, Cloner},
+        Error, Vmm,
+    },
+    Api,
+};
+
+/// Produces the [`CloneTarget`] (API socket, log path, TAP devices) of the `n`-th microVM
+/// restored by the pool.
+pub type TargetFactory = dyn Fn(u64) -> CloneTarget + Send + Sync;
+
+/// Sizing and timing of a [`WarmPool`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PoolConfig {
+    /// Number of paused microVMs the pool tries to keep ready.
+    pub size: usize,
+    /// Maximum number of restores in flight.
+    pub concurrency: usize,
+    /// Interval between health checks of idle microVMs.
+    pub health_check_interval: Duration,
+    /// Delay before retrying after a failed restore.
+    pub retry_backoff: Duration,
+}
+
+impl PoolConfig {
+    #[inline]
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            concurrency: 2,
+            health_check_interval: Duration::from_secs(5),
+            retry_backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+/// How the identity of a handed-out microVM is written to its MMDS.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MmdsUpdate {
+    /// Replace the MMDS contents (`PUT /mmds`).
+    Put(serde_json::Value),
+    /// Merge into the MMDS contents (`PATCH /mmds`).
+    Patch(serde_json::Value),
+}
+
+/// A pool of pre-restored, paused microVMs.
+///
+/// Background tasks are aborted when the pool is dropped; idle microVMs are then killed along
+/// with it. Use [`shutdown`](Self::shutdown) to also wait for the restores in flight and remove
+/// the API sockets of all microVMs.
+pub struct WarmPool {
+    shared: Arc<Shared>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+struct Shared {
+    cloner: Cloner,
+    factory: Box<TargetFactory>,
+    config: PoolConfig,
+    idle: Mutex<VecDeque<Vmm>>,
+    ready: Notify,
+    /// One permit per pool slot that is neither idle nor being restored.
+    vacant: Semaphore,
+    seq: AtomicU64,
+    /// Set once by [`WarmPool::shutdown`] to stop the background tasks.
+    stop: watch::Sender<bool>,
+}
+
+impl WarmPool {
+    /// Start a pool that restores microVMs through `cloner`, naming each of them through
+    /// `factory`.
+    ///
+    /// The `cloner` is always configured to leave microVMs paused. Must be called from within a
+    /// Tokio runtime.
+    pub fn start(
+        cloner: Cloner,
+        factory: impl Fn(u64) -> CloneTarget + Send + Sync + 'static,
+        config: PoolConfig,
+    ) -> Self {
+        let shared = Arc::new(Shared {
+            cloner: cloner.resume(false),
+            factory: Box::new(factory),
+            config,
+            idle: Mutex::new(VecDeque::with_capacity(config.size)),
+            ready: Notify::new(),
+            vacant: Semaphore::new(config.size),
+            seq: AtomicU64::new(0),
+            stop: watch::Sender::new(false),
+        });
+
+        let tasks = vec![
+            ::tokio::spawn(Arc::clone(&shared).refill()),
+            ::tokio::spawn(Arc::clone(&shared).check_health()),
+        ];
+        Self { shared, tasks }
+    }
+
+    /// Number of idle microVMs currently ready to be handed out.
+    pub fn idle(&self) -> usize {
+        self.shared.idle.lock().expect("pool lock poisoned").len()
+    }
+
+    /// Take a microVM out of the pool, waiting for one to become ready if necessary, then
+    /// apply `identity` to its MMDS and resume it.
+    ///
+    /// If applying the identity or resuming fails, the microVM is shut down and the error is
+    /// returned.
+    #[instrument(level = Level::DEBUG, skip_all)]
+    pub async fn acquire(&self, identity: Option<MmdsUpdate>) -> Result<Vmm, Error> {
+        let vmm = loop {
+            let ready = self.shared.ready.notified();
+            if let Some(vmm) = self.shared.take() {
+                break vmm;
+            }
+            ready.await;
+        };
+        hand_out(vmm, identity).await
+    }
+
+    /// Like [`acquire`](Self::acquire), but returns `None` instead of waiting when the pool is
+    /// empty.
+    pub async fn try_acquire(&self, identity: Option<MmdsUpdate>) -> Option<Result<Vmm, Error>> {
+        let vmm = self.shared.take()?;
+        Some(hand_out(vmm, identity).await)
+    }
+
+    /// Stop refilling the pool, wait for the restores in flight, and shut down all idle
+    /// microVMs.
+    pub async fn shutdown(mut self) {
+        self.shared.stop.send_replace(true);
+        for task in self.tasks.drain(..) {
+            if let Err(err) = task.await {
+                warn!(error = %err, "pool task failed");
+            }
+        }
+        let idle = std::mem::take(&mut *self.shared.idle.lock().expect("pool lock poisoned"));
+        for vmm in idle {
+            let id = vmm.id().to_owned();
+            if let Err(err) = vmm.shutdown().await {
+                warn!(%id, error = %err, "failed to shut down idle microVM");
+            }
+        }
+    }
+}
+
+impl Drop for WarmPool {
+    fn drop(&mut self) {
+        self.tasks.iter().for_each(JoinHandle::abort);
+    }
+}
+
+impl std::fmt::Debug for WarmPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WarmPool")
+            .field("config", &self.shared.config)
+            .field("idle", &self.idle())
+            .finish()
+    }
+}
+
+async fn hand_out(vmm: Vmm, identity: Option<MmdsUpdate>) -> Result<Vmm, Error> {
+    let result = async {
+        match identity {
+            Some(MmdsUpdate::Put(data)) => vmm.client().put_mmds(Some(data)).await?,
+            Some(MmdsUpdate::Patch(data)) => vmm.client().patch_mmds(Some(data)).await?,
+            None => (),
+        }
+        vmm.client()
+            .patch_vm(models::Vm::new(models::vm::State::Resumed))
+            .await
+    }
+    .await;
+
+    match result {
+        Ok(()) => Ok(vmm),
+        Err(err) => {
+            let id = vmm.id().to_owned();
+            if let Err(err) = vmm.shutdown().await {
+                warn!(%id, error = %err, "failed to shut down microVM after a failed hand-out");
+            }
+            Err(err.into())
+        }
+    }
+}
+
+impl Shared {
+    /// Pop an idle microVM, vacating its slot so that a replacement gets restored.
+    fn take(&self) -> Option<Vmm> {
+        let vmm = self.idle.lock().expect("pool lock poisoned").pop_front()?;
+        self.vacant.add_permits(1);
+        Some(vmm)
+    }
+
+    fn put(&self, vmm: Vmm) {
+        self.idle.lock().expect("pool lock poisoned").push_back(vmm);
+        self.ready.notify_one();
+    }
+
+    async fn refill(self: Arc<Self>) {
+        let mut stop = self.stop.subscribe();
+        let mut restores = JoinSet::new();
+        loop {
+            ::tokio::select! {
+                _ = stop.wait_for(|stop| *stop) => break,
+                permit = self.vacant.acquire(), if restores.len() < self.config.concurrency => {
+                    permit.expect("semaphore never closed").forget();
+                    restores.spawn(Arc::clone(&self).restore_one());
+                }
+                Some(_) = restores.join_next() => (),
+            }
+        }
+
+        // Aborting a restore midway would leak its API socket; let it finish instead, so that
+        // the microVM is shut down along with the idle ones.
+        while restores.join_next().await.is_some() {}
+    }
+
+    async fn restore_one(self: Arc<Self>) {
+        let n = self.seq.fetch_add(1, Ordering::Relaxed);
+        match self.cloner.clone_one((self.factory)(n)).await {
+            Ok(vmm) => {
+                debug!(id = vmm.id(), "microVM restored into the pool");
+                self.put(vmm);
+            }
+            Err(err) => {
+                warn!(error = %err, "failed to restore microVM into the pool");
+                sleep(self.config.retry_backoff).await;
+                self.vacant.add_permits(1);
+            }
+        }
+    }
+
+    async fn check_health(self: Arc<Self>) {
+        let mut ticks = interval(self.config.health_check_interval);
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut stop = self.stop.subscribe();
+        loop {
+            ::tokio::select! {
+                _ = stop.wait_for(|stop| *stop) => return,
+                _ = ticks.tick() => (),
+            }
+
+            // Check one microVM at a time, so that the rest can still be handed out meanwhile.
+            let len = self.idle.lock().expect("pool lock poisoned").len();
+            for _ in 0..len {
+                if *stop.borrow() {
+                    return;
+                }
+                let Some(mut vmm) = self.idle.lock().expect("pool lock poisoned").pop_front()
+                else {
+                    break;
+                };
+                if is_healthy(&mut vmm).await {
+                    self.put(vmm);
+                    continue;
+                }
+                warn!(id = vmm.id(), "discarding unhealthy microVM");
+                if let Err(err) = vmm.shutdown().await {
+                    warn!(error = %err, "failed to shut down unhealthy microVM");
+                }
+                self.vacant.add_permits(1);
+            }
+        }
+    }
+}
+
+async fn is_healthy(vmm: &mut Vmm) -> bool {
+    if !matches!(vmm.try_wait(), Ok(None)) {
+        return false;
+    }
+    matches!(
+        vmm.client().describe_instance().await,
+        Ok(models::InstanceInfo {
+            state: models::instance_info::State::Paused,
+            ..
+        })
+    )
+}
@@ -0,0 +1,167 @@
+//! Launching vhost-user-block backends, which emulate
+//! [`VhostUserBlock`](models::VhostUserBlock) drives outside of Firecracker.
+//!
+//! Firecracker connects to a backend's socket as soon as the drive is configured, so the backend
+//! must be listening by then; [`Backend::spawn`] waits for its socket to appear, and
+//! [`Backend::attach`] configures a drive on it.
+
+use std::{process::ExitStatus, time::Duration};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use tokio::process::{Child, Command};
+use tracing::{debug, instrument, Level};
+
+use crate::{
+    models,
+    vmm::{remove_if_exists, wait_for_socket, Error},
+    Api,
+};
+
+/// Default path of the `qemu-storage-daemon` binary, resolved through `$PATH`.
+pub const QEMU_STORAGE_DAEMON_BIN: &str = "qemu-storage-daemon";
+
+/// Builds the command that starts a vhost-user-block backend.
+///
+/// Implemented for closures, to support arbitrary backends:
+///
+/// ```
+/// use tokio::process::Command;
+/// use wick::vmm::vhost_user::Launcher;
+///
+/// let launcher = |socket: &camino::Utf8Path| {
+///     let mut cmd = Command::new("vhost-user-blk");
+///     cmd.arg("--socket-path").arg(socket).arg("--blk-file").arg("/srv/rootfs.ext4");
+///     cmd
+/// };
+/// # fn check(_: impl Launcher) {}
+/// # check(launcher);
+/// ```
+pub trait Launcher {
+    /// The command that starts a backend listening on `socket`.
+    fn command(&self, socket: &Utf8Path) -> Command;
+}
+
+impl<F: Fn(&Utf8Path) -> Command> Launcher for F {
+    #[inline]
+    fn command(&self, socket: &Utf8Path) -> Command {
+        self(socket)
+    }
+}
+
+/// Exports a disk image through `qemu-storage-daemon`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QemuStorageDaemon {
+    /// Path to the `qemu-storage-daemon` binary.
+    pub bin: Utf8PathBuf,
+    /// Path to the raw disk image to export.
+    pub image: Utf8PathBuf,
+    pub read_only: bool,
+}
+
+impl QemuStorageDaemon {
+    #[inline]
+    pub fn new(image: impl Into<Utf8PathBuf>, read_only: bool) -> Self {
+        Self {
+            bin: QEMU_STORAGE_DAEMON_BIN.into(),
+            image: image.into(),
+            read_only,
+        }
+    }
+}
+
+impl Launcher for QemuStorageDaemon {
+    fn command(&self, socket: &Utf8Path) -> Command {
+        // QEMU escapes commas in option values by doubling them.
+        let escape = |path: &Utf8Path| path.as_str().replace(',', ",,");
+        let (read_only, writable) = if self.read_only {
+            ("on", "off")
+        } else {
+            ("off", "on")
+        };
+
+        let mut cmd = Command::new(&self.bin);
+        cmd.arg("--blockdev").arg(format!(
+            "driver=file,node-name=disk,filename={},read-only={read_only}",
+            escape(&self.image),
+        ));
+        cmd.arg("--export").arg(format!(
+            "type=vhost-user-blk,id=disk,node-name=disk,addr.type=unix,addr.path={},writable={writable}",
+            escape(socket),
+        ));
+        cmd
+    }
+}
+
+/// A running vhost-user-block backend process.
+///
+/// The process is killed if the `Backend` is dropped; use [`shutdown`](Self::shutdown) to also
+/// reap it and remove its socket.
+#[derive(Debug)]
+pub struct Backend {
+    socket: Utf8PathBuf,
+    child: Child,
+}
+
+impl Backend {
+    /// Start a backend listening on `socket`, and wait until the socket appears.
+    ///
+    /// A stale socket left behind at `socket` is removed beforehand.
+    #[instrument(level = Level::DEBUG, skip(launcher))]
+    pub async fn spawn(
+        launcher: &impl Launcher,
+        socket: impl Into<Utf8PathBuf> + std::fmt::Debug,
+        startup_timeout: Duration,
+    ) -> Result<Self, Error> {
+        let socket = socket.into();
+        remove_if_exists(&socket).await?;
+
+        let mut child = launcher
+            .command(&socket)
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(Error::Spawn)?;
+        wait_for_socket(&socket, &mut child, startup_timeout).await?;
+        debug!(pid = child.id(), "vhost-user backend socket is up");
+
+        Ok(Self { socket, child })
+    }
+
+    /// Path of the backend's socket.
+    #[inline]
+    pub fn socket(&self) -> &Utf8Path {
+        &self.socket
+    }
+
+    /// The OS-assigned process ID, if the process has not been reaped yet.
+    #[inline]
+    pub fn pid(&self) -> Option<u32> {
+        self.child.id()
+    }
+
+    /// Configure `drive` on the VMM behind `api`, backed by this backend; its socket is
+    /// overwritten with the backend's.
+    pub async fn attach<A: Api>(
+        &self,
+        api: &A,
+        mut drive: models::VhostUserBlock,
+    ) -> Result<(), Error> {
+        drive.socket = self.socket.clone();
+        let drive_id = drive.drive_id.clone();
+        api.put_guest_drive_by_id(&drive_id, drive.into()).await?;
+        Ok(())
+    }
+
+    /// Check whether the process has exited, without blocking.
+    pub fn try_wait(&mut self) -> Result<Option<ExitStatus>, Error> {
+        self.child.try_wait().map_err(Error::Io)
+    }
+
+    /// Kill the process, reap it and remove its socket.
+    #[instrument(level = Level::DEBUG, skip(self), fields(socket = %self.socket))]
+    pub async fn shutdown(mut self) -> Result<(), Error> {
+        if self.child.try_wait().map_err(Error::Io)?.is_none() {
+            self.child.kill().await.map_err(Error::Io)?;
+        }
+        remove_if_exists(&self.socket).await
+    }
+}
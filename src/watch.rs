@@ -0,0 +1,163 @@
+//! [`Stream`]s that poll a VMM at an interval and yield values only when they change.
+//!
+//! A stream ends once a request fails: its last item is then an `Err` carrying the
+//! [`StopReason`], and no further requests are made.
+
+use std::time::Duration;
+
+use futures_core::Stream;
+use futures_util::{future, stream, StreamExt};
+use tokio::time::{interval, Interval, MissedTickBehavior};
+
+use crate::{
+    models::{self, instance_info::State},
+    Api, Error,
+};
+
+/// Why a stream stopped polling.
+#[derive(Debug, thiserror::Error)]
+pub enum StopReason {
+    /// The API socket could not be reached; the VMM has most probably exited.
+    #[error("the VMM went away")]
+    VmmGone(#[source] Error),
+
+    /// The VMM responded with an error, or with a malformed response.
+    #[error("the VMM failed to respond")]
+    Failed(#[source] Error),
+}
+
+impl From<Error> for StopReason {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::HyperClient(_) | Error::Hyper(_) => Self::VmmGone(err),
+            _ => Self::Failed(err),
+        }
+    }
+}
+
+/// A change of [`InstanceInfo::state`](models::InstanceInfo::state).
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Transition {
+    /// The previously observed state; `None` for the first observation.
+    pub from: Option<State>,
+    pub to: State,
+}
+
+struct Poller<A, T> {
+    api: A,
+    ticks: Interval,
+    last: Option<T>,
+    stopped: bool,
+}
+
+impl<A, T: Clone + PartialEq> Poller<A, T> {
+    fn new(api: A, period: Duration) -> Self {
+        let mut ticks = interval(period);
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        Self {
+            api,
+            ticks,
+            last: None,
+            stopped: false,
+        }
+    }
+
+    /// Wait for the next poll; `None` once the poller has stopped.
+    async fn tick(&mut self) -> Option<()> {
+        if self.stopped {
+            return None;
+        }
+        self.ticks.tick().await;
+        Some(())
+    }
+
+    /// Turn the result of a poll into an item, unless it is unchanged since the last one.
+    fn filter(&mut self, polled: Result<T, Error>) -> Option<Result<T, StopReason>> {
+        match polled {
+            Ok(value) if self.last.as_ref() == Some(&value) => None,
+            Ok(value) => {
+                self.last = Some(value.clone());
+                Some(Ok(value))
+            }
+            Err(err) => {
+                self.stopped = true;
+                Some(Err(err.into()))
+            }
+        }
+    }
+}
+
+/// Poll [`Api::describe_instance`] every `period`, yielding the instance information whenever
+/// it changes.
+pub fn instance_info<A: Api>(
+    api: A,
+    period: Duration,
+) -> impl Stream<Item = Result<models::InstanceInfo, StopReason>> + Send {
+    stream::unfold(Poller::new(api, period), |mut poller| async move {
+        loop {
+            poller.tick().await?;
+            let polled = poller.api.describe_instance().await;
+            if let Some(item) = poller.filter(polled) {
+                return Some((item, poller));
+            }
+        }
+    })
+}
+
+/// Poll [`Api::describe_instance`] every `period`, yielding the transitions of the instance's
+/// state (e.g. `NotStarted` → `Running` → `Paused`).
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn f() {
+/// use std::time::Duration;
+/// use futures_util::StreamExt;
+///
+/// let client = wick::Client::new("/tmp/fc.sock");
+/// let mut transitions = std::pin::pin!(wick::watch::state_transitions(
+///     client,
+///     Duration::from_millis(100),
+/// ));
+/// while let Some(transition) = transitions.next().await {
+///     match transition {
+///         Ok(transition) => println!("{:?} -> {:?}", transition.from, transition.to),
+///         Err(reason) => println!("stopped watching: {reason}"),
+///     }
+/// }
+/// # }
+/// ```
+pub fn state_transitions<A: Api>(
+    api: A,
+    period: Duration,
+) -> impl Stream<Item = Result<Transition, StopReason>> + Send {
+    let mut last = None;
+    instance_info(api, period).filter_map(move |info| {
+        let item = match info {
+            Ok(info) if last.as_ref() == Some(&info.state) => None,
+            Ok(info) => Some(Ok(Transition {
+                from: last.replace(info.state.clone()),
+                to: info.state,
+            })),
+            Err(reason) => Some(Err(reason)),
+        };
+        future::ready(item)
+    })
+}
+
+/// Poll [`Api::describe_balloon_stats`] every `period`, yielding the balloon statistics
+/// whenever they change.
+pub fn balloon_stats<A: Api>(
+    api: A,
+    period: Duration,
+) -> impl Stream<Item = Result<models::BalloonStats, StopReason>> + Send {
+    stream::unfold(Poller::new(api, period), |mut poller| async move {
+        loop {
+            poller.tick().await?;
+            let polled = poller.api.describe_balloon_stats().await;
+            if let Some(item) = poller.filter(polled) {
+                return Some((item, poller));
+            }
+        }
+    })
+}